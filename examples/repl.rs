@@ -185,6 +185,7 @@ mod example {
                         pid,
                         AttachOptions {
                             kill_on_exit: false,
+                            all_threads: false,
                         },
                     )?;
                     println!("{:?}", status);
@@ -193,8 +194,8 @@ mod example {
                 }
             }
             Some("detach") => {
-                context.remote()?.detach()?;
-                context.remote = None;
+                context.remote()?;
+                context.remote.take().unwrap().detach()?;
             }
             Some("kill") => println!("{:?}", context.remote()?.kill()?),
             Some("si") | Some("stepi") => println!("{:?}", context.remote()?.step()?),
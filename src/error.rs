@@ -0,0 +1,93 @@
+//! Errors returned by Headcrab.
+
+#[cfg(target_os = "linux")]
+use crate::target::{HardwareBreakpointError, SoftwareBreakpointError, WriteMemoryError};
+
+/// An error returned by the functions working with a target.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// A system call failed, e.g. `ptrace` or `waitpid`.
+    #[cfg(unix)]
+    #[error("ptrace error: {0}")]
+    Ptrace(#[from] nix::Error),
+    /// Information about the debuggee couldn't be read from `/proc`.
+    #[cfg(target_os = "linux")]
+    #[error("failed to read from /proc: {0}")]
+    Proc(#[from] procfs::ProcError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// A path or an argument passed to the debuggee contains a nul byte.
+    #[error(transparent)]
+    Nul(#[from] std::ffi::NulError),
+    #[cfg(target_os = "linux")]
+    #[error(transparent)]
+    HardwareBreakpoint(#[from] HardwareBreakpointError),
+    #[cfg(target_os = "linux")]
+    #[error(transparent)]
+    SoftwareBreakpoint(#[from] SoftwareBreakpointError),
+    #[cfg(target_os = "linux")]
+    #[error(transparent)]
+    WriteMemory(#[from] WriteMemoryError),
+    /// An object file couldn't be parsed.
+    #[error(transparent)]
+    Object(#[from] object::read::Error),
+    /// The DWARF debug info of an object file couldn't be read.
+    #[error(transparent)]
+    Dwarf(#[from] gimli::Error),
+    /// A compressed DWARF section couldn't be decompressed.
+    #[error("Failed to decompress {section}: {source}")]
+    Decompress {
+        section: &'static str,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// Capstone failed to set up or to decode instructions.
+    #[error("Disassembly failed: {0}")]
+    Disassembly(capstone::Error),
+    /// No symbol with the name was found in the objects searched.
+    #[error("Symbol {0} not found")]
+    SymbolNotFound(String),
+    /// A symbol is smaller than the number of bytes to watch.
+    #[error(
+        "Symbol {name} is {size} bytes large, which is smaller than the watched {watched} bytes"
+    )]
+    SymbolTooSmall {
+        name: String,
+        size: u64,
+        watched: usize,
+    },
+    /// A pointer of a chain followed with `LinuxTarget::follow_chain` couldn't be read.
+    #[cfg(target_os = "linux")]
+    #[error("Can't read pointer {level} of the chain at 0x{addr:x}: {source}")]
+    BrokenChain {
+        level: usize,
+        addr: usize,
+        source: Box<Error>,
+    },
+    /// The address isn't in any mapping of the debuggee.
+    #[error("Address 0x{0:x} is not mapped")]
+    NotMapped(usize),
+    /// The address has to be page aligned.
+    #[error("Address 0x{0:x} is not page aligned")]
+    NotPageAligned(usize),
+    /// No region was watched at the address with `LinuxTarget::soft_watch_region`.
+    #[error("No watched region at 0x{0:x}")]
+    NoWatchedRegion(usize),
+    /// A pattern to search for and its mask differ in length.
+    #[error("Pattern of {pattern} bytes with a mask of {mask} bytes")]
+    PatternMaskLength { pattern: usize, mask: usize },
+    /// No register has the name.
+    #[error("Unknown register: {0}")]
+    UnknownRegister(String),
+    /// The bytes at the address couldn't be decoded as an instruction.
+    #[error("Can't decode the instruction at 0x{0:x}")]
+    InvalidInstruction(usize),
+    /// The debuggee stopped in a way the operation didn't expect.
+    #[cfg(unix)]
+    #[error("Unexpected wait status: {0:?}")]
+    UnexpectedWaitStatus(nix::sys::wait::WaitStatus),
+    /// Any other failure, described by the message.
+    #[error("{0}")]
+    Other(String),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -1,5 +1,8 @@
 //! Headcrab, a modern Rust debugging library.
 
+mod error;
+pub use error::{Error, Result};
+
 /// Functions to work with target processes: reading & writing memory, process control functions, etc.
 pub mod target;
 
@@ -6,7 +6,7 @@ pub mod unwind;
 
 use gimli::read::{EvaluationResult, Reader as _};
 use object::{
-    read::{Object, ObjectSection, ObjectSegment},
+    read::{Object, ObjectSegment},
     SymbolKind,
 };
 use std::{
@@ -15,16 +15,27 @@ use std::{
     fs::File,
     path::Path,
 };
-pub use sym::Symbol;
+
+use crate::{Error, Result};
+pub use sym::{Symbol, SymbolLang};
 
 mod frame;
+mod line;
+mod process;
 mod relocate;
+mod section;
 mod source;
 mod sym;
+mod symbol_source;
+mod table;
 
 pub use frame::{Frame, FrameIter, Local, LocalValue};
+pub use line::LineTable;
+pub use process::ProcessSymbols;
 pub use relocate::RelocatedDwarf;
-pub use source::DisassemblySource;
+pub use source::{BranchKind, DisassemblySource, Instruction};
+pub use symbol_source::SymbolSource;
+pub use table::SymbolTable;
 
 macro_rules! dwarf_attr_or_continue {
     (str($dwarf:ident,$unit:ident) $entry:ident.$name:ident) => {
@@ -89,12 +100,12 @@ pub struct ParsedDwarf<'a> {
             gimli::Expression<Reader<'a>>,
         ),
     >,
-    symbols: Vec<Symbol<'a>>,
+    symbols: SymbolTable<'a>,
     symbol_names: HashMap<String, usize>,
 }
 
 impl<'a> ParsedDwarf<'a> {
-    pub fn new(bytes: &'a [u8]) -> Result<ParsedDwarf<'a>, Box<dyn std::error::Error>> {
+    pub fn new(bytes: &'a [u8]) -> Result<ParsedDwarf<'a>> {
         // This is completely inefficient and hackish code, but currently it serves the only
         // purpose of getting addresses of static variables.
         // TODO: this will be reworked in a more complete symbolication framework.
@@ -108,20 +119,12 @@ impl<'a> ParsedDwarf<'a> {
         };
 
         // This can be also processed in parallel.
-        let loader = |id: gimli::SectionId| -> Result<Reader, gimli::Error> {
-            match object.section_by_name(id.name()) {
-                Some(ref section) => {
-                    let data = section
-                        .uncompressed_data()
-                        .unwrap_or(Cow::Borrowed(&[][..]));
-                    let data = match data {
-                        Cow::Owned(vec) => RcCow::Owned(vec.into()),
-                        Cow::Borrowed(slice) => RcCow::Borrowed(slice),
-                    };
-                    Ok(gimli::EndianReader::new(data, endian))
-                }
-                None => Ok(gimli::EndianReader::new(RcCow::Borrowed(&[][..]), endian)),
-            }
+        let loader = |id: gimli::SectionId| -> Result<Reader> {
+            let data = match section::dwarf_section(&object, id)? {
+                Cow::Owned(vec) => RcCow::Owned(vec.into()),
+                Cow::Borrowed(slice) => RcCow::Borrowed(slice),
+            };
+            Ok(gimli::EndianReader::new(data, endian))
         };
         // we don't support supplementary object files for now
         let sup_loader = |_| Ok(gimli::EndianReader::new(RcCow::Borrowed(&[][..]), endian));
@@ -151,30 +154,10 @@ impl<'a> ParsedDwarf<'a> {
             }
         }
 
-        let mut symbols: Vec<_> = object
-            .symbols()
-            .chain(object.dynamic_symbols())
-            .map(|(_, sym)| sym)
-            .filter(|symbol| {
-                // Copied from `object::read::SymbolMap::filter`
-                match symbol.kind() {
-                    SymbolKind::Unknown | SymbolKind::Text | SymbolKind::Data => {}
-                    SymbolKind::Null
-                    | SymbolKind::Section
-                    | SymbolKind::File
-                    | SymbolKind::Label
-                    | SymbolKind::Tls => {
-                        return false;
-                    }
-                }
-                !symbol.is_undefined() && symbol.section() != object::SymbolSection::Common
-            })
-            .map(Into::into)
-            .collect();
-        symbols.sort_by_key(|sym: &Symbol| sym.address());
+        let symbols = SymbolTable::new(&object);
 
         let mut symbol_names = HashMap::new();
-        for sym in &symbols {
+        for sym in symbols.symbols() {
             if let Some(name) = sym.demangled_name() {
                 symbol_names.insert(name.to_string(), sym.address() as usize);
             }
@@ -194,10 +177,8 @@ impl<'a> ParsedDwarf<'a> {
     }
 
     pub fn get_address_symbol(&self, addr: usize) -> Option<Symbol<'a>> {
-        let index = match self
-            .symbols
-            .binary_search_by(|sym| sym.address().cmp(&(addr as u64)))
-        {
+        let symbols = self.symbols.symbols();
+        let index = match symbols.binary_search_by(|sym| sym.address().cmp(&(addr as u64))) {
             // Found an exact match.
             Ok(index) => index,
             // Address before the first symbol.
@@ -205,8 +186,8 @@ impl<'a> ParsedDwarf<'a> {
             // Address between two symbols. `index` is the index of the later of the two.
             Err(index) => index - 1,
         };
-        let symbol = &self.symbols[index];
-        if self.symbols.get(index + 1).map(|sym| sym.address()) <= Some(addr as u64) {
+        let symbol = &symbols[index];
+        if symbols.get(index + 1).map(|sym| sym.address()) <= Some(addr as u64) {
             return None;
         }
         Some(symbol.clone())
@@ -226,7 +207,7 @@ impl<'a> ParsedDwarf<'a> {
         */
     }
 
-    pub fn get_var_address(&self, name: &str) -> Result<Option<usize>, Box<dyn std::error::Error>> {
+    pub fn get_var_address(&self, name: &str) -> Result<Option<usize>> {
         if let Some((unit_header, expr)) = self.vars.get(name) {
             let unit = self.addr2line.dwarf().unit(unit_header.clone())?;
             let mut eval = expr.clone().evaluation(unit.encoding());
@@ -240,10 +221,7 @@ impl<'a> ParsedDwarf<'a> {
         Ok(None)
     }
 
-    pub fn get_addr_frames(
-        &'a self,
-        addr: usize,
-    ) -> Result<FrameIter<'a>, Box<dyn std::error::Error>> {
+    pub fn get_addr_frames(&'a self, addr: usize) -> Result<FrameIter<'a>> {
         Ok(FrameIter {
             dwarf: self.addr2line.dwarf(),
             unit: self.addr2line.find_dwarf_unit(addr as u64),
@@ -265,9 +243,7 @@ mod inner {
         // todo: impl loader struct instead of taking 'path' as an argument.
         // It will be required to e.g. load coredumps, or external debug info, or to
         // communicate with rustc/lang servers.
-        pub fn new<P: AsRef<std::path::Path>>(
-            path: P,
-        ) -> Result<Dwarf, Box<dyn std::error::Error>> {
+        pub fn new<P: AsRef<std::path::Path>>(path: P) -> Result<Dwarf> {
             // Load ELF/Mach-O object file
             let file = File::open(path)?;
 
@@ -336,7 +312,7 @@ impl Dwarf {
         self.rent(|parsed| Some(parsed.get_address_symbol(addr)?.kind()))
     }
 
-    pub fn get_var_address(&self, name: &str) -> Result<Option<usize>, Box<dyn std::error::Error>> {
+    pub fn get_var_address(&self, name: &str) -> Result<Option<usize>> {
         self.rent(|parsed| parsed.get_var_address(name))
     }
 
@@ -1,6 +1,7 @@
 use gimli::{DebuggingInformationEntry, Dwarf, Unit};
 
 use super::Reader;
+use crate::{Error, Result};
 
 pub fn in_range(
     dwarf: &Dwarf<Reader>,
@@ -78,7 +79,7 @@ pub fn evaluate_expression<'a>(
     expr: gimli::Expression<Reader<'a>>,
     frame_base: Option<u64>,
     get_reg: impl Fn(gimli::Register, gimli::ValueType) -> gimli::Value,
-) -> Result<Vec<gimli::Piece<Reader<'a>>>, Box<dyn std::error::Error>> {
+) -> Result<Vec<gimli::Piece<Reader<'a>>>> {
     let mut eval = expr.evaluation(unit.encoding());
     let mut res = eval.evaluate()?;
     loop {
@@ -88,7 +89,7 @@ pub fn evaluate_expression<'a>(
             }
             gimli::EvaluationResult::RequiresFrameBase => {
                 res = eval.resume_with_frame_base(
-                    frame_base.ok_or_else(|| "No frame base".to_owned())?,
+                    frame_base.ok_or_else(|| Error::Other("No frame base".to_owned()))?,
                 )?;
             }
             gimli::EvaluationResult::RequiresRegister {
@@ -100,7 +101,7 @@ pub fn evaluate_expression<'a>(
                 } else {
                     let base_type_die = unit.entry(base_type)?;
                     gimli::ValueType::from_entry(&base_type_die)?
-                        .ok_or_else(|| "not a base type".to_owned())?
+                        .ok_or_else(|| Error::Other("not a base type".to_owned()))?
                 };
 
                 let val = get_reg(register, ty);
@@ -0,0 +1,132 @@
+//! A table mapping addresses to source lines, built from `.debug_line`.
+
+use object::Object;
+use std::borrow::Cow;
+use std::path::PathBuf;
+
+use crate::Result;
+
+/// The rows of all line programs of an object file.
+#[derive(Clone, Debug)]
+pub struct LineTable {
+    files: Vec<PathBuf>,
+    /// Sorted by address. Rows with `line == None` end a sequence of instructions.
+    rows: Vec<LineRow>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct LineRow {
+    address: u64,
+    file: usize,
+    line: Option<u32>,
+}
+
+impl LineTable {
+    /// Parses the line programs of every compilation unit in `object`.
+    pub fn new(object: &object::File) -> Result<Self> {
+        let endian = if object.is_little_endian() {
+            gimli::RunTimeEndian::Little
+        } else {
+            gimli::RunTimeEndian::Big
+        };
+
+        let loader = |id: gimli::SectionId| super::section::dwarf_section(object, id);
+        // we don't support supplementary object files for now
+        let sup_loader = |_| Ok(Cow::Borrowed(&[][..]));
+        let dwarf_cow = gimli::Dwarf::load(loader, sup_loader)?;
+        let dwarf = dwarf_cow.borrow(|section| gimli::EndianSlice::new(section, endian));
+
+        let mut files = Vec::new();
+        let mut rows = Vec::new();
+        let mut units = dwarf.units();
+        while let Some(header) = units.next()? {
+            let unit = dwarf.unit(header)?;
+            let program = match unit.line_program.clone() {
+                Some(program) => program,
+                None => continue,
+            };
+
+            // Indices into `files` of the file entries of this line program.
+            let mut unit_files = Vec::new();
+            let mut program_rows = program.rows();
+            while let Some((header, row)) = program_rows.next_row()? {
+                if row.end_sequence() {
+                    rows.push(LineRow {
+                        address: row.address(),
+                        file: 0,
+                        line: None,
+                    });
+                    continue;
+                }
+
+                let file_index = row.file_index() as usize;
+                if unit_files.len() <= file_index {
+                    unit_files.resize(file_index + 1, None);
+                }
+                let file = match unit_files[file_index] {
+                    Some(file) => file,
+                    None => {
+                        let path = match row.file(header) {
+                            Some(file) => file_path(&dwarf, &unit, header, file)?,
+                            None => PathBuf::new(),
+                        };
+                        files.push(path);
+                        unit_files[file_index] = Some(files.len() - 1);
+                        files.len() - 1
+                    }
+                };
+
+                rows.push(LineRow {
+                    address: row.address(),
+                    file,
+                    line: Some(row.line().unwrap_or(0) as u32),
+                });
+            }
+        }
+
+        // A sequence may start where another one ends, so the end marker has to come first.
+        rows.sort_by_key(|row| (row.address, row.line.is_some()));
+
+        Ok(LineTable { files, rows })
+    }
+
+    /// Returns the source file and line of the instruction at `addr`.
+    ///
+    /// This is the row with the greatest address not exceeding `addr`, unless a sequence of
+    /// instructions ends in between.
+    pub fn location(&self, addr: u64) -> Option<(PathBuf, u32)> {
+        let index = match self.rows.binary_search_by(|row| row.address.cmp(&addr)) {
+            Ok(mut index) => {
+                while self.rows.get(index + 1).map(|row| row.address) == Some(addr) {
+                    index += 1;
+                }
+                index
+            }
+            Err(0) => return None,
+            Err(index) => index - 1,
+        };
+        let row = self.rows[index];
+        Some((self.files[row.file].clone(), row.line?))
+    }
+}
+
+fn file_path<R: gimli::Reader>(
+    dwarf: &gimli::Dwarf<R>,
+    unit: &gimli::Unit<R>,
+    header: &gimli::LineProgramHeader<R>,
+    file: &gimli::FileEntry<R>,
+) -> Result<PathBuf> {
+    let mut path = PathBuf::new();
+    if let Some(comp_dir) = &unit.comp_dir {
+        path.push(&*comp_dir.to_string_lossy()?);
+    }
+    if let Some(directory) = file.directory(header) {
+        path.push(&*dwarf.attr_string(unit, directory)?.to_string_lossy()?);
+    }
+    path.push(
+        &*dwarf
+            .attr_string(unit, file.path_name())?
+            .to_string_lossy()?,
+    );
+    Ok(path)
+}
@@ -0,0 +1,134 @@
+//! Symbols of all objects loaded into a process.
+
+use super::{Symbol, SymbolSource};
+use crate::{target::LoadedObject, Result};
+use std::{
+    collections::HashMap,
+    mem,
+    path::{Path, PathBuf},
+};
+
+struct Module {
+    path: PathBuf,
+    /// Difference between the runtime and the link-time addresses.
+    bias: u64,
+    symbols: SymbolSource,
+    /// Added with `ProcessSymbols::add_image` instead of loaded from `path`.
+    in_memory: bool,
+}
+
+impl Module {
+    fn contains(&self, addr: u64) -> bool {
+        let link_addr = addr.wrapping_sub(self.bias);
+        self.symbols.link_start <= link_addr && link_addr < self.symbols.link_end
+    }
+}
+
+/// The symbols of the executable and all shared libraries loaded into a process, at the addresses
+/// they are loaded at.
+///
+/// Object files are parsed once when they are loaded, `update` only parses newly loaded ones.
+/// Objects that can't be parsed, e.g. mapped files that aren't ELF objects, have no symbols and
+/// are listed by `skipped`.
+#[derive(Default)]
+pub struct ProcessSymbols {
+    modules: Vec<Module>,
+    skipped: Vec<(PathBuf, crate::Error)>,
+}
+
+impl ProcessSymbols {
+    /// Loads the symbols of `objects`, as returned by `LinuxTarget::loaded_objects`.
+    pub fn new(objects: &[LoadedObject]) -> Result<ProcessSymbols> {
+        let mut symbols = ProcessSymbols::default();
+        symbols.update(objects)?;
+        Ok(symbols)
+    }
+
+    /// Makes the symbols match the currently loaded `objects`, e.g. after the process loaded or
+    /// unloaded a library with `dlopen` or `dlclose`.
+    ///
+    /// Objects that are still loaded aren't parsed again. Objects added with `add_image` are
+    /// kept. Objects that fail to parse are skipped, see `skipped`, and tried again by the next
+    /// update.
+    pub fn update(&mut self, objects: &[LoadedObject]) -> Result<()> {
+        let (in_memory, modules): (Vec<_>, _) = mem::take(&mut self.modules)
+            .into_iter()
+            .partition(|module| module.in_memory);
+        let mut cached: HashMap<PathBuf, SymbolSource> = modules
+            .into_iter()
+            .map(|module: Module| (module.path, module.symbols))
+            .collect();
+
+        let mut modules = Vec::with_capacity(objects.len());
+        let mut skipped = Vec::new();
+        for object in objects {
+            let symbols = match cached.remove(&object.path) {
+                Some(symbols) => symbols,
+                None => match SymbolSource::open(&object.path) {
+                    Ok(symbols) => symbols,
+                    Err(err) => {
+                        skipped.push((object.path.clone(), err));
+                        continue;
+                    }
+                },
+            };
+            modules.push(Module {
+                path: object.path.clone(),
+                bias: object.base.wrapping_sub(symbols.link_start),
+                symbols,
+                in_memory: false,
+            });
+        }
+        modules.extend(in_memory);
+        self.modules = modules;
+        self.skipped = skipped;
+        Ok(())
+    }
+
+    /// Returns the objects of the last `new` or `update` that couldn't be parsed, together with
+    /// the reason.
+    pub fn skipped(&self) -> &[(PathBuf, crate::Error)] {
+        &self.skipped
+    }
+
+    /// Adds the symbols of `object`, parsed from its ELF `image` instead of a file, e.g. the
+    /// vDSO as returned by `LinuxTarget::vdso` and `LinuxTarget::read_vdso_image`.
+    pub fn add_image(&mut self, object: &LoadedObject, image: Vec<u8>) -> Result<()> {
+        let symbols = SymbolSource::from_image(image)?;
+        self.modules.push(Module {
+            path: object.path.clone(),
+            bias: object.base.wrapping_sub(symbols.link_start),
+            symbols,
+            in_memory: true,
+        });
+        Ok(())
+    }
+
+    /// Returns the path of the object containing `addr`, the symbol containing `addr` and the
+    /// offset of `addr` within the symbol.
+    pub fn resolve(&self, addr: u64) -> Option<(&Path, &Symbol<'_>, u64)> {
+        let module = self.modules.iter().find(|module| module.contains(addr))?;
+        let (symbol, offset) = module
+            .symbols
+            .table()
+            .symbol_for_addr(addr.wrapping_sub(module.bias))?;
+        Some((&module.path, symbol, offset))
+    }
+
+    /// Returns the runtime address of the symbol called `name`, see `SymbolTable::address_of`.
+    ///
+    /// Objects are searched in the order they are mapped, so the executable usually comes
+    /// first, followed by those added with `add_image`.
+    pub fn address_of(&self, name: &str) -> Option<u64> {
+        self.symbol_by_name(name).map(|(addr, _)| addr)
+    }
+
+    /// Returns the runtime address of the symbol called `name` together with the symbol, see
+    /// `address_of`.
+    pub fn symbol_by_name(&self, name: &str) -> Option<(u64, &Symbol<'_>)> {
+        self.modules.iter().find_map(|module| {
+            let symbol = module.symbols.table().symbol_by_name(name)?;
+            Some((symbol.address().wrapping_add(module.bias), symbol))
+        })
+    }
+}
@@ -0,0 +1,85 @@
+//! Reading DWARF sections, which may be compressed.
+
+use crate::{Error, Result};
+use object::{elf, Object, ObjectSection, SectionFlags};
+use std::borrow::Cow;
+
+/// `ch_type` of sections compressed with zstd, not known to `object` yet.
+const ELFCOMPRESS_ZSTD: u32 = 2;
+
+/// Returns the contents of the section `id` of `object`, or nothing if there is no such section.
+///
+/// Sections compressed with zlib, either with `SHF_COMPRESSED` or as a GNU `.zdebug_` section,
+/// are decompressed. Sections compressed with zstd are only decompressed with the `zstd`
+/// feature, an error is returned otherwise rather than leaving the section empty.
+pub(super) fn dwarf_section<'data>(
+    object: &object::File<'data>,
+    id: gimli::SectionId,
+) -> Result<Cow<'data, [u8]>> {
+    let section = match object.section_by_name(id.name()) {
+        Some(section) => section,
+        None => return Ok(Cow::Borrowed(&[][..])),
+    };
+
+    if let SectionFlags::Elf { sh_flags } = section.flags() {
+        if sh_flags & u64::from(elf::SHF_COMPRESSED) != 0 {
+            let data = section.data()?;
+            let (ch_type, ch_size, header_size) =
+                compression_header(object, data).ok_or_else(|| {
+                    Error::Other(format!("Truncated compression header in {}", id.name()))
+                })?;
+            if ch_type == ELFCOMPRESS_ZSTD {
+                return decompress_zstd(&data[header_size..], ch_size as usize)
+                    .map(Cow::Owned)
+                    .map_err(|source| Error::Decompress {
+                        section: id.name(),
+                        source,
+                    });
+            }
+        }
+    }
+
+    section
+        .uncompressed_data()
+        .map_err(|err| Error::Decompress {
+            section: id.name(),
+            source: err.into(),
+        })
+}
+
+/// Reads `ch_type` and `ch_size` of the `Elf32_Chdr` or `Elf64_Chdr` at the start of `data`,
+/// together with the size of the header.
+fn compression_header(object: &object::File, data: &[u8]) -> Option<(u32, u64, usize)> {
+    let little_endian = object.is_little_endian();
+    let read = |offset: usize, len: usize| {
+        let bytes = data.get(offset..offset + len)?;
+        let fold = |value, &byte| value << 8 | u64::from(byte);
+        Some(if little_endian {
+            bytes.iter().rev().fold(0, fold)
+        } else {
+            bytes.iter().fold(0, fold)
+        })
+    };
+
+    if object.is_64() {
+        Some((read(0, 4)? as u32, read(8, 8)?, 24))
+    } else {
+        Some((read(0, 4)? as u32, read(4, 4)?, 12))
+    }
+}
+
+#[cfg(feature = "zstd")]
+fn decompress_zstd(
+    data: &[u8],
+    size: usize,
+) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+    Ok(zstd::bulk::decompress(data, size)?)
+}
+
+#[cfg(not(feature = "zstd"))]
+fn decompress_zstd(
+    _data: &[u8],
+    _size: usize,
+) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+    Err("zstd compressed sections require the `zstd` feature".into())
+}
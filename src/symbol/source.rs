@@ -1,7 +1,54 @@
+use crate::{Error, Result};
 use capstone::Capstone;
 
 pub struct DisassemblySource(Capstone);
 
+/// A single decoded machine instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    /// Address of the first byte of the instruction.
+    pub address: usize,
+    /// Length of the instruction in bytes.
+    pub len: usize,
+    /// The instruction in AT&T syntax, like `movq $0, %rax`.
+    pub text: String,
+    /// The raw bytes the instruction was decoded from.
+    pub bytes: Vec<u8>,
+}
+
+/// The kind of a control-flow instruction, see `Instruction::branch_kind`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BranchKind {
+    Call,
+    Return,
+    /// An unconditional jump.
+    Jump,
+    /// A jump that may or may not be taken, including `loop` and `jrcxz`.
+    ConditionalJump,
+}
+
+impl Instruction {
+    /// Returns the kind of control-flow instruction this is, or `None` if it always continues
+    /// with the next instruction. Interrupts and syscalls aren't considered branches.
+    pub fn branch_kind(&self) -> Option<BranchKind> {
+        let mnemonic = self
+            .text
+            .split_whitespace()
+            .find(|word| !matches!(*word, "bnd" | "notrack" | "rep" | "repz" | "repe"))?;
+        if mnemonic.starts_with("call") || mnemonic.starts_with("lcall") {
+            Some(BranchKind::Call)
+        } else if mnemonic.starts_with("ret") || mnemonic.starts_with("lret") {
+            Some(BranchKind::Return)
+        } else if mnemonic.starts_with("jmp") || mnemonic.starts_with("ljmp") {
+            Some(BranchKind::Jump)
+        } else if mnemonic.starts_with('j') || mnemonic.starts_with("loop") {
+            Some(BranchKind::ConditionalJump)
+        } else {
+            None
+        }
+    }
+}
+
 impl DisassemblySource {
     pub fn new() -> Self {
         use capstone::arch::{BuildsCapstone, BuildsCapstoneSyntax};
@@ -17,12 +64,7 @@ impl DisassemblySource {
         DisassemblySource(cs)
     }
 
-    pub fn source_snippet(
-        &self,
-        bytes: &[u8],
-        addr: u64,
-        show_address: bool,
-    ) -> Result<String, Box<dyn std::error::Error>> {
+    pub fn source_snippet(&self, bytes: &[u8], addr: u64, show_address: bool) -> Result<String> {
         use std::fmt::Write;
 
         let mut fmt = String::new();
@@ -43,24 +85,49 @@ impl DisassemblySource {
 
         Ok(fmt)
     }
+
+    /// Decodes up to `count` instructions from `bytes`, which start at `addr`.
+    ///
+    /// Decoding stops early at the end of `bytes` or at the first invalid instruction.
+    pub fn disassemble(&self, bytes: &[u8], addr: usize, count: usize) -> Result<Vec<Instruction>> {
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+
+        let insns = self
+            .0
+            .disasm_count(bytes, addr as u64, count)
+            .map_err(Error::Disassembly)?;
+        Ok(insns
+            .iter()
+            .map(|insn| {
+                let mut text = insn.mnemonic().unwrap_or_default().to_string();
+                if let Some(op_str) = insn.op_str().filter(|op_str| !op_str.is_empty()) {
+                    text.push(' ');
+                    text.push_str(op_str);
+                }
+                Instruction {
+                    address: insn.address() as usize,
+                    len: insn.bytes().len(),
+                    text,
+                    bytes: insn.bytes().to_vec(),
+                }
+            })
+            .collect())
+    }
 }
 
 impl super::Dwarf {
-    pub fn source_location(
-        &self,
-        addr: usize,
-    ) -> Result<(String, u64, u64), Box<dyn std::error::Error>> {
+    pub fn source_location(&self, addr: usize) -> Result<(String, u64, u64)> {
         self.rent(|parsed| {
             let addr2line: &addr2line::Context<_> = &parsed.addr2line;
-            println!("{:08x}", addr);
-            assert!(addr2line.find_dwarf_unit(addr as u64).is_some());
             let location = addr2line
                 .find_location(addr as u64)?
-                .ok_or_else(|| "source location not found".to_string())?;
+                .ok_or_else(|| Error::Other("source location not found".to_string()))?;
             Ok((
                 location
                     .file
-                    .ok_or_else(|| "Unknown file".to_string())?
+                    .ok_or_else(|| Error::Other("Unknown file".to_string()))?
                     .to_string(),
                 location.line.unwrap_or(0) as u64,
                 location.column.unwrap_or(0) as u64,
@@ -68,13 +135,13 @@ impl super::Dwarf {
         })
     }
 
-    pub fn source_snippet(&self, addr: usize) -> Result<String, Box<dyn std::error::Error>> {
+    pub fn source_snippet(&self, addr: usize) -> Result<String> {
         let (file, line, _column) = self.source_location(addr)?;
         let file = std::fs::read_to_string(file)?;
         Ok(file
             .lines()
             .nth(line as usize)
-            .ok_or_else(|| "Line not found".to_string())?
+            .ok_or_else(|| Error::Other("Line not found".to_string()))?
             .to_string())
     }
 }
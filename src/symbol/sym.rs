@@ -1,34 +1,82 @@
 //! Implementation of a symbol table entry that will automatically
 //! demangle rustc names.
 
-use addr2line::demangle_auto;
-use std::borrow::Cow;
+use addr2line::demangle;
 use std::ops::{Deref, DerefMut};
 
+/// The mangling scheme of a symbol name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SymbolLang {
+    /// A Rust name.
+    Rust,
+    /// An Itanium C++ ABI name.
+    Cpp,
+    /// A name that isn't mangled, or couldn't be demangled.
+    Raw,
+}
+
 /// A symbol table entry.
 #[derive(Clone, Debug)]
 pub struct Symbol<'data> {
     demangled_name: Option<String>,
+    lang: SymbolLang,
+    version: Option<&'data str>,
     symbol: object::Symbol<'data>,
 }
 
 impl<'data> Symbol<'data> {
     /// Returns the demangled name if this symbol has a name.
+    ///
+    /// The name is demangled once when the symbol is created and owned by it, so it's only
+    /// borrowed for as long as the symbol is.
     #[inline]
-    pub fn demangled_name(&'data self) -> Option<&'data str> {
-        // TODO: Avoid this allocation in every call. (lifetime errors)
+    pub fn demangled_name(&self) -> Option<&str> {
         self.demangled_name.as_deref()
     }
+
+    /// Returns the demangled name if this symbol has a name, together with the mangling scheme
+    /// it was demangled with.
+    #[inline]
+    pub fn demangled_name_with_lang(&self) -> Option<(&str, SymbolLang)> {
+        Some((self.demangled_name.as_deref()?, self.lang))
+    }
+
+    /// Returns the GNU symbol version of a dynamic symbol, e.g. `GLIBC_2.14` for
+    /// `memcpy@@GLIBC_2.14`, if it has one.
+    ///
+    /// Shared libraries can define several versions of a symbol with the same name, the version
+    /// tells them apart.
+    #[inline]
+    pub fn version(&self) -> Option<&'data str> {
+        self.version
+    }
+
+    pub(super) fn with_version(mut self, version: Option<&'data str>) -> Self {
+        self.version = version;
+        self
+    }
 }
 
 impl<'data> From<object::Symbol<'data>> for Symbol<'data> {
     fn from(symbol: object::Symbol<'data>) -> Self {
-        let demangled_name = symbol
-            .name()
-            .map(|name| demangle_auto(Cow::Borrowed(name), None).to_string());
+        // Rust names are tried first, as legacy ones are valid C++ names too.
+        let (demangled_name, lang) = match symbol.name() {
+            Some(name) => {
+                if let Some(demangled) = demangle(name, gimli::DW_LANG_Rust) {
+                    (Some(demangled), SymbolLang::Rust)
+                } else if let Some(demangled) = demangle(name, gimli::DW_LANG_C_plus_plus) {
+                    (Some(demangled), SymbolLang::Cpp)
+                } else {
+                    (Some(name.to_string()), SymbolLang::Raw)
+                }
+            }
+            None => (None, SymbolLang::Raw),
+        };
         Symbol {
             symbol,
             demangled_name,
+            lang,
+            version: None,
         }
     }
 }
@@ -46,3 +94,71 @@ impl DerefMut for Symbol<'_> {
         &mut self.symbol
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Symbol, SymbolLang};
+    use object::write;
+    use object::{Architecture, BinaryFormat, Endianness, Object, SectionKind, SymbolScope};
+
+    fn demangle(mangled: &str) -> Option<(String, SymbolLang)> {
+        let mut obj =
+            write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        let text = obj.add_section(vec![], b".text".to_vec(), SectionKind::Text);
+        obj.append_section_data(text, &[0x90; 0x10], 16);
+        obj.add_symbol(write::Symbol {
+            name: mangled.as_bytes().to_vec(),
+            value: 0,
+            size: 0x10,
+            kind: object::SymbolKind::Text,
+            scope: SymbolScope::Linkage,
+            weak: false,
+            section: write::SymbolSection::Section(text),
+            flags: object::SymbolFlags::None,
+        });
+        let bytes = obj.write().unwrap();
+        let object = object::File::parse(&bytes).unwrap();
+
+        let (_, symbol) = object
+            .symbols()
+            .find(|(_, sym)| sym.name() == Some(mangled))
+            .unwrap();
+        // The demangled name only borrows the symbol, not the object data.
+        let symbol = Symbol::from(symbol);
+        assert_eq!(
+            symbol.demangled_name(),
+            symbol.demangled_name_with_lang().map(|(name, _)| name)
+        );
+        symbol
+            .demangled_name_with_lang()
+            .map(|(name, lang)| (name.to_string(), lang))
+    }
+
+    #[test]
+    fn demangled_name() {
+        assert_eq!(
+            demangle("_ZN4core3ptr13drop_in_place17h0123456789abcdefE"),
+            Some(("core::ptr::drop_in_place".to_string(), SymbolLang::Rust))
+        );
+        assert_eq!(
+            demangle("_ZN66_$LT$alloc..vec..Vec$LT$T$GT$$u20$as$u20$core..ops..drop..Drop$GT$4drop17h0123456789abcdefE"),
+            Some(("<alloc::vec::Vec<T> as core::ops::drop::Drop>::drop".to_string(), SymbolLang::Rust))
+        );
+        assert_eq!(
+            demangle("main"),
+            Some(("main".to_string(), SymbolLang::Raw))
+        );
+    }
+
+    #[test]
+    fn demangled_cpp_name() {
+        assert_eq!(
+            demangle("_ZN3foo3barEv"),
+            Some(("foo::bar()".to_string(), SymbolLang::Cpp))
+        );
+        assert_eq!(
+            demangle("_ZNK3foo3Baz6methodEi"),
+            Some(("foo::Baz::method(int) const".to_string(), SymbolLang::Cpp))
+        );
+    }
+}
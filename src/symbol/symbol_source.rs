@@ -0,0 +1,260 @@
+//! Symbol tables of object files together with the data they borrow from.
+
+use super::{Symbol, SymbolTable};
+use crate::Result;
+use object::{Object, ObjectSegment};
+use std::{
+    fs::File,
+    mem::{self, ManuallyDrop},
+    path::{Path, PathBuf},
+};
+
+/// The directory separate debug files are installed in.
+const DEBUG_DIR: &str = "/usr/lib/debug";
+
+/// The symbol table of an object file, together with the mapped file or the image it borrows
+/// from, so the symbols can be kept around without the `object::File` they were parsed from.
+pub struct SymbolSource {
+    /// The mapped file or the image read from memory.
+    _data: Box<dyn AsRef<[u8]>>,
+    /// The mapped separate debug file, if one was found.
+    _debug_data: Option<memmap::Mmap>,
+    debug_file: Option<PathBuf>,
+    table: ManuallyDrop<SymbolTable<'static>>,
+    /// Start of the link-time address range covered by the segments.
+    pub(super) link_start: u64,
+    /// End of the link-time address range covered by the segments.
+    pub(super) link_end: u64,
+}
+
+impl SymbolSource {
+    /// Maps the object file at `path` and parses its symbols.
+    ///
+    /// If the object file refers to a separate debug file, as stripped binaries of many
+    /// distributions do, its symbols are added as well. The debug file is looked up by the build
+    /// id in `/usr/lib/debug/.build-id` first, then by the name in the `.gnu_debuglink` section
+    /// in the directory of the object file, its `.debug` subdirectory and the same directory
+    /// under `/usr/lib/debug`. Debug files whose build id or CRC doesn't match are skipped.
+    pub fn open(path: &Path) -> Result<SymbolSource> {
+        let mmap = map_file(path)?;
+        let debug = find_debug_file(path, &object::File::parse(&mmap)?, Path::new(DEBUG_DIR));
+        let mut source = SymbolSource::parse(Box::new(mmap))?;
+
+        if let Some((debug_file, debug_data)) = debug {
+            let debug_object = object::File::parse(&debug_data)?;
+            let debug_table = SymbolTable::new(&debug_object);
+            // Safety: See `parse`, the mapped debug file is kept alongside `data`.
+            let debug_table =
+                unsafe { mem::transmute::<SymbolTable<'_>, SymbolTable<'static>>(debug_table) };
+            source.table.merge(debug_table);
+            source._debug_data = Some(debug_data);
+            source.debug_file = Some(debug_file);
+        }
+        Ok(source)
+    }
+
+    /// Parses the symbols of the object file `image`, e.g. read from the debuggee's memory.
+    ///
+    /// Separate debug files aren't looked up, as there is no path to search relative to.
+    pub fn from_image(image: Vec<u8>) -> Result<SymbolSource> {
+        SymbolSource::parse(Box::new(image))
+    }
+
+    fn parse(data: Box<dyn AsRef<[u8]>>) -> Result<SymbolSource> {
+        let object = object::File::parse((*data).as_ref())?;
+
+        let (link_start, link_end) = object
+            .segments()
+            .map(|segment| {
+                (
+                    segment.address().saturating_sub(segment.file_range().0),
+                    segment.address() + segment.size(),
+                )
+            })
+            .fold((u64::MAX, 0), |(start, end), (seg_start, seg_end)| {
+                (start.min(seg_start), end.max(seg_end))
+            });
+        let table = ManuallyDrop::new(SymbolTable::new(&object));
+
+        // Safety: `table` doesn't outlive `data`, from which it borrows, because no reference to
+        // `table` can be obtained without the lifetime being shortened to be smaller than the
+        // `SymbolSource` that contains both `table` and the `data` it borrows from. Moving the
+        // box doesn't move the bytes.
+        let table = unsafe {
+            mem::transmute::<ManuallyDrop<SymbolTable<'_>>, ManuallyDrop<SymbolTable<'static>>>(
+                table,
+            )
+        };
+
+        Ok(SymbolSource {
+            _data: data,
+            _debug_data: None,
+            debug_file: None,
+            table,
+            link_start,
+            link_end,
+        })
+    }
+
+    /// Returns the path of the separate debug file whose symbols were added, if any.
+    pub fn debug_file(&self) -> Option<&Path> {
+        self.debug_file.as_deref()
+    }
+
+    /// Returns the symbol table, borrowing from `self`.
+    pub fn table(&self) -> &SymbolTable<'_> {
+        &self.table
+    }
+
+    /// Returns all symbols sorted by address, see `SymbolTable::symbols`.
+    pub fn symbols(&self) -> &[Symbol<'_>] {
+        self.table().symbols()
+    }
+}
+
+impl Drop for SymbolSource {
+    fn drop(&mut self) {
+        unsafe {
+            ManuallyDrop::drop(&mut self.table);
+        }
+    }
+}
+
+fn map_file(path: &Path) -> std::io::Result<memmap::Mmap> {
+    let file = File::open(path)?;
+    // Safety: Not really, this assumes that the backing file will not be truncated or written to
+    // while it is used by us.
+    unsafe { memmap::Mmap::map(&file) }
+}
+
+/// Finds and maps the separate debug file of `object` at `path`, see `SymbolSource::open`.
+/// `debug_dir` takes the place of `/usr/lib/debug`.
+fn find_debug_file(
+    path: &Path,
+    object: &object::File,
+    debug_dir: &Path,
+) -> Option<(PathBuf, memmap::Mmap)> {
+    let build_id = object.build_id().ok().flatten();
+    if let Some(build_id) = build_id.filter(|build_id| build_id.len() >= 2) {
+        let hex: String = build_id
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        let debug_file = debug_dir
+            .join(".build-id")
+            .join(&hex[..2])
+            .join(format!("{}.debug", &hex[2..]));
+        let found = map_file(&debug_file).ok().filter(|mmap| {
+            object::File::parse(mmap)
+                .ok()
+                .and_then(|debug_object| debug_object.build_id().ok().flatten())
+                == Some(build_id)
+        });
+        if let Some(mmap) = found {
+            return Some((debug_file, mmap));
+        }
+    }
+
+    let (name, crc) = object.gnu_debuglink().ok().flatten()?;
+    let name = Path::new(std::str::from_utf8(name).ok()?);
+    let dir = std::fs::canonicalize(path).ok()?.parent()?.to_path_buf();
+    let candidates = [
+        dir.join(name),
+        dir.join(".debug").join(name),
+        debug_dir
+            .join(dir.strip_prefix("/").unwrap_or(&dir))
+            .join(name),
+    ];
+    candidates.iter().find_map(|debug_file| {
+        map_file(debug_file)
+            .ok()
+            .filter(|mmap| crc32(mmap) == crc)
+            .map(|mmap| (debug_file.clone(), mmap))
+    })
+}
+
+/// The CRC-32 used by `.gnu_debuglink`, the same as the one of zlib.
+fn crc32(data: &[u8]) -> u32 {
+    let mut table = [0u32; 256];
+    for (index, entry) in table.iter_mut().enumerate() {
+        let mut crc = index as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                0xedb8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+        }
+        *entry = crc;
+    }
+    !data.iter().fold(!0, |crc, &byte| {
+        table[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{crc32, find_debug_file, SymbolSource};
+    use object::Object;
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn open_own_executable() {
+        let path = std::env::current_exe().unwrap();
+        let source = SymbolSource::open(&path).unwrap();
+        let main = source
+            .table()
+            .symbol_by_name("main")
+            .expect("no main symbol");
+        assert!(main.address() != 0);
+        assert!(source
+            .symbols()
+            .iter()
+            .any(|symbol| symbol.name() == Some("main")));
+
+        let image = std::fs::read(&path).unwrap();
+        let from_image = SymbolSource::from_image(image).unwrap();
+        assert_eq!(from_image.table().address_of("main"), Some(main.address()));
+        assert_eq!(source.symbols().len(), from_image.symbols().len());
+    }
+
+    #[test]
+    fn find_debug_file_by_build_id() {
+        let path = std::env::current_exe().unwrap();
+        let data = std::fs::read(&path).unwrap();
+        let object = object::File::parse(&data).unwrap();
+        let build_id = object
+            .build_id()
+            .unwrap()
+            .expect("test executable without build id");
+        let hex: String = build_id
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+
+        let dir = std::env::temp_dir().join(format!("headcrab-build-id.{}", std::process::id()));
+        let debug_file = dir
+            .join(".build-id")
+            .join(&hex[..2])
+            .join(format!("{}.debug", &hex[2..]));
+        std::fs::create_dir_all(debug_file.parent().unwrap()).unwrap();
+        std::fs::write(&debug_file, &data).unwrap();
+        let found = find_debug_file(&path, &object, &dir).map(|(found, _)| found);
+
+        // A debug file with another build id is skipped.
+        let mut other_data = data.clone();
+        let offset = build_id.as_ptr() as usize - data.as_ptr() as usize;
+        other_data[offset] ^= 0xff;
+        std::fs::write(&debug_file, &other_data).unwrap();
+        let other_found = find_debug_file(&path, &object, &dir).map(|(found, _)| found);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(found, Some(debug_file));
+        assert_eq!(other_found, None);
+    }
+}
@@ -0,0 +1,517 @@
+//! A symbol table to map addresses to symbols.
+
+use super::Symbol;
+use object::{Object, ObjectSection, SymbolKind};
+use std::collections::{HashMap, HashSet};
+
+/// The symbols of an object file sorted by address.
+#[derive(Clone, Debug)]
+pub struct SymbolTable<'data> {
+    symbols: Vec<Symbol<'data>>,
+}
+
+impl<'data> SymbolTable<'data> {
+    /// Collects both the regular and the dynamic symbols defined in `object`.
+    pub fn new(object: &object::File<'data>) -> Self {
+        let versions = dynamic_symbol_versions(object);
+        let mut symbols: Vec<_> = object
+            .symbols()
+            .map(|(_, sym)| (sym, None))
+            .chain(
+                object
+                    .dynamic_symbols()
+                    .map(|(index, sym)| (sym, versions.get(index.0).copied().flatten())),
+            )
+            .filter(|(symbol, _)| {
+                // Copied from `object::read::SymbolMap::filter`
+                match symbol.kind() {
+                    SymbolKind::Unknown | SymbolKind::Text | SymbolKind::Data => {}
+                    SymbolKind::Null
+                    | SymbolKind::Section
+                    | SymbolKind::File
+                    | SymbolKind::Label
+                    | SymbolKind::Tls => {
+                        return false;
+                    }
+                }
+                !symbol.is_undefined() && symbol.section() != object::SymbolSection::Common
+            })
+            .map(|(symbol, version)| Symbol::from(symbol).with_version(version))
+            .collect();
+        symbols.sort_by_key(|sym: &Symbol| sym.address());
+
+        SymbolTable { symbols }
+    }
+
+    /// Adds the symbols of `other` that aren't in `self` yet, e.g. those of a separate debug
+    /// file. Symbols with the same name and address are considered the same.
+    pub fn merge(&mut self, other: SymbolTable<'data>) {
+        let known: HashSet<_> = self
+            .symbols
+            .iter()
+            .map(|sym| (sym.address(), sym.name()))
+            .collect();
+        let new: Vec<_> = other
+            .symbols
+            .into_iter()
+            .filter(|sym| !known.contains(&(sym.address(), sym.name())))
+            .collect();
+        self.symbols.extend(new);
+        self.symbols.sort_by_key(|sym: &Symbol| sym.address());
+    }
+
+    /// Returns all symbols sorted by address.
+    pub fn symbols(&self) -> &[Symbol<'data>] {
+        &self.symbols
+    }
+
+    /// Returns the function symbols sorted by address.
+    pub fn functions(&self) -> impl Iterator<Item = &Symbol<'data>> + '_ {
+        self.symbols
+            .iter()
+            .filter(|sym| sym.kind() == SymbolKind::Text)
+    }
+
+    /// Returns the data symbols, like variables, sorted by address.
+    pub fn data_symbols(&self) -> impl Iterator<Item = &Symbol<'data>> + '_ {
+        self.symbols
+            .iter()
+            .filter(|sym| sym.kind() == SymbolKind::Data)
+    }
+
+    /// Returns the symbols visible outside of their object file sorted by address, e.g. to list
+    /// the functions exported by a shared library together with `functions`.
+    pub fn globals(&self) -> impl Iterator<Item = &Symbol<'data>> + '_ {
+        self.symbols.iter().filter(|sym| sym.is_global())
+    }
+
+    /// Returns the names of the symbols as they are in the object file, without demangling,
+    /// sorted by address like `symbols`. Symbols without a name are skipped.
+    ///
+    /// The names are borrowed from the object file data rather than from the table.
+    pub fn raw_names(&self) -> impl Iterator<Item = &'data str> + '_ {
+        self.symbols.iter().filter_map(|sym| sym.name())
+    }
+
+    /// Returns the symbol containing `addr` and the offset of `addr` within it.
+    ///
+    /// Symbols without a size are assumed to extend up to the next symbol. If none of the
+    /// symbols starting closest before `addr` contains it, the closest earlier symbol whose size
+    /// covers `addr` is taken, e.g. a function containing a smaller symbol that ends earlier.
+    pub fn symbol_for_addr(&self, addr: u64) -> Option<(&Symbol<'data>, u64)> {
+        // Index of the first symbol after `addr`.
+        let end = match self
+            .symbols
+            .binary_search_by(|sym| sym.address().cmp(&addr))
+        {
+            Ok(mut index) => {
+                while self.symbols.get(index).map(|sym| sym.address()) == Some(addr) {
+                    index += 1;
+                }
+                index
+            }
+            Err(index) => index,
+        };
+        let start_addr = self.symbols[..end].last()?.address();
+        let next_addr = self.symbols.get(end).map(|sym| sym.address());
+
+        // There may be several symbols at the same address, take the first one containing `addr`.
+        let nearest = self.symbols[..end]
+            .iter()
+            .rev()
+            .take_while(|sym| sym.address() == start_addr)
+            .filter(|sym| {
+                if sym.size() != 0 {
+                    addr < sym.address() + sym.size()
+                } else {
+                    next_addr.map_or(addr == sym.address(), |next_addr| addr < next_addr)
+                }
+            })
+            .last();
+        let sym = match nearest {
+            Some(sym) => sym,
+            None => self.symbols[..end]
+                .iter()
+                .rev()
+                .find(|sym| sym.size() != 0 && addr < sym.address() + sym.size())?,
+        };
+        Some((sym, addr - sym.address()))
+    }
+
+    /// Returns the symbols starting at an address in `start..end`, sorted by address.
+    ///
+    /// Symbols starting before `start` aren't included, even if they extend into the range.
+    pub fn symbols_in_range(&self, start: u64, end: u64) -> &[Symbol<'data>] {
+        let first = self.symbols.partition_point(|sym| sym.address() < start);
+        let last = self.symbols.partition_point(|sym| sym.address() < end);
+        &self.symbols[first..last.max(first)]
+    }
+
+    /// Returns the address of the symbol called `name`, which can be either its mangled or its
+    /// demangled name.
+    ///
+    /// When several symbols match, global symbols are preferred over local ones and strong symbols
+    /// over weak ones. The symbol with the lowest address wins among equally good matches.
+    pub fn address_of(&self, name: &str) -> Option<u64> {
+        self.symbol_by_name(name).map(|sym| sym.address())
+    }
+
+    /// Returns the symbol called `name`, choosing among several matches like `address_of`.
+    pub fn symbol_by_name(&self, name: &str) -> Option<&Symbol<'data>> {
+        self.symbols
+            .iter()
+            .filter(|sym| sym.name() == Some(name) || sym.demangled_name() == Some(name))
+            .min_by_key(|sym| (sym.is_local(), sym.is_weak()))
+    }
+}
+
+/// Returns the version of every dynamic symbol of `object`, indexed like the symbols, from the
+/// GNU symbol versioning sections `.gnu.version` and `.gnu.version_d`.
+///
+/// Only versions defined by `object` are found, those of undefined symbols are in
+/// `.gnu.version_r`. The result is empty if `object` doesn't use symbol versioning.
+fn dynamic_symbol_versions<'data>(object: &object::File<'data>) -> Vec<Option<&'data str>> {
+    let section_data = |name| {
+        object
+            .section_by_name(name)
+            .and_then(|section| section.data().ok())
+    };
+    let (versym, verdef, dynstr) = match (
+        section_data(".gnu.version"),
+        section_data(".gnu.version_d"),
+        section_data(".dynstr"),
+    ) {
+        (Some(versym), Some(verdef), Some(dynstr)) => (versym, verdef, dynstr),
+        _ => return Vec::new(),
+    };
+
+    let little_endian = object.is_little_endian();
+    let read_u16 = |data: &[u8], offset: usize| {
+        let bytes = [*data.get(offset)?, *data.get(offset + 1)?];
+        Some(if little_endian {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        })
+    };
+    let read_u32 = |data: &[u8], offset: usize| {
+        let low = read_u16(data, offset)? as u32;
+        let high = read_u16(data, offset + 2)? as u32;
+        Some(if little_endian {
+            low | high << 16
+        } else {
+            low << 16 | high
+        })
+    };
+    let read_str = |offset: usize| {
+        let bytes = dynstr.get(offset..)?;
+        let len = bytes.iter().position(|&byte| byte == 0)?;
+        std::str::from_utf8(&bytes[..len]).ok()
+    };
+
+    // Names of the versions by index. Each `Elf_Verdef` is followed by `Elf_Verdaux` entries,
+    // the first of which names the version.
+    let mut names = HashMap::new();
+    let mut offset = 0;
+    while let (Some(flags), Some(index), Some(aux), Some(next)) = (
+        read_u16(verdef, offset + 2),
+        read_u16(verdef, offset + 4),
+        read_u32(verdef, offset + 12),
+        read_u32(verdef, offset + 16),
+    ) {
+        // The base version is the name of the object itself, symbols with it aren't versioned.
+        if flags & VER_FLG_BASE == 0 {
+            let name = read_u32(verdef, offset + aux as usize)
+                .and_then(|name_offset| read_str(name_offset as usize));
+            if let Some(name) = name {
+                names.insert(index, name);
+            }
+        }
+        if next == 0 {
+            break;
+        }
+        offset += next as usize;
+    }
+
+    (0..versym.len() / 2)
+        .map(|index| {
+            let version = read_u16(versym, index * 2)? & !VERSYM_HIDDEN;
+            names.get(&version).copied()
+        })
+        .collect()
+}
+
+/// `vd_flags` of the version definition naming the object itself.
+const VER_FLG_BASE: u16 = 0x1;
+/// Set in `.gnu.version` for symbols that aren't the default version.
+const VERSYM_HIDDEN: u16 = 0x8000;
+
+#[cfg(test)]
+mod tests {
+    use super::{Symbol, SymbolTable};
+    use object::write;
+    use object::{Architecture, BinaryFormat, Endianness, SectionKind, SymbolKind, SymbolScope};
+    use SymbolKind::{Data, Text};
+    use SymbolScope::{Compilation, Dynamic, Linkage};
+
+    fn synthetic_object(symbols: &[(&str, u64, u64)]) -> Vec<u8> {
+        let symbols: Vec<_> = symbols
+            .iter()
+            .map(|&(name, value, size)| (name, value, size, Text, Linkage, false))
+            .collect();
+        synthetic_object_with_binding(&symbols)
+    }
+
+    /// `Data` symbols are put into `.data`, all others into `.text`.
+    fn synthetic_object_with_binding(
+        symbols: &[(&str, u64, u64, SymbolKind, SymbolScope, bool)],
+    ) -> Vec<u8> {
+        let mut obj =
+            write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        let text = obj.add_section(vec![], b".text".to_vec(), SectionKind::Text);
+        obj.append_section_data(text, &[0x90; 0x100], 16);
+        let data = obj.add_section(vec![], b".data".to_vec(), SectionKind::Data);
+        obj.append_section_data(data, &[0; 0x100], 16);
+        for &(name, value, size, kind, scope, weak) in symbols {
+            let section = if kind == Data { data } else { text };
+            obj.add_symbol(write::Symbol {
+                name: name.as_bytes().to_vec(),
+                value,
+                size,
+                kind,
+                scope,
+                weak,
+                section: write::SymbolSection::Section(section),
+                flags: object::SymbolFlags::None,
+            });
+        }
+        obj.write().unwrap()
+    }
+
+    fn lookup(table: &SymbolTable, addr: u64) -> Option<(String, u64)> {
+        table
+            .symbol_for_addr(addr)
+            .map(|(sym, offset)| (sym.name().unwrap().to_string(), offset))
+    }
+
+    #[test]
+    fn symbols_in_range() {
+        let bytes = synthetic_object(&[
+            ("a", 0x10, 0x10),
+            ("b", 0x20, 0x8),
+            ("b_alias", 0x20, 0x8),
+            ("c", 0x30, 0),
+            ("d", 0x80, 0x4),
+        ]);
+        let object = object::File::parse(&bytes).unwrap();
+        let table = SymbolTable::new(&object);
+        let names = |start, end| {
+            let mut names: Vec<_> = table
+                .symbols_in_range(start, end)
+                .iter()
+                .map(|sym| sym.name().unwrap())
+                .collect();
+            // The order of symbols at the same address is unspecified.
+            names.sort();
+            names
+        };
+
+        assert_eq!(names(0x0, 0x100), ["a", "b", "b_alias", "c", "d"]);
+        assert_eq!(names(0x20, 0x30), ["b", "b_alias"]);
+        // `a` extends into the range, but starts before it.
+        assert_eq!(names(0x18, 0x21), ["b", "b_alias"]);
+        // The end is exclusive.
+        assert_eq!(names(0x10, 0x20), ["a"]);
+        assert_eq!(names(0x31, 0x80), Vec::<&str>::new());
+        assert_eq!(names(0x80, 0x10), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn raw_names() {
+        use object::Object;
+
+        let bytes = synthetic_object(&[
+            ("_ZN5hello4main17h0123456789abcdefE", 0x40, 0x10),
+            ("_ZN3foo3barEv", 0x20, 0x10),
+            ("plain", 0x10, 0x10),
+        ]);
+        let object = object::File::parse(&bytes).unwrap();
+        let table = SymbolTable::new(&object);
+
+        let names: Vec<_> = table.raw_names().collect();
+        assert_eq!(
+            names,
+            [
+                "plain",
+                "_ZN3foo3barEv",
+                "_ZN5hello4main17h0123456789abcdefE"
+            ]
+        );
+        let mut in_object: Vec<_> = object
+            .symbols()
+            .filter_map(|(_, sym)| sym.name())
+            .filter(|name| !name.is_empty())
+            .collect();
+        in_object.sort_unstable();
+        let mut sorted = names.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, in_object);
+        // The names live as long as the object file data, not only as long as the table.
+        drop(table);
+        assert_eq!(names[0], "plain");
+    }
+
+    #[test]
+    fn symbol_for_addr() {
+        let bytes = synthetic_object(&[
+            ("sized", 0x10, 0x10),
+            ("unsized", 0x30, 0),
+            ("after_unsized", 0x40, 0x8),
+            ("last_unsized", 0x60, 0),
+        ]);
+        let object = object::File::parse(&bytes).unwrap();
+        let table = SymbolTable::new(&object);
+
+        assert_eq!(lookup(&table, 0x0), None);
+        assert_eq!(lookup(&table, 0x10), Some(("sized".to_string(), 0)));
+        assert_eq!(lookup(&table, 0x1f), Some(("sized".to_string(), 0xf)));
+        // Gap between the end of `sized` and `unsized`.
+        assert_eq!(lookup(&table, 0x20), None);
+        assert_eq!(lookup(&table, 0x30), Some(("unsized".to_string(), 0)));
+        assert_eq!(lookup(&table, 0x3f), Some(("unsized".to_string(), 0xf)));
+        assert_eq!(lookup(&table, 0x44), Some(("after_unsized".to_string(), 4)));
+        assert_eq!(lookup(&table, 0x48), None);
+        assert_eq!(lookup(&table, 0x60), Some(("last_unsized".to_string(), 0)));
+        assert_eq!(lookup(&table, 0x61), None);
+    }
+
+    #[test]
+    fn symbol_for_addr_nested() {
+        let bytes = synthetic_object(&[
+            ("outer", 0x100, 0x100),
+            ("inner", 0x120, 0x10),
+            ("label", 0x140, 0),
+            ("innermost", 0x150, 0x8),
+        ]);
+        let object = object::File::parse(&bytes).unwrap();
+        let table = SymbolTable::new(&object);
+
+        assert_eq!(lookup(&table, 0x110), Some(("outer".to_string(), 0x10)));
+        assert_eq!(lookup(&table, 0x128), Some(("inner".to_string(), 8)));
+        // Past the end of `inner`, but still inside `outer`.
+        assert_eq!(lookup(&table, 0x130), Some(("outer".to_string(), 0x30)));
+        // The label without a size extends up to `innermost`.
+        assert_eq!(lookup(&table, 0x148), Some(("label".to_string(), 8)));
+        assert_eq!(lookup(&table, 0x154), Some(("innermost".to_string(), 4)));
+        assert_eq!(lookup(&table, 0x160), Some(("outer".to_string(), 0x60)));
+        assert_eq!(lookup(&table, 0x200), None);
+    }
+
+    #[test]
+    fn symbol_for_addr_with_aliases() {
+        let bytes = synthetic_object(&[("empty_alias", 0x10, 0), ("function", 0x10, 0x20)]);
+        let object = object::File::parse(&bytes).unwrap();
+        let table = SymbolTable::new(&object);
+
+        assert_eq!(lookup(&table, 0x18).map(|(_, offset)| offset), Some(8));
+        // The alias without a size ends at the next symbol, which doesn't exist.
+        assert_eq!(lookup(&table, 0x28), Some(("function".to_string(), 0x18)));
+    }
+
+    #[test]
+    fn address_of_c_symbol() {
+        let bytes = synthetic_object(&[("helper", 0x10, 0x10), ("main", 0x20, 0x10)]);
+        let object = object::File::parse(&bytes).unwrap();
+        let table = SymbolTable::new(&object);
+
+        assert_eq!(table.address_of("main"), Some(0x20));
+        assert_eq!(table.address_of("helper"), Some(0x10));
+        assert_eq!(table.address_of("missing"), None);
+    }
+
+    #[test]
+    fn address_of_rust_symbol() {
+        let bytes = synthetic_object(&[("_ZN5hello4main17h0123456789abcdefE", 0x40, 0x10)]);
+        let object = object::File::parse(&bytes).unwrap();
+        let table = SymbolTable::new(&object);
+
+        assert_eq!(
+            table.address_of("_ZN5hello4main17h0123456789abcdefE"),
+            Some(0x40)
+        );
+        assert_eq!(table.address_of("hello::main"), Some(0x40));
+    }
+
+    #[test]
+    fn functions_and_data_symbols() {
+        let bytes = synthetic_object_with_binding(&[
+            ("exported", 0x10, 8, Text, Dynamic, false),
+            ("helper", 0x20, 8, Text, Compilation, false),
+            ("main", 0x30, 8, Text, Linkage, false),
+            ("COUNTER", 0x0, 8, Data, Linkage, false),
+            ("cache", 0x8, 8, Data, Compilation, false),
+        ]);
+        let object = object::File::parse(&bytes).unwrap();
+        let table = SymbolTable::new(&object);
+
+        let names = |symbols: Vec<&Symbol>| {
+            let mut names: Vec<_> = symbols.iter().map(|sym| sym.name().unwrap()).collect();
+            names.sort_unstable();
+            names.into_iter().map(str::to_string).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            names(table.functions().collect()),
+            ["exported", "helper", "main"]
+        );
+        assert_eq!(names(table.data_symbols().collect()), ["COUNTER", "cache"]);
+        assert_eq!(
+            names(table.globals().collect()),
+            ["COUNTER", "exported", "main"]
+        );
+        assert_eq!(table.functions().filter(|sym| sym.is_global()).count(), 2);
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64", target_env = "gnu"))]
+    #[test]
+    fn libc_symbol_versions() {
+        let objects = crate::target::LinuxTarget::me().loaded_objects().unwrap();
+        let libc = objects
+            .iter()
+            .find(|object| {
+                matches!(object.path.file_name().and_then(|name| name.to_str()),
+                    Some(name) if name.starts_with("libc.so") || name.starts_with("libc-"))
+            })
+            .expect("libc isn't loaded");
+        let bytes = std::fs::read(&libc.path).unwrap();
+        let object = object::File::parse(&bytes).unwrap();
+        let table = SymbolTable::new(&object);
+
+        // `memcpy` got a new version when it stopped supporting overlapping buffers.
+        let mut versions: Vec<_> = table
+            .symbols()
+            .iter()
+            .filter(|sym| sym.name() == Some("memcpy"))
+            .map(|sym| sym.version())
+            .collect();
+        versions.sort_unstable();
+        versions.dedup();
+        assert_eq!(versions, [Some("GLIBC_2.14"), Some("GLIBC_2.2.5")]);
+    }
+
+    #[test]
+    fn address_of_prefers_strong_global_symbols() {
+        let bytes = synthetic_object_with_binding(&[
+            ("ambiguous", 0x10, 0x10, Text, Compilation, false),
+            ("ambiguous", 0x20, 0x10, Text, Linkage, true),
+            ("ambiguous", 0x30, 0x10, Text, Linkage, false),
+            ("ambiguous", 0x40, 0x10, Text, Linkage, false),
+            ("weak_only", 0x50, 0x10, Text, Compilation, false),
+            ("weak_only", 0x60, 0x10, Text, Linkage, true),
+        ]);
+        let object = object::File::parse(&bytes).unwrap();
+        let table = SymbolTable::new(&object);
+
+        assert_eq!(table.address_of("ambiguous"), Some(0x30));
+        assert_eq!(table.address_of("weak_only"), Some(0x60));
+    }
+}
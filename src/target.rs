@@ -18,7 +18,74 @@ mod windows;
 #[cfg(target_os = "windows")]
 pub use windows::*;
 
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+mod registers;
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+pub use registers::Registers;
+#[cfg(target_arch = "x86_64")]
+pub use registers::{Flags, SegmentRegisters};
+
 mod thread;
+pub use thread::{Thread, ThreadState};
+
+/// The operations every target supports, so that tools can be written independently of the
+/// platform, e.g. against a `&mut dyn Target`.
+///
+/// Platform specific functionality, like hardware breakpoints, is only available as inherent
+/// methods of the target types.
+///
+/// ```no_run
+/// # #[cfg(target_os = "linux")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use headcrab::target::{LinuxTarget, Target, TargetStatus};
+///
+/// type DynTarget = dyn Target<Regs = libc::user_regs_struct, ThreadId = i32, Status = TargetStatus>;
+///
+/// /// Runs the debuggee until it hits a breakpoint at `addr`.
+/// fn run_to(target: &mut DynTarget, addr: usize) -> headcrab::Result<TargetStatus> {
+///     target.set_breakpoint(addr)?;
+///     target.cont()
+/// }
+///
+/// let (mut target, _status) = LinuxTarget::launch("/bin/true")?;
+/// let regs = target.read_regs()?;
+/// run_to(&mut target, regs.rip as usize + 0x10)?;
+/// # Ok(())
+/// # }
+/// # #[cfg(not(target_os = "linux"))]
+/// # fn main() {}
+/// ```
+pub trait Target {
+    /// The register set of a thread, e.g. `libc::user_regs_struct` on Linux.
+    type Regs;
+    /// Identifies a thread of the debuggee.
+    type ThreadId;
+    /// Why the debuggee stopped.
+    type Status;
+
+    /// Reads the registers of the debuggee's main thread.
+    fn read_regs(&self) -> crate::Result<Self::Regs>;
+
+    /// Writes the registers of the debuggee's main thread.
+    fn write_regs(&self, regs: Self::Regs) -> crate::Result<()>;
+
+    /// Reads `buf.len()` bytes from debuggee's memory at location `addr`.
+    ///
+    /// Named so it doesn't clash with the `read` builders of the targets.
+    fn read_memory(&self, addr: usize, buf: &mut [u8]) -> crate::Result<()>;
+
+    /// Writes `bytes` to debuggee's memory at location `addr`.
+    fn write_memory(&self, addr: usize, bytes: &[u8]) -> crate::Result<()>;
+
+    /// Continues the debuggee and waits for it to stop again.
+    fn cont(&mut self) -> crate::Result<Self::Status>;
+
+    /// Sets a software breakpoint at `addr`.
+    fn set_breakpoint(&mut self, addr: usize) -> crate::Result<()>;
+
+    /// Returns the threads of the debuggee.
+    fn threads(&self) -> crate::Result<Vec<Box<dyn Thread<ThreadId = Self::ThreadId>>>>;
+}
 
 #[derive(Debug)]
 pub struct MemoryMap {
@@ -36,3 +103,170 @@ pub struct MemoryMap {
     /// Is mapped memory private.
     pub is_private: bool,
 }
+
+impl MemoryMap {
+    /// Returns whether `addr` lies within the mapped memory. The end address is exclusive.
+    pub fn contains(&self, addr: usize) -> bool {
+        self.address.0 <= addr as u64 && (addr as u64) < self.address.1
+    }
+}
+
+/// Selects memory maps, e.g. the ones `LinuxTarget::search_memory` scans.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MapFilter {
+    /// Every map.
+    All,
+    /// Writable maps, like the heap, the stacks and the data segments.
+    Writable,
+    /// Executable maps, which hold the code.
+    Executable,
+    /// Maps that aren't backed by a file, like the heap and the stacks.
+    Anonymous,
+    /// Maps of the file at this path.
+    File(std::path::PathBuf),
+}
+
+impl MapFilter {
+    /// Returns whether `map` is selected.
+    pub fn matches(&self, map: &MemoryMap) -> bool {
+        match self {
+            MapFilter::All => true,
+            MapFilter::Writable => map.is_writable,
+            MapFilter::Executable => map.is_executable,
+            MapFilter::Anonymous => map.backing_file.is_none(),
+            MapFilter::File(path) => match &map.backing_file {
+                Some((map_path, _offset)) => map_path == path,
+                None => false,
+            },
+        }
+    }
+}
+
+/// An executable or shared library mapped into a process.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadedObject {
+    /// Path of the mapped file.
+    pub path: std::path::PathBuf,
+    /// Start address of the lowest mapping of the file.
+    pub base: u64,
+    /// Is any part of the file mapped executable.
+    pub is_executable: bool,
+}
+
+impl LoadedObject {
+    /// Groups the file backed `maps` by file, ordered by base address.
+    pub fn from_maps(maps: &[MemoryMap]) -> Vec<LoadedObject> {
+        let mut objects: Vec<LoadedObject> = Vec::new();
+        for map in maps {
+            let path = match &map.backing_file {
+                Some((path, _offset)) => path,
+                None => continue,
+            };
+            match objects.iter_mut().find(|object| object.path == *path) {
+                Some(object) => {
+                    object.base = std::cmp::min(object.base, map.address.0);
+                    object.is_executable |= map.is_executable;
+                }
+                None => objects.push(LoadedObject {
+                    path: path.clone(),
+                    base: map.address.0,
+                    is_executable: map.is_executable,
+                }),
+            }
+        }
+        objects.sort_by_key(|object| object.base);
+        objects
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LoadedObject, MapFilter, MemoryMap};
+
+    fn map(start: u64, end: u64) -> MemoryMap {
+        MemoryMap {
+            address: (start, end),
+            backing_file: None,
+            is_readable: true,
+            is_writable: false,
+            is_executable: false,
+            is_private: true,
+        }
+    }
+
+    #[test]
+    fn map_containing_addr() {
+        let maps = [
+            map(0x1000, 0x2000),
+            map(0x2000, 0x4000),
+            map(0x8000, 0x9000),
+        ];
+        let start_of_map_containing = |addr| {
+            maps.iter()
+                .find(|map| map.contains(addr))
+                .map(|map| map.address.0)
+        };
+
+        assert_eq!(start_of_map_containing(0xfff), None);
+        assert_eq!(start_of_map_containing(0x1000), Some(0x1000));
+        assert_eq!(start_of_map_containing(0x1fff), Some(0x1000));
+        // The end address belongs to the next map, if any.
+        assert_eq!(start_of_map_containing(0x2000), Some(0x2000));
+        assert_eq!(start_of_map_containing(0x4000), None);
+        assert_eq!(start_of_map_containing(0x8fff), Some(0x8000));
+        assert_eq!(start_of_map_containing(0x9000), None);
+    }
+
+    #[test]
+    fn map_filter() {
+        let anonymous = MemoryMap {
+            is_writable: true,
+            ..map(0x1000, 0x2000)
+        };
+        let code = MemoryMap {
+            backing_file: Some(("/bin/true".into(), 0)),
+            is_executable: true,
+            ..map(0x2000, 0x3000)
+        };
+
+        assert!(MapFilter::All.matches(&anonymous) && MapFilter::All.matches(&code));
+        assert!(MapFilter::Writable.matches(&anonymous) && !MapFilter::Writable.matches(&code));
+        assert!(!MapFilter::Executable.matches(&anonymous) && MapFilter::Executable.matches(&code));
+        assert!(MapFilter::Anonymous.matches(&anonymous) && !MapFilter::Anonymous.matches(&code));
+        let file = MapFilter::File("/bin/true".into());
+        assert!(!file.matches(&anonymous) && file.matches(&code));
+        assert!(!MapFilter::File("/bin/false".into()).matches(&code));
+    }
+
+    #[test]
+    fn loaded_objects_from_maps() {
+        let file_map = |start, end, path: &str, is_executable| MemoryMap {
+            backing_file: Some((path.into(), 0)),
+            is_executable,
+            ..map(start, end)
+        };
+        let maps = [
+            file_map(0x5000, 0x6000, "/bin/true", false),
+            file_map(0x6000, 0x7000, "/bin/true", true),
+            map(0x7000, 0x8000),
+            file_map(0x2000, 0x3000, "/lib/libc.so.6", false),
+            file_map(0x1000, 0x2000, "/lib/libc.so.6", false),
+        ];
+
+        assert_eq!(
+            LoadedObject::from_maps(&maps),
+            [
+                LoadedObject {
+                    path: "/lib/libc.so.6".into(),
+                    base: 0x1000,
+                    is_executable: false,
+                },
+                LoadedObject {
+                    path: "/bin/true".into(),
+                    base: 0x5000,
+                    is_executable: true,
+                },
+            ]
+        );
+    }
+}
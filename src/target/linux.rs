@@ -0,0 +1,5397 @@
+mod breakpoints;
+#[cfg(target_arch = "x86_64")]
+mod core_dump;
+#[cfg(target_arch = "x86_64")]
+mod core_target;
+mod hardware_breakpoint;
+mod memory;
+mod readmem;
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+mod snapshot;
+mod software_breakpoint;
+mod writemem;
+
+use crate::target::thread::{Thread, ThreadState};
+use crate::target::unix::{self, UnixTarget};
+#[cfg(target_arch = "x86_64")]
+use crate::target::Target;
+use crate::{Error, Result};
+use nix::sys::ptrace;
+use nix::unistd::{getpid, Pid};
+use procfs::process::{Process, Task};
+use procfs::ProcError;
+use std::{
+    cell::{Cell, Ref, RefCell},
+    collections::HashMap,
+    ffi::CString,
+    fs::File,
+    io::{BufRead, BufReader},
+    os::unix::io::{AsRawFd, FromRawFd, RawFd},
+    path::{Path, PathBuf},
+};
+
+pub use breakpoints::{Breakpoint, Breakpoints};
+#[cfg(target_arch = "x86_64")]
+pub use core_target::CoreTarget;
+pub use hardware_breakpoint::{
+    DebugStatus, HardwareBreakpoint, HardwareBreakpointError, HardwareBreakpointSize,
+    HardwareBreakpointType,
+};
+#[cfg(target_arch = "aarch64")]
+use hardware_breakpoint::{UserHwdebugReg, UserHwdebugState, NT_ARM_HW_BREAK, NT_ARM_HW_WATCH};
+pub use readmem::ReadMemory;
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+pub use snapshot::{Snapshot, SnapshotDiff};
+pub use software_breakpoint::SoftwareBreakpointError;
+pub use writemem::{WriteMemory, WriteMemoryError};
+
+lazy_static::lazy_static! {
+    static ref PAGE_SIZE: usize = unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize };
+}
+
+#[cfg(target_arch = "x86_64")]
+lazy_static::lazy_static! {
+    /// Offset of `u_debugreg` in `libc::user`, the layout `PTRACE_PEEKUSER` uses.
+    static ref DEBUG_REG_OFFSET: usize = unsafe {
+        let x = std::mem::zeroed::<libc::user>();
+        (&x.u_debugreg as *const _ as usize) - (&x as *const _ as usize)
+    };
+}
+
+#[cfg(target_arch = "x86_64")]
+const SUPPORTED_HARDWARE_BREAKPOINTS: usize = 4;
+
+/// Up to 16 breakpoints and 16 watchpoints, depending on the CPU.
+#[cfg(target_arch = "aarch64")]
+const SUPPORTED_HARDWARE_BREAKPOINTS: usize = 32;
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+const SUPPORTED_HARDWARE_BREAKPOINTS: usize = 0;
+
+// `si_code` values of a `SIGTRAP`, missing from older libc versions.
+const TRAP_TRACE: libc::c_int = 2;
+const TRAP_HWBKPT: libc::c_int = 4;
+
+// The `PTRACE_EVENT_*` value of the stops of seized debuggees, missing from libc.
+const PTRACE_EVENT_STOP: libc::c_int = 128;
+
+// The `si_code` of a `SIGSEGV` caused by the protection of a mapped page, missing from libc.
+const SEGV_ACCERR: libc::c_int = 2;
+
+// `PTRACE_GET_SYSCALL_INFO` from Linux 5.3, missing from older libc versions.
+#[cfg(target_arch = "x86_64")]
+const PTRACE_GET_SYSCALL_INFO: libc::c_uint = 0x420e;
+#[cfg(target_arch = "x86_64")]
+const PTRACE_SYSCALL_INFO_ENTRY: u8 = 1;
+/// Size of `struct ptrace_syscall_info`.
+#[cfg(target_arch = "x86_64")]
+const PTRACE_SYSCALL_INFO_SIZE: usize = 88;
+
+/// The longest valid x86 instruction is 15 bytes.
+#[cfg(target_arch = "x86_64")]
+const MAX_INSTRUCTION_LEN: usize = 15;
+
+struct LinuxThread {
+    task: Task,
+}
+
+impl LinuxThread {
+    fn new(task: Task) -> LinuxThread {
+        LinuxThread { task }
+    }
+}
+
+impl Thread for LinuxThread {
+    type ThreadId = i32;
+
+    fn name(&self) -> Result<Option<String>> {
+        match self.task.stat() {
+            Ok(t_stat) => Ok(Some(t_stat.comm.clone())),
+            Err(ProcError::NotFound(_)) | Err(ProcError::Incomplete(_)) => {
+                // ok to skip. Thread is gone or it's page is not complete yet.
+                Ok(None)
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn thread_id(&self) -> Self::ThreadId {
+        self.task.tid
+    }
+
+    fn state(&self) -> Result<Option<ThreadState>> {
+        let state = match self.task.stat() {
+            Ok(t_stat) => t_stat.state,
+            Err(ProcError::NotFound(_)) | Err(ProcError::Incomplete(_)) => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        // See `man 5 proc`, some letters were only used by older kernels.
+        Ok(Some(match state {
+            'R' => ThreadState::Running,
+            'S' => ThreadState::Sleeping,
+            'D' => ThreadState::DiskSleep,
+            'T' => ThreadState::Stopped,
+            't' => ThreadState::TracingStop,
+            'Z' => ThreadState::Zombie,
+            'X' | 'x' => ThreadState::Dead,
+            'I' => ThreadState::Idle,
+            other => ThreadState::Other(other),
+        }))
+    }
+
+    fn cpu(&self) -> Result<Option<usize>> {
+        match self.task.stat() {
+            Ok(t_stat) => Ok(t_stat.processor.map(|cpu| cpu as usize)),
+            Err(ProcError::NotFound(_)) | Err(ProcError::Incomplete(_)) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// This structure holds the state of a debuggee on Linux based systems
+/// You can use it to read & write debuggee's memory, pause it, set breakpoints, etc.
+pub struct LinuxTarget {
+    pid: Pid,
+    hardware_breakpoints: [Option<HardwareBreakpoint>; SUPPORTED_HARDWARE_BREAKPOINTS],
+    /// Original bytes of the instructions patched with `int3`, keyed by address.
+    breakpoints: HashMap<usize, u8>,
+    /// Bookkeeping of the breakpoints managed with `Breakpoints`.
+    breakpoint_list: breakpoints::BreakpointList,
+    /// Ptrace options currently set on the debuggee. `PTRACE_SETOPTIONS` replaces all of them
+    /// at once, so they're tracked here to be able to toggle them one by one.
+    options: ptrace::Options,
+    /// Address of an existing `syscall` instruction used to make syscalls in the debuggee.
+    syscall_site: Cell<Option<usize>>,
+    /// `/proc/<pid>/mem` of the debuggee, opened on first use.
+    mem_file: RefCell<Option<File>>,
+    /// Whether the last syscall stop reported by `syscall_step` was an entry.
+    #[cfg(target_arch = "x86_64")]
+    in_syscall: bool,
+    /// Threads stopped by `stop_all_threads`, and whether they were attached to for that.
+    stopped_threads: RefCell<Vec<(i32, bool)>>,
+    /// Threads other than the main one that stay traced, see `AttachOptions::all_threads`.
+    traced_threads: Vec<i32>,
+    /// Threads reported by a clone event whose initial `SIGSTOP` hasn't been seen yet.
+    pending_clones: Vec<i32>,
+    /// Stops of traced threads seen by `stop_all_threads` while waiting for its `SIGSTOP`, which
+    /// `wait_any_thread` reports before waiting for new ones.
+    pending_stops: RefCell<Vec<nix::sys::wait::WaitStatus>>,
+    /// Regions watched with `soft_watch_region`, as start and length.
+    soft_watch_regions: Vec<(usize, usize)>,
+    /// Original protection of the pages made read-only for `soft_watch_regions`, keyed by
+    /// address.
+    guarded_pages: HashMap<usize, libc::c_int>,
+    /// Signals `wait` reports, `None` for all of them, see `catch_signals`.
+    caught_signals: Option<Vec<nix::sys::signal::Signal>>,
+}
+
+/// This structure is used to pass options to attach
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AttachOptions {
+    /// Determines whether process will be killed on debugger exit or crash.
+    pub kill_on_exit: bool,
+    /// Determines whether all threads of the process are traced, not only the main one.
+    ///
+    /// `PTRACE_O_TRACECLONE` is enabled too, so threads created later are traced as well, and
+    /// every thread creation stops the creating thread with a `WaitStatus::PtraceEvent`. Use
+    /// `cont_all_threads` and `wait_any_thread` to run the debuggee, which handle those events.
+    pub all_threads: bool,
+}
+
+/// Where a standard stream of a launched debuggee is connected to, see `LaunchOptions`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stdio {
+    /// The stream of this process is inherited.
+    Inherit,
+    /// The stream is connected to this file descriptor, which stays owned by the caller.
+    Fd(RawFd),
+    /// The stream is connected to a new pipe, whose other end is returned in `DebuggeePipes`.
+    Piped,
+}
+
+// Deriving it would need `#[default]`, which older compilers don't support.
+#[allow(clippy::derivable_impls)]
+impl Default for Stdio {
+    fn default() -> Self {
+        Stdio::Inherit
+    }
+}
+
+/// This structure is used to pass options to `LinuxTarget::launch_with_options`.
+#[derive(Debug)]
+pub struct LaunchOptions {
+    pub stdin: Stdio,
+    pub stdout: Stdio,
+    pub stderr: Stdio,
+    /// Determines whether process will be killed on debugger exit or crash, like
+    /// `AttachOptions::kill_on_exit`. Defaults to `true`, which is what `launch` does.
+    pub kill_on_exit: bool,
+}
+
+impl Default for LaunchOptions {
+    fn default() -> Self {
+        LaunchOptions {
+            stdin: Stdio::default(),
+            stdout: Stdio::default(),
+            stderr: Stdio::default(),
+            kill_on_exit: true,
+        }
+    }
+}
+
+/// Our ends of the pipes created for the `Stdio::Piped` streams of a launched debuggee.
+#[derive(Debug, Default)]
+pub struct DebuggeePipes {
+    /// Writing to this feeds the debuggee's stdin.
+    pub stdin: Option<File>,
+    pub stdout: Option<File>,
+    pub stderr: Option<File>,
+}
+
+/// Where a debuggee is stopped, as returned by `LinuxTarget::current_location`.
+///
+/// It's displayed like `0x401136 in main+4 at src/main.rs:3`, leaving out what is unknown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    /// The program counter.
+    pub pc: usize,
+    /// The demangled name of the symbol containing `pc` and the offset of `pc` within it.
+    pub symbol: Option<(String, usize)>,
+    /// The source file and line of the instruction at `pc`, if there is debug info for it.
+    pub source: Option<(String, u64)>,
+}
+
+impl std::fmt::Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "0x{:x}", self.pc)?;
+        if let Some((name, offset)) = &self.symbol {
+            write!(f, " in {}+{}", name, offset)?;
+        }
+        if let Some((file, line)) = &self.source {
+            write!(f, " at {}:{}", file, line)?;
+        }
+        Ok(())
+    }
+}
+
+/// A software breakpoint, as returned by `LinuxTarget::list_breakpoints`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BreakpointInfo {
+    pub addr: usize,
+    /// The original byte of the instruction replaced by `int3`, `None` if the breakpoint is
+    /// disabled, in which case the instruction is in place.
+    pub orig_byte: Option<u8>,
+    pub enabled: bool,
+    /// The id of the breakpoint if it's managed with `LinuxTarget::breakpoints`.
+    pub id: Option<usize>,
+    /// Whether it's a temporary breakpoint of `LinuxTarget::breakpoints`, which is removed when
+    /// it's hit.
+    pub temporary: bool,
+    /// The demangled name of the symbol containing `addr` and the offset of `addr` within it.
+    pub symbol: Option<(String, usize)>,
+}
+
+/// A file descriptor of a debuggee, as returned by `LinuxTarget::open_files`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpenFile {
+    pub fd: RawFd,
+    /// What the file descriptor refers to, as shown by `/proc/<pid>/fd`. Files have their path,
+    /// while other kinds of descriptors have names like `socket:[1234]`, `pipe:[1234]` or
+    /// `anon_inode:[eventfd]`.
+    pub path: PathBuf,
+}
+
+/// Information about a debuggee from `/proc/<pid>/status`, as returned by `LinuxTarget::status`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessStatus {
+    /// The name of the command, truncated to 15 bytes.
+    pub name: String,
+    pub parent_pid: Pid,
+    /// The id of the thread tracing the debuggee, normally one of this process, `None` if it
+    /// isn't traced.
+    pub tracer_pid: Option<Pid>,
+    /// The number of threads.
+    pub threads: u64,
+    /// The size of the virtual memory in bytes, `None` for kernel threads.
+    pub vm_size: Option<u64>,
+    /// The resident set size in bytes, `None` for kernel threads.
+    pub vm_rss: Option<u64>,
+}
+
+/// A branch instruction executed by `LinuxTarget::step_until_branch`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Branch {
+    pub kind: crate::symbol::BranchKind,
+    /// Address of the branch instruction.
+    pub address: usize,
+    /// Where execution continued, the next instruction for a conditional jump that wasn't taken.
+    pub target: usize,
+}
+
+/// The registers of a thread running 32-bit code, in the layout of `struct user_regs_struct` on
+/// i386, as returned by `LinuxTarget::read_regs32`.
+///
+/// The segment selectors are in the low 16 bits of the `x*` fields.
+#[cfg(target_arch = "x86_64")]
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Registers32 {
+    pub ebx: u32,
+    pub ecx: u32,
+    pub edx: u32,
+    pub esi: u32,
+    pub edi: u32,
+    pub ebp: u32,
+    pub eax: u32,
+    pub xds: u32,
+    pub xes: u32,
+    pub xfs: u32,
+    pub xgs: u32,
+    pub orig_eax: u32,
+    pub eip: u32,
+    pub xcs: u32,
+    pub eflags: u32,
+    pub esp: u32,
+    pub xss: u32,
+}
+
+/// A write to a page guarded with `LinuxTarget::soft_watch_region`, as returned by
+/// `LinuxTarget::soft_watch_fault`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SoftWatchFault {
+    /// The address the write faulted at, which is where it starts unless it crosses a page.
+    pub addr: usize,
+    /// Start and length of the watched region containing `addr`, `None` if the write only hit
+    /// another part of a guarded page.
+    pub region: Option<(usize, usize)>,
+}
+
+/// A typed view of the reason a debuggee stopped, as returned by [`LinuxTarget::wait`].
+///
+/// [`LinuxTarget::wait`]: struct.LinuxTarget.html#method.wait
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopEvent {
+    /// The debuggee exited with the given status code.
+    Exited(i32),
+    /// The debuggee was terminated by a signal.
+    Killed(nix::sys::signal::Signal),
+    /// The debuggee was stopped by a signal. It's not delivered unless passed to `cont`.
+    Signal(nix::sys::signal::Signal),
+    /// The debuggee created a new process with `fork` or `vfork`.
+    Fork(Pid),
+    /// The debuggee created a new thread with `clone`.
+    Clone(Pid),
+    /// The debuggee replaced its image with `execve`.
+    Exec,
+    /// The debuggee entered or exited a syscall.
+    Syscall,
+    /// Any other `PTRACE_EVENT_*` stop.
+    PtraceEvent(i32),
+}
+
+/// A syscall stop of the debuggee, as returned by [`LinuxTarget::syscall_step`].
+///
+/// [`LinuxTarget::syscall_step`]: struct.LinuxTarget.html#method.syscall_step
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyscallStop {
+    /// The debuggee is about to execute the syscall `nr` with the arguments `args`.
+    Entry { nr: u64, args: [u64; 6] },
+    /// The syscall `nr` returned `ret`. Values between -4095 and -1 are negated errno values.
+    Exit { nr: u64, ret: i64 },
+}
+
+/// The state of a debuggee after it changed, as returned by [`LinuxTarget::wait_status`].
+///
+/// [`LinuxTarget::wait_status`]: struct.LinuxTarget.html#method.wait_status
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetStatus {
+    /// The debuggee exited with the given status code.
+    Exited(i32),
+    /// The debuggee was terminated by a signal.
+    Signaled(nix::sys::signal::Signal),
+    /// The debuggee was stopped by a signal, other than for a breakpoint or single step.
+    Stopped(nix::sys::signal::Signal),
+    /// The debuggee hit a software or hardware breakpoint.
+    Breakpoint,
+    /// The debuggee executed a single instruction after `step`.
+    SingleStep,
+    /// The debuggee was resumed by a `SIGCONT`.
+    Continued,
+}
+
+impl UnixTarget for LinuxTarget {
+    /// Provides the Pid of the debuggee process
+    fn pid(&self) -> Pid {
+        self.pid
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+impl Target for LinuxTarget {
+    type Regs = libc::user_regs_struct;
+    type ThreadId = i32;
+    type Status = TargetStatus;
+
+    fn read_regs(&self) -> Result<libc::user_regs_struct> {
+        LinuxTarget::read_regs(self)
+    }
+
+    fn write_regs(&self, regs: libc::user_regs_struct) -> Result<()> {
+        LinuxTarget::write_regs(self, regs)
+    }
+
+    fn read_memory(&self, addr: usize, buf: &mut [u8]) -> Result<()> {
+        self.read().read_byte_slice(buf, addr).apply()
+    }
+
+    fn write_memory(&self, addr: usize, bytes: &[u8]) -> Result<()> {
+        self.write_bytes(addr, bytes)
+    }
+
+    /// Continues without delivering a signal, like `unpause`, and classifies the stop with
+    /// `wait_status`.
+    fn cont(&mut self) -> Result<TargetStatus> {
+        ptrace::cont(self.pid, None)?;
+        self.wait_status()
+    }
+
+    fn set_breakpoint(&mut self, addr: usize) -> Result<()> {
+        LinuxTarget::set_breakpoint(self, addr)
+    }
+
+    fn threads(&self) -> Result<Vec<Box<dyn Thread<ThreadId = i32>>>> {
+        LinuxTarget::threads(self)
+    }
+}
+
+impl LinuxTarget {
+    fn new(pid: Pid) -> Self {
+        Self {
+            pid,
+            hardware_breakpoints: Default::default(),
+            breakpoints: HashMap::new(),
+            breakpoint_list: Default::default(),
+            options: ptrace::Options::empty(),
+            syscall_site: Cell::new(None),
+            mem_file: RefCell::new(None),
+            #[cfg(target_arch = "x86_64")]
+            in_syscall: false,
+            stopped_threads: RefCell::new(Vec::new()),
+            traced_threads: Vec::new(),
+            pending_clones: Vec::new(),
+            pending_stops: RefCell::new(Vec::new()),
+            soft_watch_regions: Vec::new(),
+            guarded_pages: HashMap::new(),
+            caught_signals: None,
+        }
+    }
+
+    /// Launches a new debuggee process, which is killed when the debugger exits. Use
+    /// `launch_with_options` to launch one that keeps running.
+    pub fn launch(path: &str) -> Result<(LinuxTarget, nix::sys::wait::WaitStatus)> {
+        LinuxTarget::launch_cstrings(CString::new(path)?, &[], None, [None; 3], true)
+    }
+
+    /// Launches a new debuggee process with the arguments `args` and the environment `env`.
+    ///
+    /// `args` don't include the program name, `path` is passed as `argv[0]`. Unlike with
+    /// `launch`, the debuggee doesn't inherit the environment of this process, it only gets the
+    /// variables in `env`. Like `launch`, this returns once the debuggee stopped after `execve`.
+    pub fn launch_with(
+        path: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+    ) -> Result<(LinuxTarget, nix::sys::wait::WaitStatus)> {
+        let (target, status, _pipes) =
+            LinuxTarget::launch_with_options(path, args, Some(env), LaunchOptions::default())?;
+        Ok((target, status))
+    }
+
+    /// Launches a new debuggee process like `launch_with`, with its standard streams connected
+    /// as specified in `options`. If `env` is `None`, the environment of this process is
+    /// inherited. The debuggee is only killed when the debugger exits if
+    /// `options.kill_on_exit` is set.
+    ///
+    /// The streams are set up in the child process after forking and before `execve`. Returns
+    /// our ends of the pipes created for `Stdio::Piped`, e.g. to read the debuggee's output.
+    pub fn launch_with_options(
+        path: &str,
+        args: &[&str],
+        env: Option<&[(&str, &str)]>,
+        options: LaunchOptions,
+    ) -> Result<(LinuxTarget, nix::sys::wait::WaitStatus, DebuggeePipes)> {
+        let args = args
+            .iter()
+            .map(|arg| CString::new(*arg))
+            .collect::<Result<Vec<_>, _>>()?;
+        let env = env
+            .map(|env| {
+                env.iter()
+                    .map(|(name, value)| CString::new(format!("{}={}", name, value)))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()?;
+
+        let mut stdio = [None; 3];
+        let mut pipes: [Option<File>; 3] = Default::default();
+        // The debuggee's ends of the pipes, closed when they're dropped after launching.
+        let mut child_ends = Vec::new();
+        for (fd, stream) in [options.stdin, options.stdout, options.stderr]
+            .iter()
+            .enumerate()
+        {
+            match *stream {
+                Stdio::Inherit => {}
+                Stdio::Fd(raw_fd) => stdio[fd] = Some(raw_fd),
+                Stdio::Piped => {
+                    // Close-on-exec keeps other debuggees launched in the meantime from
+                    // inheriting the pipes, `dup2` clears it for the debuggee's copy.
+                    let (read, write) = nix::unistd::pipe2(nix::fcntl::OFlag::O_CLOEXEC)?;
+                    let (read, write) =
+                        unsafe { (File::from_raw_fd(read), File::from_raw_fd(write)) };
+                    let (ours, theirs) = if fd == 0 {
+                        (write, read)
+                    } else {
+                        (read, write)
+                    };
+                    stdio[fd] = Some(theirs.as_raw_fd());
+                    pipes[fd] = Some(ours);
+                    child_ends.push(theirs);
+                }
+            }
+        }
+
+        let (target, status) = LinuxTarget::launch_cstrings(
+            CString::new(path)?,
+            &args,
+            env.as_deref(),
+            stdio,
+            options.kill_on_exit,
+        )?;
+        let [stdin, stdout, stderr] = pipes;
+        Ok((
+            target,
+            status,
+            DebuggeePipes {
+                stdin,
+                stdout,
+                stderr,
+            },
+        ))
+    }
+
+    fn launch_cstrings(
+        path: CString,
+        args: &[CString],
+        env: Option<&[CString]>,
+        stdio: [Option<RawFd>; 3],
+        kill_on_exit: bool,
+    ) -> Result<(LinuxTarget, nix::sys::wait::WaitStatus)> {
+        let (pid, status) = unix::launch(path, args, env, stdio)?;
+        let mut target = LinuxTarget::new(pid);
+        if kill_on_exit {
+            target.kill_on_exit()?;
+        }
+        Ok((target, status))
+    }
+
+    /// Attaches process as a debuggee.
+    pub fn attach(
+        pid: Pid,
+        options: AttachOptions,
+    ) -> Result<(LinuxTarget, nix::sys::wait::WaitStatus)> {
+        let status = unix::attach(pid)?;
+        let mut target = LinuxTarget::new(pid);
+
+        if options.kill_on_exit {
+            target.kill_on_exit()?;
+        }
+        if options.all_threads {
+            target.attach_threads(false)?;
+        }
+
+        Ok((target, status))
+    }
+
+    /// Attaches to the running process `pid` with `PTRACE_SEIZE`, without stopping it.
+    ///
+    /// Unlike with `attach`, no `SIGSTOP` is sent, so the process keeps running until it hits a
+    /// breakpoint, gets a signal or is stopped with `interrupt`. Its registers and memory can only
+    /// be accessed while it's stopped. The ptrace options are set together with the attach
+    /// instead of afterwards. Stops caused by a `SIGSTOP` someone else sends the debuggee are
+    /// reported as `WaitStatus::PtraceEvent` with `PTRACE_EVENT_STOP` rather than looking like the
+    /// delivery of the signal, and so are the first stops of threads created later with
+    /// `AttachOptions::all_threads`.
+    pub fn seize(pid: Pid, options: AttachOptions) -> Result<LinuxTarget> {
+        let mut ptrace_options = ptrace::Options::empty();
+        if options.kill_on_exit {
+            ptrace_options |= ptrace::Options::PTRACE_O_EXITKILL;
+        }
+        if options.all_threads {
+            ptrace_options |= ptrace::Options::PTRACE_O_TRACECLONE;
+        }
+
+        ptrace::seize(pid, ptrace_options)?;
+        let mut target = LinuxTarget::new(pid);
+        target.options = ptrace_options;
+        if options.all_threads {
+            target.attach_threads(true)?;
+        }
+        Ok(target)
+    }
+
+    /// Stops the running debuggee with `PTRACE_INTERRUPT`, which only works if it was attached
+    /// to with `seize`, and waits until it's stopped.
+    ///
+    /// The debuggee stops with `WaitStatus::PtraceEvent(pid, SIGTRAP, PTRACE_EVENT_STOP)`,
+    /// unless it happens to stop for another reason first. It has to be running, otherwise
+    /// there's no stop to wait for.
+    pub fn interrupt(&self) -> Result<nix::sys::wait::WaitStatus> {
+        unsafe {
+            let ret = libc::ptrace(
+                libc::PTRACE_INTERRUPT,
+                libc::pid_t::from(self.pid),
+                std::ptr::null_mut::<libc::c_void>(),
+                std::ptr::null_mut::<libc::c_void>(),
+            );
+            nix::errno::Errno::result(ret)?;
+        }
+        Ok(nix::sys::wait::waitpid(self.pid, None)?)
+    }
+
+    /// Attaches to all threads of the debuggee that aren't traced yet and makes new threads be
+    /// traced automatically, see `AttachOptions::all_threads`.
+    ///
+    /// The list of threads is read again until no new thread shows up, as the threads that
+    /// aren't stopped yet can create more of them in the meantime.
+    ///
+    /// With `seize` the threads are attached to with `PTRACE_SEIZE` and keep running, like the
+    /// debuggee itself, which already has to be seized with `PTRACE_O_TRACECLONE`.
+    fn attach_threads(&mut self, seize: bool) -> Result<()> {
+        use nix::sys::wait::{waitpid, WaitPidFlag, WaitStatus};
+
+        if !seize {
+            self.set_options(self.options | ptrace::Options::PTRACE_O_TRACECLONE)?;
+        }
+        loop {
+            let mut attached_any = false;
+            for task in Process::new(self.pid.as_raw())?.tasks()?.flatten() {
+                let tid = task.tid;
+                if tid == self.pid.as_raw() || self.traced_threads.contains(&tid) {
+                    continue;
+                }
+
+                let thread = Pid::from_raw(tid);
+                let result = if seize {
+                    ptrace::seize(thread, self.options)
+                } else {
+                    ptrace::attach(thread)
+                };
+                match result {
+                    Ok(()) => {}
+                    Err(nix::Error::Sys(nix::errno::Errno::ESRCH)) => continue,
+                    Err(err) => return Err(err.into()),
+                }
+                if !seize {
+                    match waitpid(thread, Some(WaitPidFlag::__WALL)) {
+                        Ok(WaitStatus::Exited(..)) | Ok(WaitStatus::Signaled(..)) => continue,
+                        Ok(_) => {}
+                        Err(nix::Error::Sys(nix::errno::Errno::ECHILD)) => continue,
+                        Err(err) => return Err(err.into()),
+                    }
+                    ptrace::setoptions(thread, self.options)?;
+                }
+                self.traced_threads.push(tid);
+                attached_any = true;
+            }
+            if !attached_any {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Attaches to every process called `name`, see `attach`.
+    ///
+    /// A process matches if `name` is its `comm`, the name the kernel reports for it, which is
+    /// truncated to 15 bytes, or the file name of its `argv[0]`. This process never matches.
+    /// If attaching to one of the matches fails, the ones attached so far are detached again and
+    /// the error is returned. Returns an empty list if no process matches.
+    pub fn attach_by_name(name: &str, options: AttachOptions) -> Result<Vec<LinuxTarget>> {
+        let me = getpid().as_raw();
+        let pids: Vec<_> = procfs::process::all_processes()?
+            .into_iter()
+            .filter(|process| process.pid() != me)
+            .filter(|process| {
+                if process.stat.comm == name {
+                    return true;
+                }
+                match process.cmdline() {
+                    Ok(cmdline) => match cmdline.first() {
+                        Some(arg) => Path::new(arg).file_name() == Some(name.as_ref()),
+                        None => false,
+                    },
+                    Err(_) => false,
+                }
+            })
+            .map(|process| Pid::from_raw(process.pid()))
+            .collect();
+
+        let mut targets = Vec::with_capacity(pids.len());
+        for pid in pids {
+            match LinuxTarget::attach(pid, options) {
+                Ok((target, _status)) => targets.push(target),
+                Err(err) => {
+                    for target in targets {
+                        let _ = target.detach();
+                    }
+                    return Err(err);
+                }
+            }
+        }
+        Ok(targets)
+    }
+
+    /// Uses this process as a debuggee.
+    pub fn me() -> LinuxTarget {
+        LinuxTarget::new(getpid())
+    }
+
+    /// Reads memory from a debuggee process.
+    pub fn read(&self) -> ReadMemory {
+        ReadMemory::new(&self)
+    }
+
+    /// Writes memory to a debuggee process.
+    pub fn write(&self) -> WriteMemory {
+        WriteMemory::new(&self)
+    }
+
+    /// Returns `/proc/<pid>/mem` of the debuggee, opening it on first use.
+    fn mem_file(&self) -> Result<Ref<'_, File>> {
+        if self.mem_file.borrow().is_none() {
+            let file = std::fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open(format!("/proc/{}/mem", self.pid))?;
+            self.mem_file.replace(Some(file));
+        }
+        Ok(Ref::map(self.mem_file.borrow(), |file| {
+            file.as_ref().unwrap()
+        }))
+    }
+
+    /// Continues execution of a debuggee and waits for it to stop again.
+    ///
+    /// When the debuggee is stopped on a signal, it is only delivered if it's passed as `signal`.
+    /// Passing the signal the debuggee stopped on forwards it, while `None` swallows it, see
+    /// `cont_suppressing_signal`. Any other signal can be injected this way too.
+    ///
+    /// This doesn't change the debuggee's relation with the debugger: if it was attached with
+    /// `kill_on_exit`, it still gets killed when the debugger exits while the debuggee runs.
+    pub fn cont(
+        &self,
+        signal: Option<nix::sys::signal::Signal>,
+    ) -> Result<nix::sys::wait::WaitStatus> {
+        ptrace::cont(self.pid, signal)?;
+        let status = nix::sys::wait::waitpid(self.pid, None)?;
+        Ok(status)
+    }
+
+    /// Continues execution of a debuggee without delivering the signal it's stopped on, and
+    /// waits for it to stop again.
+    ///
+    /// The signal is discarded as if it had never been sent: the debuggee's handlers don't run
+    /// and the default action, like terminating the process, isn't taken. This is the same as
+    /// `cont(None)`. `siginfo` can be used beforehand to decide whether a signal should be
+    /// suppressed.
+    pub fn cont_suppressing_signal(&self) -> Result<nix::sys::wait::WaitStatus> {
+        self.cont(None)
+    }
+
+    /// Reads `len` bytes from debuggee's memory at location `addr`.
+    /// This is a shortcut for reading a byte slice with `ReadMemory`.
+    pub fn read_bytes(&self, addr: usize, len: usize) -> Result<Vec<u8>> {
+        let mut bytes = vec![0; len];
+        self.read().read_byte_slice(&mut bytes, addr).apply()?;
+        Ok(bytes)
+    }
+
+    /// Reads a value of type `T` from debuggee's memory at location `addr`.
+    ///
+    /// The `Pod` bound makes sure that any bytes read are a valid `T`, so no unsafe code is
+    /// needed. Values are read in the native byte order, which is also the debuggee's.
+    pub fn read_struct<T: bytemuck::Pod>(&self, addr: usize) -> Result<T> {
+        let mut val = T::zeroed();
+        self.read()
+            .read_byte_slice(bytemuck::bytes_of_mut(&mut val), addr)
+            .apply()?;
+        Ok(val)
+    }
+
+    /// Reads a value of type `T` from the thread-local storage of the thread `tid`, at `offset`
+    /// bytes from its thread pointer, which is the `fs_base` register.
+    ///
+    /// On x86_64 the TLS blocks of the executable and of the libraries loaded at startup are
+    /// right below the thread pointer, so their variables have negative offsets.
+    /// The thread has to be traced and stopped, see `threads` for getting the thread ids.
+    #[cfg(target_arch = "x86_64")]
+    pub fn read_tls<T: bytemuck::Pod>(&self, tid: i32, offset: isize) -> Result<T> {
+        let thread_pointer = self.read_thread_regs(tid)?.fs_base as usize;
+        self.read_struct(thread_pointer.wrapping_add(offset as usize))
+    }
+
+    /// Writes `bytes` to debuggee's memory at location `addr`.
+    /// This is a shortcut for writing a byte slice with `WriteMemory`.
+    pub fn write_bytes(&self, addr: usize, bytes: &[u8]) -> Result<()> {
+        self.write().write_slice(bytes, addr).apply()
+    }
+
+    /// Reads a little-endian `u32` from debuggee's memory at location `addr`.
+    pub fn read_u32_le(&self, addr: usize) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.read_struct(addr)?))
+    }
+
+    /// Reads a little-endian `u64` from debuggee's memory at location `addr`.
+    pub fn read_u64_le(&self, addr: usize) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.read_struct(addr)?))
+    }
+
+    /// Reads a pointer from debuggee's memory at location `addr`, in the native size and byte
+    /// order, e.g. to follow a chain of pointers: `read_ptr(read_ptr(addr)? + offset)`.
+    pub fn read_ptr(&self, addr: usize) -> Result<usize> {
+        self.read_struct(addr)
+    }
+
+    /// Follows a chain of pointers starting with the one at `base`: each offset is added to the
+    /// last pointer read, and the pointer at the resulting address is read next, except for the
+    /// last offset. Returns the address of the last offset, e.g. of a field to read, or the
+    /// pointer at `base` if there are no offsets.
+    ///
+    /// Fails with `Error::BrokenChain` if one of the pointers can't be read, e.g. because it's
+    /// null or unmapped.
+    pub fn follow_chain(&self, base: usize, offsets: &[isize]) -> Result<usize> {
+        let read = |level: usize, addr: usize| {
+            self.read_ptr(addr).map_err(|err| Error::BrokenChain {
+                level,
+                addr,
+                source: Box::new(err),
+            })
+        };
+
+        let mut ptr = read(0, base)?;
+        for (level, &offset) in offsets.iter().enumerate() {
+            let addr = ptr.wrapping_add(offset as usize);
+            if level + 1 == offsets.len() {
+                return Ok(addr);
+            }
+            ptr = read(level + 1, addr)?;
+        }
+        Ok(ptr)
+    }
+
+    /// Writes `value` as a little-endian `u32` to debuggee's memory at location `addr`.
+    pub fn write_u32_le(&self, addr: usize, value: u32) -> Result<()> {
+        self.write_bytes(addr, &value.to_le_bytes())
+    }
+
+    /// Writes `value` as a little-endian `u64` to debuggee's memory at location `addr`.
+    pub fn write_u64_le(&self, addr: usize, value: u64) -> Result<()> {
+        self.write_bytes(addr, &value.to_le_bytes())
+    }
+
+    /// Writes the pointer `value` to debuggee's memory at location `addr`, see `read_ptr`.
+    pub fn write_ptr(&self, addr: usize, value: usize) -> Result<()> {
+        self.write_bytes(addr, &value.to_ne_bytes())
+    }
+
+    /// Reads a NUL-terminated string from debuggee's memory at location `addr`.
+    ///
+    /// Memory is read one page at a time until a NUL byte is found or `max_len` bytes are read.
+    /// If one of the following pages can't be read, the part of the string read so far is
+    /// returned. Invalid UTF-8 sequences are replaced with `U+FFFD`.
+    pub fn read_string(&self, addr: usize, max_len: usize) -> Result<String> {
+        let mut bytes = Vec::new();
+        let mut buf = vec![0u8; *PAGE_SIZE];
+
+        while bytes.len() < max_len {
+            let chunk_addr = addr + bytes.len();
+            // Read until the end of the page to avoid touching the next one if it's not needed.
+            let chunk_len =
+                std::cmp::min(*PAGE_SIZE - chunk_addr % *PAGE_SIZE, max_len - bytes.len());
+            let chunk = &mut buf[..chunk_len];
+
+            if let Err(err) = self.read().read_byte_slice(chunk, chunk_addr).apply() {
+                if bytes.is_empty() {
+                    return Err(err);
+                }
+                break;
+            }
+
+            if let Some(nul_pos) = chunk.iter().position(|&byte| byte == 0) {
+                bytes.extend_from_slice(&chunk[..nul_pos]);
+                break;
+            }
+            bytes.extend_from_slice(chunk);
+        }
+
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    /// Disassembles up to `count` instructions starting at `addr`.
+    ///
+    /// Software breakpoints set with `set_breakpoint` are hidden: the original instruction is
+    /// decoded instead of the `int3` replacing it. Fewer instructions are returned if the code
+    /// runs into unreadable memory or an invalid instruction.
+    #[cfg(target_arch = "x86_64")]
+    pub fn disassemble(
+        &self,
+        addr: usize,
+        count: usize,
+    ) -> Result<Vec<crate::symbol::Instruction>> {
+        let mut buf = vec![std::mem::MaybeUninit::<u8>::uninit(); count * MAX_INSTRUCTION_LEN];
+        let read_len = self
+            .read()
+            .hide_breakpoints()
+            .read_slice_uninit(&mut buf, addr)
+            .apply_uninit()?;
+        // `apply_uninit` initialized this many bytes.
+        let bytes =
+            unsafe { &*(&buf[..read_len] as *const [std::mem::MaybeUninit<u8>] as *const [u8]) };
+
+        crate::symbol::DisassemblySource::new().disassemble(bytes, addr, count)
+    }
+
+    /// Reads the register values from the main thread of a debuggee process.
+    pub fn read_regs(&self) -> Result<libc::user_regs_struct> {
+        self.read_thread_regs(self.pid.as_raw())
+    }
+
+    /// Reads the general purpose registers of the debuggee.
+    pub fn read_registers(&self) -> Result<super::Registers> {
+        Ok(self.read_regs()?.into())
+    }
+
+    /// Writes the register values for the main thread of a debuggee process.
+    pub fn write_regs(&self, regs: libc::user_regs_struct) -> Result<()> {
+        self.write_thread_regs(self.pid.as_raw(), regs)
+    }
+
+    /// Reads the register values from the thread `tid` of a debuggee process.
+    /// The thread has to be traced and stopped, see `threads` for getting the thread ids.
+    #[cfg(target_arch = "x86_64")]
+    pub fn read_thread_regs(&self, tid: i32) -> Result<libc::user_regs_struct> {
+        nix::sys::ptrace::getregs(Pid::from_raw(tid)).map_err(|err| err.into())
+    }
+
+    /// Reads the register values from the thread `tid` of a debuggee process.
+    /// The thread has to be traced and stopped, see `threads` for getting the thread ids.
+    #[cfg(target_arch = "aarch64")]
+    pub fn read_thread_regs(&self, tid: i32) -> Result<libc::user_regs_struct> {
+        // nix only wraps PTRACE_GETREGS, which doesn't exist on aarch64
+        let mut regs: libc::user_regs_struct = unsafe { std::mem::zeroed() };
+        let mut iov = libc::iovec {
+            iov_base: &mut regs as *mut _ as *mut libc::c_void,
+            iov_len: std::mem::size_of::<libc::user_regs_struct>(),
+        };
+        unsafe {
+            let ret = libc::ptrace(
+                libc::PTRACE_GETREGSET,
+                tid,
+                libc::NT_PRSTATUS as *mut libc::c_void,
+                &mut iov as *mut _ as *mut libc::c_void,
+            );
+            nix::errno::Errno::result(ret)?;
+        }
+        Ok(regs)
+    }
+
+    /// Writes the register values for the thread `tid` of a debuggee process.
+    /// The thread has to be traced and stopped, see `threads` for getting the thread ids.
+    #[cfg(target_arch = "x86_64")]
+    pub fn write_thread_regs(&self, tid: i32, regs: libc::user_regs_struct) -> Result<()> {
+        nix::sys::ptrace::setregs(Pid::from_raw(tid), regs).map_err(|err| err.into())
+    }
+
+    /// Writes the register values for the thread `tid` of a debuggee process.
+    /// The thread has to be traced and stopped, see `threads` for getting the thread ids.
+    #[cfg(target_arch = "aarch64")]
+    pub fn write_thread_regs(&self, tid: i32, mut regs: libc::user_regs_struct) -> Result<()> {
+        let mut iov = libc::iovec {
+            iov_base: &mut regs as *mut _ as *mut libc::c_void,
+            iov_len: std::mem::size_of::<libc::user_regs_struct>(),
+        };
+        unsafe {
+            let ret = libc::ptrace(
+                libc::PTRACE_SETREGSET,
+                tid,
+                libc::NT_PRSTATUS as *mut libc::c_void,
+                &mut iov as *mut _ as *mut libc::c_void,
+            );
+            nix::errno::Errno::result(ret)?;
+        }
+        Ok(())
+    }
+
+    /// Reads the status flags of the main thread of a debuggee process.
+    #[cfg(target_arch = "x86_64")]
+    pub fn read_flags(&self) -> Result<super::Flags> {
+        Ok(super::Flags::from_bits(self.read_regs()?.eflags))
+    }
+
+    /// Writes the status flags of the main thread of a debuggee process.
+    ///
+    /// The kernel ignores changes to flags that user space can't change, like IF.
+    #[cfg(target_arch = "x86_64")]
+    pub fn write_flags(&self, flags: super::Flags) -> Result<()> {
+        let mut regs = self.read_regs()?;
+        regs.eflags = flags.bits();
+        self.write_regs(regs)
+    }
+
+    /// Reads the segment selectors of the main thread of a debuggee process.
+    #[cfg(target_arch = "x86_64")]
+    pub fn read_segment_registers(&self) -> Result<super::SegmentRegisters> {
+        Ok(self.read_regs()?.into())
+    }
+
+    /// Writes the segment selectors of the main thread of a debuggee process.
+    ///
+    /// The kernel only accepts selectors user space could load itself, e.g. `cs` has to be one
+    /// of `SegmentRegisters::USER_CS` and `SegmentRegisters::USER32_CS`, and fails otherwise.
+    #[cfg(target_arch = "x86_64")]
+    pub fn write_segment_registers(&self, segments: super::SegmentRegisters) -> Result<()> {
+        let mut regs = self.read_regs()?;
+        segments.write_to(&mut regs);
+        self.write_regs(regs)
+    }
+
+    /// Checks whether the main thread of a debuggee process runs 64-bit code, rather than
+    /// 32-bit code in compatibility mode, by its `cs` selector.
+    #[cfg(target_arch = "x86_64")]
+    pub fn is_64bit_mode(&self) -> Result<bool> {
+        Ok(self.read_segment_registers()?.is_64bit_mode())
+    }
+
+    /// Checks whether the executable of the debuggee is a 32-bit ELF file, by the class in its
+    /// header.
+    ///
+    /// Unlike `is_64bit_mode`, this doesn't depend on the code currently running, but it's also
+    /// true for executables of the x32 ABI, which run in 64-bit mode.
+    pub fn is_32bit_executable(&self) -> Result<bool> {
+        use std::io::Read;
+
+        let mut ident = [0u8; 5];
+        File::open(format!("/proc/{}/exe", self.pid))?.read_exact(&mut ident)?;
+        if ident[..4] != object::elf::ELFMAG {
+            return Err(Error::Other(format!(
+                "The executable of {} is not an ELF file",
+                self.pid
+            )));
+        }
+        Ok(ident[4] == object::elf::ELFCLASS32)
+    }
+
+    /// Reads the registers of the main thread of a debuggee process running 32-bit code.
+    ///
+    /// The kernel gives these in the i386 layout with `PTRACE_GETREGSET`, while `read_regs`
+    /// returns them zero-extended to the x86_64 layout. Fails if the thread runs 64-bit code,
+    /// see `is_64bit_mode`.
+    #[cfg(target_arch = "x86_64")]
+    pub fn read_regs32(&self) -> Result<Registers32> {
+        // Big enough for the registers of 64-bit code, which are returned for those threads.
+        let mut buf = [0u8; std::mem::size_of::<libc::user_regs_struct>()];
+        let mut iov = libc::iovec {
+            iov_base: buf.as_mut_ptr() as *mut libc::c_void,
+            iov_len: buf.len(),
+        };
+        unsafe {
+            let ret = libc::ptrace(
+                libc::PTRACE_GETREGSET,
+                libc::pid_t::from(self.pid),
+                object::elf::NT_PRSTATUS as usize as *mut libc::c_void,
+                &mut iov as *mut _ as *mut libc::c_void,
+            );
+            nix::errno::Errno::result(ret)?;
+        }
+        if iov.iov_len != std::mem::size_of::<Registers32>() {
+            return Err(Error::Other(format!(
+                "{} doesn't run 32-bit code",
+                self.pid
+            )));
+        }
+
+        // Safety: `Registers32` only consists of integers, any bytes are valid.
+        Ok(unsafe { std::ptr::read_unaligned(buf.as_ptr() as *const Registers32) })
+    }
+
+    /// Sets or clears the trap flag of the thread `tid`, so that continuing it, e.g. with
+    /// `ptrace::cont`, stops it with a `SIGTRAP` after every instruction until it's cleared
+    /// again.
+    ///
+    /// Unlike `ptrace::step`, which only steps the thread for a single resumption and hides the
+    /// flag from the debuggee, the flag stays set across stops and can be seen by the debuggee,
+    /// e.g. with `pushf`. Setting it on one thread lets that thread be stepped while the others
+    /// are continued normally.
+    /// The thread has to be traced and stopped, see `threads` for getting the thread ids.
+    #[cfg(target_arch = "x86_64")]
+    pub fn set_single_step(&self, tid: i32, enabled: bool) -> Result<()> {
+        let mut regs = self.read_thread_regs(tid)?;
+        let mut flags = super::Flags::from_bits(regs.eflags);
+        flags.trap = enabled;
+        regs.eflags = flags.bits();
+        self.write_thread_regs(tid, regs)
+    }
+
+    /// Reads the register called `name` from the main thread of a debuggee process. Names are
+    /// the ones of the `user_regs_struct` fields, like `"rax"`, `"rip"` or `"eflags"`.
+    ///
+    /// Returns `None` if there is no register called `name`.
+    #[cfg(target_arch = "x86_64")]
+    pub fn register_by_name(&self, name: &str) -> Result<Option<u64>> {
+        let mut regs = self.read_regs()?;
+        Ok(user_register_mut(&mut regs, name).map(|reg| *reg))
+    }
+
+    /// Writes `value` to the register called `name` of the main thread of a debuggee process,
+    /// see `register_by_name`.
+    #[cfg(target_arch = "x86_64")]
+    pub fn set_register_by_name(&self, name: &str, value: u64) -> Result<()> {
+        let mut regs = self.read_regs()?;
+        *user_register_mut(&mut regs, name)
+            .ok_or_else(|| Error::UnknownRegister(name.to_string()))? = value;
+        self.write_regs(regs)
+    }
+
+    /// Reads the floating point and SSE register values from the main thread of a debuggee process.
+    #[cfg(target_arch = "x86_64")]
+    pub fn read_fpregs(&self) -> Result<libc::user_fpregs_struct> {
+        let mut fpregs = std::mem::MaybeUninit::<libc::user_fpregs_struct>::uninit();
+        // Have to use libc because nix doesn't provide a wrapper for PTRACE_GETFPREGS
+        let ret = unsafe {
+            libc::ptrace(
+                libc::PTRACE_GETFPREGS,
+                libc::pid_t::from(self.pid),
+                std::ptr::null_mut::<libc::c_void>(),
+                fpregs.as_mut_ptr(),
+            )
+        };
+        nix::errno::Errno::result(ret)?;
+        // Safety: the kernel has filled the whole struct when PTRACE_GETFPREGS succeeds.
+        Ok(unsafe { fpregs.assume_init() })
+    }
+
+    /// Writes the floating point and SSE register values for the main thread of a debuggee process.
+    #[cfg(target_arch = "x86_64")]
+    pub fn write_fpregs(&self, fpregs: libc::user_fpregs_struct) -> Result<()> {
+        // Have to use libc because nix doesn't provide a wrapper for PTRACE_SETFPREGS
+        let ret = unsafe {
+            libc::ptrace(
+                libc::PTRACE_SETFPREGS,
+                libc::pid_t::from(self.pid),
+                std::ptr::null_mut::<libc::c_void>(),
+                &fpregs as *const libc::user_fpregs_struct,
+            )
+        };
+        nix::errno::Errno::result(ret)?;
+        Ok(())
+    }
+
+    /// Let the debuggee process execute the specified syscall.
+    #[cfg(target_arch = "x86_64")]
+    pub fn syscall(
+        &self,
+        num: libc::c_ulonglong,
+        arg1: libc::c_ulonglong,
+        arg2: libc::c_ulonglong,
+        arg3: libc::c_ulonglong,
+        arg4: libc::c_ulonglong,
+        arg5: libc::c_ulonglong,
+        arg6: libc::c_ulonglong,
+    ) -> Result<libc::c_ulonglong> {
+        // Write arguments
+        let orig_regs = self.read_regs()?;
+        let mut new_regs = orig_regs.clone();
+        new_regs.rax = num;
+        new_regs.rdi = arg1;
+        new_regs.rsi = arg2;
+        new_regs.rdx = arg3;
+        new_regs.r10 = arg4;
+        new_regs.r8 = arg5;
+        new_regs.r9 = arg6;
+
+        match self.syscall_site()? {
+            Some(site) => {
+                // Run an existing syscall instruction, so no code has to be patched
+                new_regs.rip = site as u64;
+                self.write_regs(new_regs)?;
+
+                nix::sys::ptrace::step(self.pid(), None)?;
+                nix::sys::wait::waitpid(self.pid(), None)?;
+            }
+            None => {
+                self.write_regs(new_regs)?;
+
+                // Write syscall instruction
+                let old_inst = nix::sys::ptrace::read(self.pid(), new_regs.rip as *mut _)?;
+                nix::sys::ptrace::write(
+                    self.pid(),
+                    new_regs.rip as *mut _,
+                    0x050f/*x86_64 syscall*/ as *mut _,
+                )?;
+
+                // Perform syscall
+                nix::sys::ptrace::step(self.pid(), None)?;
+                nix::sys::wait::waitpid(self.pid(), None)?;
+
+                // Restore old code
+                nix::sys::ptrace::write(self.pid(), new_regs.rip as *mut _, old_inst as *mut _)?;
+            }
+        }
+
+        // Read return value
+        let res = self.read_regs()?.rax;
+
+        // Restore old registers
+        self.write_regs(orig_regs)?;
+
+        Ok(res)
+    }
+
+    /// Calls the function at `addr` in the debuggee with up to six integer `args` following the
+    /// System V calling convention, and returns the value it left in `rax`.
+    ///
+    /// The function returns to an `int3` temporarily placed at the current instruction pointer.
+    /// All registers and the patched code are restored afterwards, as if nothing happened. The
+    /// called function must return normally: any other stop is reported as an error.
+    #[cfg(target_arch = "x86_64")]
+    pub fn call_function(&self, addr: usize, args: &[u64]) -> Result<u64> {
+        if args.len() > 6 {
+            return Err(Error::Other(format!(
+                "Functions can only be called with up to 6 arguments, got {}",
+                args.len()
+            )));
+        }
+
+        let orig_regs = self.read_regs()?;
+        let mut new_regs = orig_regs;
+        let mut arg_regs = [
+            &mut new_regs.rdi,
+            &mut new_regs.rsi,
+            &mut new_regs.rdx,
+            &mut new_regs.rcx,
+            &mut new_regs.r8,
+            &mut new_regs.r9,
+        ];
+        for (reg, &arg) in arg_regs.iter_mut().zip(args) {
+            **reg = arg;
+        }
+
+        // Skip the red zone and align the stack, so that it's 16 byte aligned again once the
+        // called function pushes `rbp` on top of the return address.
+        let return_addr = orig_regs.rip;
+        let stack_ptr = ((orig_regs.rsp - 128) & !0xf) - 8;
+        self.write_bytes(stack_ptr as usize, &return_addr.to_ne_bytes())?;
+        new_regs.rsp = stack_ptr;
+        new_regs.rip = addr as u64;
+        // No vector registers are used by variadic functions.
+        new_regs.rax = 0;
+        // Don't let the kernel restart an interrupted syscall at the new `rip`.
+        new_regs.orig_rax = u64::MAX;
+
+        let orig_byte = self.patch_byte(return_addr as usize, software_breakpoint::INT3)?;
+        self.write_regs(new_regs)?;
+
+        let status = self.cont(None);
+        let result_regs = self.read_regs();
+
+        // Restore old code and registers
+        self.patch_byte(return_addr as usize, orig_byte)?;
+        self.write_regs(orig_regs)?;
+
+        match status? {
+            nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+            status => return Err(Error::UnexpectedWaitStatus(status)),
+        }
+        let result_regs = result_regs?;
+        if result_regs.rip != return_addr + 1 {
+            return Err(Error::Other(format!(
+                "Called function stopped at 0x{:x} instead of returning",
+                result_regs.rip
+            )));
+        }
+
+        Ok(result_regs.rax)
+    }
+
+    /// Forgets the address of the existing `syscall` instruction that `syscall` runs, so it's
+    /// searched for again by the next call.
+    ///
+    /// `wait` does this when it reports an `execve`, and `munmap` and `mprotect` when they
+    /// change the pages of the instruction. Call it when the debuggee replaced its image or
+    /// unmapped its code in any other way, e.g. when stops are handled with `waitpid` instead of
+    /// `wait`, as the old address may not point to a `syscall` anymore.
+    pub fn invalidate_syscall_site(&self) {
+        self.syscall_site.set(None);
+    }
+
+    /// Forgets the cached `syscall` instruction if it's in the pages containing
+    /// `addr..addr + length`.
+    #[cfg(target_arch = "x86_64")]
+    fn invalidate_syscall_site_in(&self, addr: usize, length: usize) {
+        let in_range = match (self.syscall_site.get(), page_range(addr, length)) {
+            (Some(site), Some(pages)) => pages.contains(&site),
+            _ => false,
+        };
+        if in_range {
+            self.invalidate_syscall_site();
+        }
+    }
+
+    /// Finds an existing `syscall` instruction in the executable mappings of the debuggee.
+    /// The address is cached once found, see `invalidate_syscall_site`.
+    #[cfg(target_arch = "x86_64")]
+    fn syscall_site(&self) -> Result<Option<usize>> {
+        if let Some(site) = self.syscall_site.get() {
+            return Ok(Some(site));
+        }
+
+        for map in self.memory_maps()? {
+            // Only look at mapped files: `[vsyscall]` can't be read and jumping into it faults.
+            if !map.is_readable || !map.is_executable || map.backing_file.is_none() {
+                continue;
+            }
+            let code = match self.read_bytes(
+                map.address.0 as usize,
+                (map.address.1 - map.address.0) as usize,
+            ) {
+                Ok(code) => code,
+                Err(_) => continue,
+            };
+            if let Some(offset) = code.windows(2).position(|inst| inst == [0x0f, 0x05]) {
+                let site = map.address.0 as usize + offset;
+                self.syscall_site.set(Some(site));
+                return Ok(Some(site));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Returns the thread pointer of the thread `tid`, which locates its thread local storage.
+    ///
+    /// On x86_64 this is `fs_base`, which is read from the thread's registers. Kernels that leave
+    /// it out of the registers report 0, in which case the main thread is asked for it with an
+    /// injected `arch_prctl(ARCH_GET_FS)`. The thread has to be traced and stopped.
+    #[cfg(target_arch = "x86_64")]
+    pub fn thread_pointer(&self, tid: i32) -> Result<u64> {
+        let fs_base = self.read_thread_regs(tid)?.fs_base;
+        if fs_base != 0 || tid != self.pid.as_raw() {
+            return Ok(fs_base);
+        }
+        self.arch_get_fs()
+    }
+
+    /// Returns `fs_base` of the main thread as reported by `arch_prctl(ARCH_GET_FS)`.
+    #[cfg(target_arch = "x86_64")]
+    fn arch_get_fs(&self) -> Result<u64> {
+        const ARCH_GET_FS: u64 = 0x1003;
+
+        // `arch_prctl` stores the result in memory, use the stack below the red zone for it.
+        let addr = ((self.read_regs()?.rsp - 256) & !7) as usize;
+        let mut saved = 0u64;
+        unsafe { self.read().read(&mut saved, addr).apply()? };
+
+        let res = self.syscall(
+            libc::SYS_arch_prctl as _,
+            ARCH_GET_FS,
+            addr as u64,
+            0,
+            0,
+            0,
+            0,
+        )?;
+        let mut fs_base = 0u64;
+        unsafe { self.read().read(&mut fs_base, addr).apply()? };
+        self.write().write(&saved, addr).apply()?;
+
+        if res != 0 {
+            return Err(Error::Other(format!(
+                "arch_prctl(ARCH_GET_FS) failed: {}",
+                res as i64
+            )));
+        }
+        Ok(fs_base)
+    }
+
+    /// Let the debuggee process map memory.
+    #[cfg(target_arch = "x86_64")]
+    pub fn mmap(
+        &self,
+        addr: *mut libc::c_void,
+        length: libc::size_t,
+        prot: libc::c_int,
+        flags: libc::c_int,
+        fd: libc::c_int,
+        offset: libc::off_t,
+    ) -> Result<libc::c_ulonglong> {
+        self.syscall(
+            libc::SYS_mmap as _,
+            addr as _,
+            length as _,
+            prot as _,
+            flags as _,
+            fd as _,
+            offset as _,
+        )
+    }
+
+    /// Unmaps `length` bytes of the debuggee's memory at `addr`, returning the raw result of the
+    /// syscall.
+    ///
+    /// Both `addr` and `length` have to be page aligned and `length` can't be zero.
+    #[cfg(target_arch = "x86_64")]
+    pub fn munmap(
+        &self,
+        addr: *mut libc::c_void,
+        length: libc::size_t,
+    ) -> Result<libc::c_ulonglong> {
+        if addr as usize & (*PAGE_SIZE - 1) != 0 {
+            return Err(Error::NotPageAligned(addr as usize));
+        }
+        if length == 0 || length & (*PAGE_SIZE - 1) != 0 {
+            return Err(Error::Other(format!(
+                "Length 0x{:x} is not a multiple of the page size",
+                length
+            )));
+        }
+
+        let res = self.syscall(libc::SYS_munmap as _, addr as _, length as _, 0, 0, 0, 0)?;
+        if res == 0 {
+            self.invalidate_syscall_site_in(addr as usize, length);
+        }
+        Ok(res)
+    }
+
+    /// Changes the protection of the debuggee's memory pages containing `addr..addr + length`
+    /// to `prot`, returning the raw result of the syscall.
+    ///
+    /// `addr` has to be page aligned.
+    #[cfg(target_arch = "x86_64")]
+    pub fn mprotect(
+        &self,
+        addr: *mut libc::c_void,
+        length: libc::size_t,
+        prot: libc::c_int,
+    ) -> Result<libc::c_ulonglong> {
+        if addr as usize & (*PAGE_SIZE - 1) != 0 {
+            return Err(Error::NotPageAligned(addr as usize));
+        }
+
+        let res = self.syscall(
+            libc::SYS_mprotect as _,
+            addr as _,
+            length as _,
+            prot as _,
+            0,
+            0,
+            0,
+        )?;
+        if res == 0 {
+            self.invalidate_syscall_site_in(addr as usize, length);
+        }
+        Ok(res)
+    }
+
+    /// Reads the command line of the debuggee from `/proc/<pid>/cmdline`, starting with the
+    /// program name. Kernel threads have no command line, so the result is empty for them.
+    pub fn cmdline(&self) -> Result<Vec<String>> {
+        let data = std::fs::read(format!("/proc/{}/cmdline", self.pid))?;
+        Ok(split_nul(&data))
+    }
+
+    /// Reads the environment of the debuggee from `/proc/<pid>/environ`.
+    ///
+    /// This is the environment the debuggee was started with, changes it made later through
+    /// `setenv` aren't visible.
+    pub fn environ(&self) -> Result<HashMap<String, String>> {
+        let data = std::fs::read(format!("/proc/{}/environ", self.pid))?;
+        Ok(split_nul(&data)
+            .into_iter()
+            .filter_map(|var| {
+                let eq_pos = var.find('=')?;
+                Some((var[..eq_pos].to_string(), var[eq_pos + 1..].to_string()))
+            })
+            .collect())
+    }
+
+    /// Lists the open file descriptors of the debuggee from `/proc/<pid>/fd`, ordered by number.
+    ///
+    /// Descriptors that are closed while they're listed are left out, and so are the ones whose
+    /// target can't be read. Listing the descriptors of another user's process fails with a
+    /// permission error.
+    pub fn open_files(&self) -> Result<Vec<OpenFile>> {
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(format!("/proc/{}/fd", self.pid))? {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(_) => continue,
+            };
+            let fd = match entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.parse().ok())
+            {
+                Some(fd) => fd,
+                None => continue,
+            };
+            if let Ok(path) = std::fs::read_link(entry.path()) {
+                files.push(OpenFile { fd, path });
+            }
+        }
+        files.sort_by_key(|file| file.fd);
+        Ok(files)
+    }
+
+    /// Reads the name, parent, thread count and memory usage of the debuggee from
+    /// `/proc/<pid>/status`.
+    pub fn status(&self) -> Result<ProcessStatus> {
+        let status = Process::new(self.pid.as_raw())?.status()?;
+        Ok(ProcessStatus {
+            name: status.name,
+            parent_pid: Pid::from_raw(status.ppid),
+            tracer_pid: match status.tracerpid {
+                0 => None,
+                pid => Some(Pid::from_raw(pid)),
+            },
+            threads: status.threads,
+            // The sizes are in kibibytes.
+            vm_size: status.vmsize.map(|size| size * 1024),
+            vm_rss: status.vmrss.map(|size| size * 1024),
+        })
+    }
+
+    /// Reads the debuggee's auxiliary vector from `/proc/<pid>/auxv`, mapping each `AT_*` type,
+    /// like `libc::AT_ENTRY` or `libc::AT_SYSINFO_EHDR`, to its value.
+    pub fn auxv(&self) -> Result<HashMap<u64, u64>> {
+        let data = std::fs::read(format!("/proc/{}/auxv", self.pid))?;
+        let word_size = std::mem::size_of::<libc::c_ulong>();
+        let mut words = data.chunks_exact(word_size).map(|word| {
+            let mut bytes = [0; 8];
+            bytes[..word_size].copy_from_slice(word);
+            u64::from_ne_bytes(bytes)
+        });
+
+        let mut auxv = HashMap::new();
+        while let (Some(typ), Some(value)) = (words.next(), words.next()) {
+            if typ == libc::AT_NULL {
+                break;
+            }
+            auxv.insert(typ, value);
+        }
+        Ok(auxv)
+    }
+
+    pub fn memory_maps(&self) -> Result<Vec<super::MemoryMap>> {
+        Ok(procfs::process::Process::new(self.pid.as_raw())?
+            .maps()?
+            .into_iter()
+            .map(|map| {
+                let mut perms = map.perms.chars();
+                super::MemoryMap {
+                    address: map.address,
+                    backing_file: match map.pathname {
+                        procfs::process::MMapPath::Path(path) => Some((path, map.offset)),
+                        _ => None,
+                    },
+                    is_readable: perms.next() == Some('r'),
+                    is_writable: perms.next() == Some('w'),
+                    is_executable: perms.next() == Some('x'),
+                    is_private: perms.next() == Some('p'),
+                }
+            })
+            .collect())
+    }
+
+    /// Searches the readable memory maps selected by `regions` for `needle`, returning the
+    /// address of every match in ascending order.
+    ///
+    /// Memory is read one page at a time, keeping the end of the previous page to find matches
+    /// that span two pages. Matches spanning two maps aren't found. Pages that can't be read are
+    /// skipped.
+    pub fn search_memory(&self, needle: &[u8], regions: super::MapFilter) -> Result<Vec<usize>> {
+        self.scan_memory(needle.len(), regions, |window| window == needle)
+    }
+
+    /// Like `search_memory`, but only the bits set in `mask` have to match `pattern`, like in
+    /// the "array of bytes" scans of reverse engineering tools.
+    ///
+    /// A mask byte of `0xff` makes the pattern byte match exactly and `0x00` makes it a wildcard.
+    /// Fails if `pattern` and `mask` differ in length.
+    pub fn search_memory_masked(
+        &self,
+        pattern: &[u8],
+        mask: &[u8],
+        regions: super::MapFilter,
+    ) -> Result<Vec<usize>> {
+        if pattern.len() != mask.len() {
+            return Err(Error::PatternMaskLength {
+                pattern: pattern.len(),
+                mask: mask.len(),
+            });
+        }
+        self.scan_memory(pattern.len(), regions, |window| {
+            window
+                .iter()
+                .zip(pattern)
+                .zip(mask)
+                .all(|((&byte, &pattern_byte), &mask_byte)| (byte ^ pattern_byte) & mask_byte == 0)
+        })
+    }
+
+    /// Returns the address of every `len` bytes in the maps selected by `regions` for which
+    /// `is_match` returns `true`, see `search_memory`.
+    fn scan_memory(
+        &self,
+        len: usize,
+        regions: super::MapFilter,
+        is_match: impl Fn(&[u8]) -> bool,
+    ) -> Result<Vec<usize>> {
+        let mut matches = Vec::new();
+        if len == 0 {
+            return Ok(matches);
+        }
+
+        let mut buf = vec![0u8; len - 1 + *PAGE_SIZE];
+        let maps = self
+            .memory_maps()?
+            .into_iter()
+            .filter(|map| map.is_readable && regions.matches(map));
+        for map in maps {
+            let end = map.address.1 as usize;
+            let mut addr = map.address.0 as usize;
+            // Number of bytes at the start of `buf` kept from the previous page.
+            let mut kept = 0;
+            while addr < end {
+                let chunk_len = std::cmp::min(*PAGE_SIZE, end - addr);
+                let read_len = self
+                    .read()
+                    .read_byte_slice(&mut buf[kept..kept + chunk_len], addr)
+                    .apply_uninit()?;
+                let data = &buf[..kept + read_len];
+                let data_addr = addr - kept;
+                matches.extend(
+                    data.windows(len)
+                        .enumerate()
+                        .filter(|(_, window)| is_match(window))
+                        .map(|(offset, _)| data_addr + offset),
+                );
+
+                if read_len == chunk_len {
+                    let keep = std::cmp::min(len - 1, data.len());
+                    let data_len = data.len();
+                    buf.copy_within(data_len - keep..data_len, 0);
+                    kept = keep;
+                } else {
+                    kept = 0;
+                }
+                addr += chunk_len;
+            }
+        }
+        Ok(matches)
+    }
+
+    /// Returns the executable and the shared libraries mapped into the debuggee, see
+    /// `LoadedObject::from_maps`.
+    pub fn loaded_objects(&self) -> Result<Vec<super::LoadedObject>> {
+        Ok(super::LoadedObject::from_maps(&self.memory_maps()?))
+    }
+
+    /// Returns the vDSO the kernel mapped into the debuggee, as found through
+    /// `libc::AT_SYSINFO_EHDR` in the auxiliary vector, or `None` if there is none.
+    ///
+    /// The vDSO isn't backed by a file, so it's missing from `loaded_objects` and its `path` is
+    /// `[vdso]` like in `/proc/<pid>/maps`. Use `read_vdso_image` to resolve its symbols.
+    pub fn vdso(&self) -> Result<Option<super::LoadedObject>> {
+        Ok(self
+            .auxv()?
+            .get(&libc::AT_SYSINFO_EHDR)
+            .map(|&base| super::LoadedObject {
+                path: PathBuf::from("[vdso]"),
+                base,
+                is_executable: true,
+            }))
+    }
+
+    /// Reads the ELF image of the vDSO from the debuggee's memory, e.g. to pass it to
+    /// `ProcessSymbols::add_image` together with `vdso`.
+    ///
+    /// The size of the image is taken from the ELF header, the section headers come last.
+    pub fn read_vdso_image(&self) -> Result<Option<Vec<u8>>> {
+        use object::read::elf::FileHeader;
+
+        let base = match self.vdso()? {
+            Some(vdso) => vdso.base as usize,
+            None => return Ok(None),
+        };
+        let header_bytes: [u8; 64] = self.read_struct(base)?;
+        let header =
+            object::elf::FileHeader64::<object::NativeEndian>::parse(object::Bytes(&header_bytes))?;
+        let endian = object::NativeEndian;
+        let size = std::cmp::max(
+            header.e_shoff(endian) as usize
+                + header.e_shnum(endian) as usize * header.e_shentsize(endian) as usize,
+            header.e_phoff(endian) as usize
+                + header.e_phnum(endian) as usize * header.e_phentsize(endian) as usize,
+        );
+        Ok(Some(self.read_bytes(base, size)?))
+    }
+
+    /// Captures the general purpose registers and up to `memory_limit` bytes of the writable
+    /// memory of the debuggee, e.g. to find out what a single step changed with `Snapshot::diff`.
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    pub fn snapshot(&self, memory_limit: usize) -> Result<Snapshot> {
+        Snapshot::capture(self, memory_limit)
+    }
+
+    /// Writes an ELF core file of the debuggee to `path`.
+    ///
+    /// The core file has an `NT_PRSTATUS` note with the registers of the main thread and a
+    /// `PT_LOAD` segment for every readable memory map. Parts of a map that can't be read are
+    /// zeroed.
+    /// It can be read back with `CoreTarget`.
+    #[cfg(target_arch = "x86_64")]
+    pub fn write_core_dump(&self, path: &Path) -> Result<()> {
+        core_dump::write_core_dump(self, path)
+    }
+
+    /// Returns the memory map containing `addr`, if any.
+    pub fn map_containing(&self, addr: usize) -> Result<Option<super::MemoryMap>> {
+        Ok(self
+            .memory_maps()?
+            .into_iter()
+            .find(|map| map.contains(addr)))
+    }
+
+    /// Returns the executable memory maps of the debuggee.
+    pub fn executable_maps(&self) -> Result<Vec<super::MemoryMap>> {
+        Ok(self
+            .memory_maps()?
+            .into_iter()
+            .filter(|map| map.is_executable)
+            .collect())
+    }
+
+    /// Returns the load bias of the executable or shared object at `path`: the difference
+    /// between the addresses it's mapped at in the debuggee and the addresses it was linked at.
+    ///
+    /// Adding the bias to a link-time address, like `Symbol::address()`, gives the runtime
+    /// address. It's zero for non-PIE executables. Returns `None` if `path` has no executable
+    /// mapping in the debuggee.
+    pub fn load_bias(&self, path: &Path) -> Result<Option<usize>> {
+        use object::{Object, ObjectSegment};
+
+        // The kernel reports mapped files with symlinks resolved.
+        let path = std::fs::canonicalize(path)?;
+        let map = self
+            .executable_maps()?
+            .into_iter()
+            .find_map(|map| match map.backing_file {
+                Some((file, offset)) if file == path => Some((map.address.0, offset)),
+                _ => None,
+            });
+        let (map_start, offset) = match map {
+            Some(map) => map,
+            None => return Ok(None),
+        };
+
+        let data = std::fs::read(&path)?;
+        let object = object::File::parse(&data)?;
+        // Segments are mapped from the page boundary before their file offset, so the page
+        // with the end of one segment is usually also mapped as the start of the next one.
+        let page_mask = !(*PAGE_SIZE as u64 - 1);
+        let segment = object
+            .segments()
+            .find(|segment| segment.file_range().0 & page_mask == offset)
+            .ok_or_else(|| {
+                Error::Other(format!(
+                    "Couldn't find segment for `{}`+0x{:x}",
+                    path.display(),
+                    offset
+                ))
+            })?;
+        let link_addr = (segment.address() + offset).wrapping_sub(segment.file_range().0);
+        Ok(Some(map_start.wrapping_sub(link_addr) as usize))
+    }
+
+    /// Detaches from the debuggee, letting it run freely.
+    ///
+    /// All software and hardware breakpoints and soft watchpoints are removed first and
+    /// `PTRACE_O_EXITKILL` is disabled, so the debuggee neither hits a stray `int3` or guarded
+    /// page nor gets killed once the debugger exits. If the debuggee is stopped on a software
+    /// breakpoint, use `handle_breakpoint_hit` before detaching to move RIP back onto the
+    /// original instruction.
+    pub fn detach(mut self) -> Result<()> {
+        let addrs: Vec<_> = self.breakpoints.keys().copied().collect();
+        for addr in addrs {
+            self.clear_breakpoint(addr)?;
+        }
+        self.clear_all_hardware_breakpoints()?;
+        #[cfg(target_arch = "x86_64")]
+        let regions: Vec<_> = self
+            .soft_watch_regions
+            .iter()
+            .map(|&(addr, _)| addr)
+            .collect();
+        #[cfg(target_arch = "x86_64")]
+        for addr in regions {
+            self.clear_soft_watch_region(addr)?;
+        }
+
+        if self.options.contains(ptrace::Options::PTRACE_O_EXITKILL) {
+            self.set_options(self.options - ptrace::Options::PTRACE_O_EXITKILL)?;
+        }
+
+        ptrace::detach(self.pid, None)?;
+        Ok(())
+    }
+
+    /// Kill debuggee when debugger exits.
+    fn kill_on_exit(&mut self) -> Result<()> {
+        self.set_options(self.options | ptrace::Options::PTRACE_O_EXITKILL)
+    }
+
+    /// Enables or disables following of the processes and threads created by the debuggee
+    /// with `fork`, `vfork` or `clone`.
+    ///
+    /// When enabled, the new children are traced automatically and the debuggee stops with
+    /// a `WaitStatus::PtraceEvent` every time it creates one. Use [`forked_child`] to get
+    /// a target for the new child.
+    ///
+    /// [`forked_child`]: #method.forked_child
+    pub fn set_fork_follow(&mut self, enabled: bool) -> Result<()> {
+        let fork_options = ptrace::Options::PTRACE_O_TRACEFORK
+            | ptrace::Options::PTRACE_O_TRACEVFORK
+            | ptrace::Options::PTRACE_O_TRACECLONE;
+        if enabled {
+            self.set_options(self.options | fork_options)
+        } else {
+            self.set_options(self.options - fork_options)
+        }
+    }
+
+    /// Returns a target for the child created by the debuggee if `status` is a fork, vfork
+    /// or clone event, or `None` for any other status.
+    ///
+    /// The child is already traced and stopped when this returns. It inherits the ptrace options
+    /// of the debuggee, but not the hardware breakpoints. The software breakpoints are only
+    /// inherited by children with their own copy of the memory, see `follow_child`.
+    pub fn forked_child(&self, status: &nix::sys::wait::WaitStatus) -> Result<Option<LinuxTarget>> {
+        match status {
+            nix::sys::wait::WaitStatus::PtraceEvent(pid, nix::sys::signal::SIGTRAP, event)
+                if *pid == self.pid
+                    && (*event == libc::PTRACE_EVENT_FORK
+                        || *event == libc::PTRACE_EVENT_VFORK
+                        || *event == libc::PTRACE_EVENT_CLONE) => {}
+            _ => return Ok(None),
+        }
+
+        let child_pid = Pid::from_raw(ptrace::getevent(self.pid)? as libc::pid_t);
+        Ok(Some(self.follow_child(child_pid)?))
+    }
+
+    /// Enables or disables stopping the debuggee with [`StopEvent::Exec`] when it calls `execve`.
+    ///
+    /// Without it, a successful `execve` raises a plain `SIGTRAP` in the debuggee instead.
+    ///
+    /// [`StopEvent::Exec`]: enum.StopEvent.html#variant.Exec
+    pub fn set_exec_tracing(&mut self, enabled: bool) -> Result<()> {
+        if enabled {
+            self.set_options(self.options | ptrace::Options::PTRACE_O_TRACEEXEC)
+        } else {
+            self.set_options(self.options - ptrace::Options::PTRACE_O_TRACEEXEC)
+        }
+    }
+
+    /// Continues the debuggee until the next syscall entry or exit with `PTRACE_SYSCALL`.
+    ///
+    /// Returns the status of the stop and, if it's a syscall stop, the syscall number and the
+    /// arguments read from the registers at entry, or its return value at exit. Other stops,
+    /// like signals, aren't syscall stops and are returned with `None`.
+    ///
+    /// Entry and exit stops are told apart with `PTRACE_GET_SYSCALL_INFO` on Linux 5.3 and
+    /// later. Older kernels don't report which one it is, then stops are assumed to alternate,
+    /// starting with an entry. That's only right if all syscalls the debuggee makes between the
+    /// first and the last call of `syscall_step` are traced with it.
+    #[cfg(target_arch = "x86_64")]
+    pub fn syscall_step(&mut self) -> Result<(nix::sys::wait::WaitStatus, Option<SyscallStop>)> {
+        // Makes syscall stops distinguishable from `SIGTRAP`s.
+        if !self
+            .options
+            .contains(ptrace::Options::PTRACE_O_TRACESYSGOOD)
+        {
+            self.set_options(self.options | ptrace::Options::PTRACE_O_TRACESYSGOOD)?;
+        }
+
+        ptrace::syscall(self.pid, None)?;
+        let status = nix::sys::wait::waitpid(self.pid, None)?;
+        if !matches!(status, nix::sys::wait::WaitStatus::PtraceSyscall(_)) {
+            return Ok((status, None));
+        }
+
+        let entry = match self.syscall_info_op()? {
+            Some(op) => op == PTRACE_SYSCALL_INFO_ENTRY,
+            None => !self.in_syscall,
+        };
+        self.in_syscall = entry;
+
+        let regs = self.read_regs()?;
+        let stop = if entry {
+            SyscallStop::Entry {
+                nr: regs.orig_rax,
+                args: [regs.rdi, regs.rsi, regs.rdx, regs.r10, regs.r8, regs.r9],
+            }
+        } else {
+            SyscallStop::Exit {
+                nr: regs.orig_rax,
+                ret: regs.rax as i64,
+            }
+        };
+        Ok((status, Some(stop)))
+    }
+
+    /// Returns the `op` of the debuggee's `PTRACE_GET_SYSCALL_INFO`, or `None` if the kernel
+    /// doesn't support it.
+    #[cfg(target_arch = "x86_64")]
+    fn syscall_info_op(&self) -> Result<Option<u8>> {
+        // `struct ptrace_syscall_info` starts with `op`, the rest isn't needed.
+        let mut info = [0u8; PTRACE_SYSCALL_INFO_SIZE];
+        let ret = unsafe {
+            libc::ptrace(
+                PTRACE_GET_SYSCALL_INFO,
+                self.pid.as_raw(),
+                info.len(),
+                info.as_mut_ptr(),
+            )
+        };
+        match nix::errno::Errno::result(ret) {
+            Ok(_) => Ok(Some(info[0])),
+            Err(nix::Error::Sys(nix::errno::Errno::EIO)) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Checks whether the debuggee has stopped or exited, without blocking.
+    ///
+    /// Returns `None` if it's still running, otherwise the status that `waitpid` would report.
+    pub fn try_wait(&self) -> Result<Option<nix::sys::wait::WaitStatus>> {
+        match nix::sys::wait::waitpid(self.pid, Some(nix::sys::wait::WaitPidFlag::WNOHANG))? {
+            nix::sys::wait::WaitStatus::StillAlive => Ok(None),
+            status => Ok(Some(status)),
+        }
+    }
+
+    /// Waits for the debuggee to stop or exit without blocking the thread, like `try_wait` but
+    /// asynchronously. Needs the `tokio` feature and has to be polled within a tokio runtime.
+    ///
+    /// The debugger gets a `SIGCHLD` every time the debuggee stops or exits, so this waits for
+    /// those rather than on a pidfd, which only reports exits.
+    #[cfg(feature = "tokio")]
+    pub async fn wait_async(&self) -> Result<nix::sys::wait::WaitStatus> {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        // Listening before checking the status so that a stop in between isn't missed.
+        let mut sigchld = signal(SignalKind::child())?;
+        loop {
+            if let Some(status) = self.try_wait()? {
+                return Ok(status);
+            }
+            // Signals are coalesced and may be for other children, so check again every time.
+            if sigchld.recv().await.is_none() {
+                return Err(Error::Other("SIGCHLD stream closed".to_string()));
+            }
+        }
+    }
+
+    /// Opens a pidfd of the debuggee with `pidfd_open`, which needs Linux 5.3 or newer.
+    ///
+    /// It becomes readable when the debuggee exits, so it can be polled for that in an event
+    /// loop. It doesn't report ptrace stops, use `SIGCHLD` and `try_wait` for those.
+    pub fn pidfd(&self) -> Result<File> {
+        let ret = unsafe { libc::syscall(libc::SYS_pidfd_open, self.pid.as_raw(), 0) };
+        let fd = nix::errno::Errno::result(ret)?;
+        Ok(unsafe { File::from_raw_fd(fd as RawFd) })
+    }
+
+    /// Makes `wait` only report stops by `signals`, like the `catch` command of other debuggers.
+    /// Any other signal is passed on to the debuggee right away, as if it wasn't traced, e.g. to
+    /// stop on `SIGSEGV` and `SIGILL` but not on `SIGWINCH`.
+    ///
+    /// `SIGTRAP`, raised by breakpoints and single steps, and `SIGSTOP` are always reported.
+    /// Other ways of waiting, like `wait_status`, still report every signal.
+    pub fn catch_signals(&mut self, signals: &[nix::sys::signal::Signal]) {
+        self.caught_signals = Some(signals.to_vec());
+    }
+
+    /// Makes `wait` report stops by all signals again, which is the default.
+    pub fn catch_all_signals(&mut self) {
+        self.caught_signals = None;
+    }
+
+    /// Checks whether `wait` reports stops by `signal`, see `catch_signals`.
+    pub fn is_signal_caught(&self, signal: nix::sys::signal::Signal) -> bool {
+        use nix::sys::signal::Signal;
+
+        match &self.caught_signals {
+            Some(signals) => {
+                matches!(signal, Signal::SIGTRAP | Signal::SIGSTOP) || signals.contains(&signal)
+            }
+            None => true,
+        }
+    }
+
+    /// Waits for the debuggee to stop and reports why it did.
+    ///
+    /// Stops by signals that aren't caught are skipped, see `catch_signals`. The signal is passed
+    /// on with `PTRACE_CONT`, so this only supports continue-style resumption: after a single
+    /// step or `PTRACE_SYSCALL`, an uncaught signal makes the debuggee run freely instead of
+    /// stopping at the next instruction or syscall. Use `wait_status` in those cases.
+    ///
+    /// On [`StopEvent::Exec`] all breakpoints and cached addresses are forgotten, as they belonged
+    /// to the old image: debug info has to be reloaded before setting new breakpoints.
+    ///
+    /// [`StopEvent::Exec`]: enum.StopEvent.html#variant.Exec
+    pub fn wait(&mut self) -> Result<StopEvent> {
+        use nix::sys::wait::WaitStatus;
+
+        let status = loop {
+            match nix::sys::wait::waitpid(self.pid, None)? {
+                WaitStatus::Stopped(_, signal) if !self.is_signal_caught(signal) => {
+                    ptrace::cont(self.pid, Some(signal))?;
+                }
+                status => break status,
+            }
+        };
+        let event = match status {
+            WaitStatus::Exited(_, code) => StopEvent::Exited(code),
+            WaitStatus::Signaled(_, signal, _) => StopEvent::Killed(signal),
+            WaitStatus::Stopped(_, signal) => StopEvent::Signal(signal),
+            WaitStatus::PtraceSyscall(_) => StopEvent::Syscall,
+            WaitStatus::PtraceEvent(_, _, event) => match event {
+                libc::PTRACE_EVENT_FORK | libc::PTRACE_EVENT_VFORK => {
+                    StopEvent::Fork(Pid::from_raw(ptrace::getevent(self.pid)? as libc::pid_t))
+                }
+                libc::PTRACE_EVENT_CLONE => {
+                    StopEvent::Clone(Pid::from_raw(ptrace::getevent(self.pid)? as libc::pid_t))
+                }
+                libc::PTRACE_EVENT_EXEC => {
+                    self.breakpoints.clear();
+                    self.breakpoint_list.clear();
+                    self.hardware_breakpoints = Default::default();
+                    self.soft_watch_regions.clear();
+                    self.guarded_pages.clear();
+                    self.invalidate_syscall_site();
+                    // The file still refers to the memory of the old image.
+                    self.mem_file.replace(None);
+                    StopEvent::Exec
+                }
+                event => StopEvent::PtraceEvent(event),
+            },
+            status => return Err(Error::UnexpectedWaitStatus(status)),
+        };
+        Ok(event)
+    }
+
+    /// Waits for the debuggee to change state and reports how.
+    ///
+    /// A `SIGTRAP` is reported as [`TargetStatus::SingleStep`] after a single step, and as
+    /// [`TargetStatus::Breakpoint`] when it's caused by a hardware breakpoint or the debuggee
+    /// stopped right after one of its software breakpoints. The breakpoint is left as is, use
+    /// `rewind_breakpoint` or `handle_breakpoint_hit` before resuming.
+    ///
+    /// [`TargetStatus::SingleStep`]: enum.TargetStatus.html#variant.SingleStep
+    /// [`TargetStatus::Breakpoint`]: enum.TargetStatus.html#variant.Breakpoint
+    pub fn wait_status(&self) -> Result<TargetStatus> {
+        use nix::sys::{
+            signal::Signal,
+            wait::{WaitPidFlag, WaitStatus},
+        };
+
+        let status = match nix::sys::wait::waitpid(self.pid, Some(WaitPidFlag::WCONTINUED))? {
+            WaitStatus::Exited(_, code) => TargetStatus::Exited(code),
+            WaitStatus::Signaled(_, signal, _) => TargetStatus::Signaled(signal),
+            WaitStatus::Continued(_) => TargetStatus::Continued,
+            WaitStatus::Stopped(_, Signal::SIGTRAP) => {
+                let siginfo = ptrace::getsiginfo(self.pid)?;
+                let pc = self.read_registers()?.pc() as usize;
+                if siginfo.si_code == TRAP_TRACE {
+                    TargetStatus::SingleStep
+                } else if self.breakpoints.contains_key(&pc.wrapping_sub(1))
+                    || (siginfo.si_code == TRAP_HWBKPT
+                        && self.hardware_breakpoints.iter().any(Option::is_some))
+                {
+                    TargetStatus::Breakpoint
+                } else {
+                    TargetStatus::Stopped(Signal::SIGTRAP)
+                }
+            }
+            WaitStatus::Stopped(_, signal) | WaitStatus::PtraceEvent(_, signal, _) => {
+                TargetStatus::Stopped(signal)
+            }
+            WaitStatus::PtraceSyscall(_) => TargetStatus::Stopped(Signal::SIGTRAP),
+            status => return Err(Error::UnexpectedWaitStatus(status)),
+        };
+        Ok(status)
+    }
+
+    /// Returns the `siginfo_t` of the signal the debuggee is stopped by.
+    pub fn siginfo(&self) -> Result<libc::siginfo_t> {
+        Ok(ptrace::getsiginfo(self.pid)?)
+    }
+
+    /// Returns the address whose access faulted if the debuggee is stopped by a `SIGSEGV` or
+    /// `SIGBUS`, or `None` for any other signal.
+    pub fn fault_addr(&self) -> Result<Option<usize>> {
+        let siginfo = self.siginfo()?;
+        match siginfo.si_signo {
+            libc::SIGSEGV | libc::SIGBUS => Ok(Some(unsafe { siginfo.si_addr() } as usize)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns a target for a child reported by [`StopEvent::Fork`] or [`StopEvent::Clone`]
+    /// once it's stopped.
+    ///
+    /// A forked child gets a copy of the software breakpoints, as its memory is a copy of the
+    /// debuggee's. Threads and `vfork` children share the memory with the debuggee instead, so
+    /// their target starts without any: setting or clearing breakpoints through both targets
+    /// would leave one of them with a wrong idea of what's in memory.
+    ///
+    /// [`StopEvent::Fork`]: enum.StopEvent.html#variant.Fork
+    /// [`StopEvent::Clone`]: enum.StopEvent.html#variant.Clone
+    pub fn follow_child(&self, child_pid: Pid) -> Result<LinuxTarget> {
+        // The child starts with a `SIGSTOP`. Threads need `__WALL` to be waited for.
+        nix::sys::wait::waitpid(child_pid, Some(nix::sys::wait::WaitPidFlag::__WALL))?;
+
+        let mut child = LinuxTarget::new(child_pid);
+        child.options = self.options;
+        if !self.shares_memory_with(child_pid)? {
+            child.breakpoints = self.breakpoints.clone();
+            child.breakpoint_list = self.breakpoint_list.clone();
+        }
+        child.syscall_site = self.syscall_site.clone();
+        Ok(child)
+    }
+
+    /// Checks whether `pid` shares the address space with the debuggee, like threads created
+    /// with `CLONE_VM` do.
+    ///
+    /// Uses `kcmp`, which isn't available on kernels built without `CONFIG_KCMP`. Then only
+    /// threads of the debuggee are recognized.
+    fn shares_memory_with(&self, pid: Pid) -> Result<bool> {
+        const KCMP_VM: libc::c_long = 1;
+        let res = unsafe {
+            libc::syscall(
+                libc::SYS_kcmp,
+                self.pid.as_raw(),
+                pid.as_raw(),
+                KCMP_VM,
+                0,
+                0,
+            )
+        };
+        if res >= 0 {
+            return Ok(res == 0);
+        }
+        let tgid = |pid: Pid| -> Result<i32> { Ok(Process::new(pid.as_raw())?.status()?.tgid) };
+        Ok(tgid(self.pid)? == tgid(pid)?)
+    }
+
+    fn set_options(&mut self, options: ptrace::Options) -> Result<()> {
+        ptrace::setoptions(self.pid, options)?;
+        self.options = options;
+        Ok(())
+    }
+
+    /// Walks the frame pointer chain starting from `rbp` and returns the return address of every
+    /// frame, innermost first.
+    ///
+    /// This only works for code compiled with frame pointers (e.g. `-Cforce-frame-pointers=yes`).
+    /// A function that doesn't maintain a frame pointer, or hasn't run its prologue yet, doesn't
+    /// get a frame of its own and its return address is missing from the result.
+    #[cfg(target_arch = "x86_64")]
+    pub fn backtrace(&self) -> Result<Vec<usize>> {
+        let maps = self.memory_maps()?;
+        let frame_size = 2 * std::mem::size_of::<usize>();
+        let is_mapped = |addr: usize| {
+            maps.iter().any(|map| {
+                map.is_readable
+                    && map.address.0 <= addr as u64
+                    && (addr + frame_size) as u64 <= map.address.1
+            })
+        };
+
+        let mut return_addrs = Vec::new();
+        let mut rbp = self.read_regs()?.rbp as usize;
+        while rbp != 0 && rbp & (std::mem::align_of::<usize>() - 1) == 0 && is_mapped(rbp) {
+            // Every frame starts with the caller's `rbp` followed by the return address.
+            let mut frame = [0usize; 2];
+            unsafe { self.read().read(&mut frame, rbp).apply()? };
+            let [saved_rbp, return_addr] = frame;
+            if return_addr == 0 {
+                break;
+            }
+            return_addrs.push(return_addr);
+
+            // The stack grows down, so anything else is garbage and could loop forever.
+            if saved_rbp <= rbp {
+                break;
+            }
+            rbp = saved_rbp;
+        }
+
+        Ok(return_addrs)
+    }
+
+    /// Returns the program counter of the debuggee together with the symbol containing it and,
+    /// if `debuginfo` has line info for it, its source location.
+    ///
+    /// `symbols` has to be up to date with the objects loaded into the debuggee, see
+    /// `ProcessSymbols::update`.
+    pub fn current_location(
+        &self,
+        symbols: &crate::symbol::ProcessSymbols,
+        debuginfo: Option<&crate::symbol::RelocatedDwarf>,
+    ) -> Result<Location> {
+        let pc = self.read_registers()?.pc() as usize;
+        let symbol = symbols.resolve(pc as u64).and_then(|(_, symbol, offset)| {
+            Some((symbol.demangled_name()?.to_string(), offset as usize))
+        });
+        // Like for `step_line`, line 0 means there is no line for the instruction.
+        let source = debuginfo
+            .and_then(|debuginfo| debuginfo.source_location(pc).ok().flatten())
+            .map(|(file, line, _column)| (file, line))
+            .filter(|&(_, line)| line != 0);
+        Ok(Location { pc, symbol, source })
+    }
+
+    /// Returns the current snapshot view of this debuggee process threads.
+    pub fn threads(&self) -> Result<Vec<Box<dyn Thread<ThreadId = i32>>>> {
+        let tasks: Vec<_> = Process::new(self.pid.as_raw())?
+            .tasks()?
+            .flatten()
+            .map(|task| Box::new(LinuxThread::new(task)) as Box<dyn Thread<ThreadId = i32>>)
+            .collect();
+
+        Ok(tasks)
+    }
+
+    /// Stops every thread of the debuggee that isn't stopped yet, so that all of them can be
+    /// inspected in a consistent state, e.g. for backtraces.
+    ///
+    /// Threads that aren't traced yet, like the ones created without `set_fork_follow`, are
+    /// attached to. Threads that exit in the meantime are skipped. Use `resume_all_threads` to
+    /// continue the stopped threads again.
+    ///
+    /// A traced thread can stop for another reason before the `SIGSTOP` arrives, e.g. on a
+    /// breakpoint. Such a thread stays stopped on `resume_all_threads`, and its stop is returned
+    /// by the next `wait_any_thread`.
+    pub fn stop_all_threads(&self) -> Result<()> {
+        use nix::sys::wait::{waitpid, WaitPidFlag, WaitStatus};
+
+        for thread in self.threads()? {
+            match thread.state()? {
+                Some(ThreadState::TracingStop)
+                | Some(ThreadState::Zombie)
+                | Some(ThreadState::Dead)
+                | None => continue,
+                _ => {}
+            }
+            let tid = thread.thread_id();
+            let pid = Pid::from_raw(tid);
+
+            // Attaching to a thread that is already traced fails with `EPERM`, such a thread
+            // is stopped with a `SIGSTOP` sent to it alone instead.
+            let attached = match ptrace::attach(pid) {
+                Ok(()) => true,
+                Err(nix::Error::Sys(nix::errno::Errno::EPERM)) => {
+                    let ret = unsafe {
+                        libc::syscall(libc::SYS_tgkill, self.pid.as_raw(), tid, libc::SIGSTOP)
+                    };
+                    match nix::errno::Errno::result(ret) {
+                        Ok(_) => false,
+                        Err(nix::Error::Sys(nix::errno::Errno::ESRCH)) => continue,
+                        Err(err) => return Err(err.into()),
+                    }
+                }
+                Err(nix::Error::Sys(nix::errno::Errno::ESRCH)) => continue,
+                Err(err) => return Err(err.into()),
+            };
+
+            let mut other_stop = false;
+            loop {
+                let status = match waitpid(pid, Some(WaitPidFlag::__WALL)) {
+                    Ok(status) => status,
+                    Err(nix::Error::Sys(nix::errno::Errno::ECHILD)) => break,
+                    Err(err) => return Err(err.into()),
+                };
+                match status {
+                    WaitStatus::Stopped(_, nix::sys::signal::SIGSTOP)
+                    | WaitStatus::PtraceEvent(_, _, PTRACE_EVENT_STOP) => {
+                        if !other_stop {
+                            self.stopped_threads.borrow_mut().push((tid, attached));
+                        }
+                        break;
+                    }
+                    WaitStatus::Exited(..) | WaitStatus::Signaled(..) => {
+                        if !attached {
+                            self.pending_stops.borrow_mut().push(status);
+                        }
+                        break;
+                    }
+                    // A thread that wasn't traced before gets its signal delivered as if it
+                    // was never attached to. The `SIGSTOP` is still dequeued before any
+                    // instruction runs.
+                    WaitStatus::Stopped(_, signal) if attached => {
+                        ptrace::cont(pid, signal)?;
+                    }
+                    // The initial stop of the new thread is enough for `wait_any_thread` to
+                    // trace it, so the clone event isn't kept.
+                    WaitStatus::PtraceEvent(_, _, libc::PTRACE_EVENT_CLONE) => {
+                        ptrace::cont(pid, None)?;
+                    }
+                    // The `SIGSTOP` is still queued, so the thread is continued to take it
+                    // without running any instruction, and this stop is reported later.
+                    status => {
+                        self.pending_stops.borrow_mut().push(status);
+                        other_stop = true;
+                        ptrace::cont(pid, None)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Continues the threads stopped by `stop_all_threads`, detaching from the ones that were
+    /// attached to for it. Threads that have exited in the meantime are skipped.
+    ///
+    /// Threads that were already stopped when `stop_all_threads` was called are left stopped.
+    pub fn resume_all_threads(&self) -> Result<()> {
+        for (tid, attached) in self.stopped_threads.replace(Vec::new()) {
+            let pid = Pid::from_raw(tid);
+            let result = if attached {
+                ptrace::detach(pid, None)
+            } else {
+                ptrace::cont(pid, None)
+            };
+            match result {
+                Ok(()) | Err(nix::Error::Sys(nix::errno::Errno::ESRCH)) => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the threads other than the main one that stay traced, see
+    /// `AttachOptions::all_threads`.
+    pub fn traced_threads(&self) -> &[i32] {
+        &self.traced_threads
+    }
+
+    /// Continues the main thread and all threads traced because of `AttachOptions::all_threads`.
+    /// Threads that aren't stopped or have exited are skipped.
+    pub fn cont_all_threads(&self) -> Result<()> {
+        let tids = std::iter::once(self.pid.as_raw()).chain(self.traced_threads.iter().copied());
+        for tid in tids {
+            match ptrace::cont(Pid::from_raw(tid), None) {
+                Ok(()) | Err(nix::Error::Sys(nix::errno::Errno::ESRCH)) => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(())
+    }
+
+    /// Waits until any traced thread of the debuggee stops, e.g. on a breakpoint, and returns
+    /// the status of that stop, which tells the id of the thread.
+    ///
+    /// Thread creations and exits of threads other than the main one are handled without
+    /// returning: new threads are added to `traced_threads` and continued together with the
+    /// thread that created them. The other threads keep running while one of them is stopped,
+    /// use `stop_all_threads` to stop them too.
+    ///
+    /// This waits for any child of this process, so it shouldn't be used while other children
+    /// are traced or waited for.
+    pub fn wait_any_thread(&mut self) -> Result<nix::sys::wait::WaitStatus> {
+        use nix::sys::wait::{waitpid, WaitPidFlag, WaitStatus};
+
+        loop {
+            // Stops seen by `stop_all_threads` come first, `remove(0)` keeps them in order.
+            let pending_stops = self.pending_stops.get_mut();
+            let status = if pending_stops.is_empty() {
+                waitpid(Pid::from_raw(-1), Some(WaitPidFlag::__WALL))?
+            } else {
+                pending_stops.remove(0)
+            };
+            match status {
+                WaitStatus::PtraceEvent(tid, _, libc::PTRACE_EVENT_CLONE) => {
+                    // If the initial stop of the new thread was reported first, it's already
+                    // traced and running.
+                    let new_tid = ptrace::getevent(tid)? as i32;
+                    if !self.traced_threads.contains(&new_tid) {
+                        self.traced_threads.push(new_tid);
+                        self.pending_clones.push(new_tid);
+                    }
+                    ptrace::cont(tid, None)?;
+                }
+                // New threads start with a `SIGSTOP`, or an event stop if the debuggee was
+                // seized.
+                WaitStatus::Stopped(tid, nix::sys::signal::SIGSTOP)
+                | WaitStatus::PtraceEvent(tid, _, PTRACE_EVENT_STOP)
+                    if tid != self.pid && !self.traced_threads.contains(&tid.as_raw()) =>
+                {
+                    // A new thread whose clone event hasn't been reported yet.
+                    self.traced_threads.push(tid.as_raw());
+                    ptrace::cont(tid, None)?;
+                }
+                WaitStatus::Stopped(tid, nix::sys::signal::SIGSTOP)
+                | WaitStatus::PtraceEvent(tid, _, PTRACE_EVENT_STOP)
+                    if self.pending_clones.contains(&tid.as_raw()) =>
+                {
+                    self.pending_clones
+                        .retain(|&pending| pending != tid.as_raw());
+                    ptrace::cont(tid, None)?;
+                }
+                WaitStatus::Exited(tid, _) | WaitStatus::Signaled(tid, _, _) if tid != self.pid => {
+                    self.traced_threads.retain(|&traced| traced != tid.as_raw());
+                    self.pending_clones
+                        .retain(|&pending| pending != tid.as_raw());
+                }
+                status => return Ok(status),
+            }
+        }
+    }
+
+    /// Manages software breakpoints with ids, hit counts and an enabled flag.
+    pub fn breakpoints(&mut self) -> Breakpoints<'_> {
+        Breakpoints::new(self)
+    }
+
+    /// Sets a software breakpoint at `addr` by replacing the first byte of the instruction
+    /// with `int3`. Setting a breakpoint at an address that already has one is a no-op.
+    pub fn set_breakpoint(&mut self, addr: usize) -> Result<()> {
+        if self.breakpoints.contains_key(&addr) {
+            return Ok(());
+        }
+
+        let orig_byte = self.patch_byte(addr, software_breakpoint::INT3)?;
+        self.breakpoints.insert(addr, orig_byte);
+        Ok(())
+    }
+
+    /// Removes the software breakpoint at `addr`, restoring the original instruction byte.
+    pub fn clear_breakpoint(&mut self, addr: usize) -> Result<()> {
+        let orig_byte = match self.breakpoints.get(&addr) {
+            Some(&orig_byte) => orig_byte,
+            None => return Err(SoftwareBreakpointError::DoesNotExist(addr).into()),
+        };
+
+        self.patch_byte(addr, orig_byte)?;
+        self.breakpoints.remove(&addr);
+        Ok(())
+    }
+
+    /// Returns all software breakpoints sorted by address, both those set with `set_breakpoint`
+    /// and the disabled ones of `breakpoints`, e.g. for an `info breakpoints` command.
+    ///
+    /// If `symbols` is given, the symbols containing the breakpoints are looked up in it. It has
+    /// to be up to date with the objects loaded into the debuggee, see `ProcessSymbols::update`.
+    pub fn list_breakpoints(
+        &self,
+        symbols: Option<&crate::symbol::ProcessSymbols>,
+    ) -> Vec<BreakpointInfo> {
+        let mut list: Vec<_> = self
+            .breakpoints
+            .iter()
+            .map(|(&addr, &orig_byte)| (addr, Some(orig_byte)))
+            .chain(
+                self.breakpoint_list
+                    .iter()
+                    .filter(|breakpoint| !breakpoint.enabled)
+                    .map(|breakpoint| (breakpoint.addr, None)),
+            )
+            .map(|(addr, orig_byte)| {
+                let managed = self
+                    .breakpoint_list
+                    .iter()
+                    .find(|breakpoint| breakpoint.addr == addr);
+                BreakpointInfo {
+                    addr,
+                    orig_byte,
+                    enabled: orig_byte.is_some(),
+                    id: managed.map(|breakpoint| breakpoint.id),
+                    temporary: matches!(managed, Some(breakpoint) if breakpoint.temporary),
+                    symbol: symbols
+                        .and_then(|symbols| symbols.resolve(addr as u64))
+                        .and_then(|(_, symbol, offset)| {
+                            Some((symbol.demangled_name()?.to_string(), offset as usize))
+                        }),
+                }
+            })
+            .collect();
+        list.sort_by_key(|breakpoint| breakpoint.addr);
+        list
+    }
+
+    /// Returns the original byte of the instruction at `addr` if there's a software breakpoint
+    /// at `addr`, or `None` if the byte there is the debuggee's own.
+    ///
+    /// Use `ReadMemory::hide_breakpoints` to read memory with the original bytes in place.
+    pub fn breakpoint_at(&self, addr: usize) -> Option<u8> {
+        self.breakpoints.get(&addr).copied()
+    }
+
+    /// Moves RIP back onto the software breakpoint the debuggee has just stopped at.
+    ///
+    /// When `int3` is executed RIP points right after it, so it needs to be moved back by one
+    /// byte before the breakpointed instruction can be executed. The breakpoint stays in place,
+    /// use `step_over_breakpoint` to resume.
+    /// Returns the address of the breakpoint that was hit, or `None` if the debuggee isn't
+    /// stopped at a known software breakpoint.
+    #[cfg(target_arch = "x86_64")]
+    pub fn rewind_breakpoint(&self) -> Result<Option<usize>> {
+        let mut regs = self.read_regs()?;
+        let addr = (regs.rip as usize).wrapping_sub(1);
+        if !self.breakpoints.contains_key(&addr) {
+            return Ok(None);
+        }
+
+        regs.rip = addr as u64;
+        self.write_regs(regs)?;
+
+        Ok(Some(addr))
+    }
+
+    /// Handles a `SIGTRAP` caused by a software breakpoint.
+    ///
+    /// This rewinds RIP like `rewind_breakpoint` and clears the breakpoint, restoring the
+    /// original instruction. The debuggee can then be resumed as if the breakpoint had never
+    /// been there.
+    /// Returns the address of the breakpoint that was hit, or `None` if the debuggee isn't
+    /// stopped at a known software breakpoint.
+    #[cfg(target_arch = "x86_64")]
+    pub fn handle_breakpoint_hit(&mut self) -> Result<Option<usize>> {
+        let addr = match self.rewind_breakpoint()? {
+            Some(addr) => addr,
+            None => return Ok(None),
+        };
+
+        self.clear_breakpoint(addr)?;
+        Ok(Some(addr))
+    }
+
+    /// Executes the instruction at the software breakpoint `addr` without losing the breakpoint.
+    ///
+    /// The original instruction byte is restored for the duration of a single step and `int3`
+    /// is written back afterwards. `addr` should be the current instruction pointer, e.g. as
+    /// returned by `rewind_breakpoint`. If there's no breakpoint at `addr`, this is the same as
+    /// a plain `step`.
+    pub fn step_over_breakpoint(&mut self, addr: usize) -> Result<nix::sys::wait::WaitStatus> {
+        let orig_byte = match self.breakpoints.get(&addr) {
+            Some(&orig_byte) => orig_byte,
+            None => return self.step(),
+        };
+
+        self.patch_byte(addr, orig_byte)?;
+        let status = self.step()?;
+
+        match status {
+            // The debuggee is gone, there's nothing to reinsert the breakpoint into.
+            nix::sys::wait::WaitStatus::Exited(..) | nix::sys::wait::WaitStatus::Signaled(..) => {}
+            _ => {
+                self.patch_byte(addr, software_breakpoint::INT3)?;
+            }
+        }
+
+        Ok(status)
+    }
+
+    /// Steps to the next source line, stepping over function calls.
+    ///
+    /// The debuggee is single-stepped until the file:line `debuginfo` reports for RIP changes or
+    /// the current function returns. Calls, including tail calls, are run to completion with a
+    /// temporary breakpoint at the return address. Software breakpoints on the current line are
+    /// stepped over, but hitting one inside a called function ends the step with RIP moved back
+    /// onto it.
+    /// Returns the status of the last stop.
+    #[cfg(target_arch = "x86_64")]
+    pub fn step_line(
+        &mut self,
+        debuginfo: &crate::symbol::RelocatedDwarf,
+    ) -> Result<nix::sys::wait::WaitStatus> {
+        // Instructions without line info, like those of functions without debug info, and
+        // compiler generated code at line 0 don't start a new line.
+        let line_of = |addr: usize| {
+            debuginfo
+                .source_location(addr)
+                .ok()
+                .flatten()
+                .map(|(file, line, _column)| (file, line))
+                .filter(|&(_, line)| line != 0)
+        };
+
+        let start_line = line_of(self.read_regs()?.rip as usize)
+            .ok_or_else(|| Error::Other("No line info for the current instruction".to_string()))?;
+        loop {
+            let regs = self.read_regs()?;
+            let pc = regs.rip as usize;
+            let insn = self
+                .disassemble(pc, 1)?
+                .pop()
+                .ok_or_else(|| Error::InvalidInstruction(pc))?;
+
+            let mut status = self.step_over_breakpoint(pc)?;
+            if !is_sigtrap(status) || insn.text.starts_with("ret") {
+                return Ok(status);
+            }
+            if insn.text.starts_with("call") {
+                let return_addr = pc + insn.len;
+                status = self.run_to_return(return_addr, regs.rsp)?;
+                if !is_sigtrap(status) || self.read_regs()?.rip as usize != return_addr {
+                    return Ok(status);
+                }
+            } else if insn.text.starts_with("jmp") {
+                let new_regs = self.read_regs()?;
+                let function = debuginfo.get_address_symbol_name(pc);
+                if function.is_some()
+                    && debuginfo.get_address_symbol_name(new_regs.rip as usize) != function
+                {
+                    // A tail call, the current function returns once the jump target does.
+                    let mut return_addr = 0usize;
+                    unsafe {
+                        self.read()
+                            .read(&mut return_addr, new_regs.rsp as usize)
+                            .apply()?;
+                    }
+                    return self.run_to_return(return_addr, new_regs.rsp + 8);
+                }
+            }
+
+            match line_of(self.read_regs()?.rip as usize) {
+                Some(line) if line != start_line => return Ok(status),
+                _ => {}
+            }
+        }
+    }
+
+    /// Runs until the current function returns, like the `finish` command of other debuggers.
+    ///
+    /// The return address is found through the frame pointer chain, so the debuggee has to be
+    /// built with frame pointers. Right at the start of a function, before `rbp` is set up, and
+    /// at any form of `ret`, like `rep ret` or `ret $8`, the return address is read from the top
+    /// of the stack instead. The start includes the `endbr64` functions built with
+    /// `-fcf-protection` start with.
+    /// Hitting a software breakpoint before the function returns ends the step with RIP moved
+    /// back onto it.
+    /// Returns the status of the last stop.
+    #[cfg(target_arch = "x86_64")]
+    pub fn step_out(&mut self) -> Result<nix::sys::wait::WaitStatus> {
+        let regs = self.read_regs()?;
+        let pc = regs.rip as usize;
+        let insn = self
+            .disassemble(pc, 1)?
+            .pop()
+            .ok_or(Error::InvalidInstruction(pc))?;
+        // Address of the return address. The prologue is matched by its encoding, so that it
+        // doesn't depend on the syntax of the disassembly.
+        let frame = if insn.branch_kind() == Some(crate::symbol::BranchKind::Return) {
+            regs.rsp
+        } else {
+            match &insn.bytes[..] {
+                // `endbr64`, `push %rbp` with and without REX prefix.
+                [0xf3, 0x0f, 0x1e, 0xfa] | [0x55] | [0x40, 0x55] => regs.rsp,
+                // Both encodings of `mov %rsp, %rbp`.
+                [0x48, 0x89, 0xe5] | [0x48, 0x8b, 0xec] => regs.rsp + 8,
+                _ => regs.rbp + 8,
+            }
+        } as usize;
+
+        let mut return_addr = 0usize;
+        unsafe { self.read().read(&mut return_addr, frame).apply()? };
+        let sp = frame as u64 + 8;
+
+        if self.breakpoints.contains_key(&pc) {
+            let status = self.step_over_breakpoint(pc)?;
+            if !is_sigtrap(status) || self.read_regs()?.rip as usize == return_addr {
+                return Ok(status);
+            }
+        }
+        self.run_to_return(return_addr, sp)
+    }
+
+    /// Runs until the debuggee reaches `addr`, like the `advance` command of other debuggers.
+    ///
+    /// A temporary software breakpoint is set at `addr` for the run, unless there already is one,
+    /// and the debuggee stops on its first hit with RIP moved back onto `addr`, even if `addr` is
+    /// in a loop. Hitting another software breakpoint on the way ends the run with RIP moved back
+    /// onto that one instead.
+    /// Returns the status of the last stop.
+    #[cfg(target_arch = "x86_64")]
+    pub fn run_until(&mut self, addr: usize) -> Result<nix::sys::wait::WaitStatus> {
+        let pc = self.read_regs()?.rip as usize;
+        if self.breakpoints.contains_key(&pc) {
+            let status = self.step_over_breakpoint(pc)?;
+            if !is_sigtrap(status) || self.read_regs()?.rip as usize == addr {
+                return Ok(status);
+            }
+        }
+
+        let temporary = !self.breakpoints.contains_key(&addr);
+        if temporary {
+            self.set_breakpoint(addr)?;
+        }
+
+        let status = self.unpause()?;
+        if is_sigtrap(status) {
+            self.rewind_breakpoint()?;
+        }
+
+        if temporary {
+            match status {
+                nix::sys::wait::WaitStatus::Exited(..)
+                | nix::sys::wait::WaitStatus::Signaled(..) => {
+                    self.breakpoints.remove(&addr);
+                }
+                _ => self.clear_breakpoint(addr)?,
+            }
+        }
+        Ok(status)
+    }
+
+    /// Single-steps up to `n` instructions, like the `stepi N` command of other debuggers.
+    ///
+    /// Software breakpoints are stepped over, including one at the current instruction. Stepping
+    /// stops early once the next instruction has an enabled software breakpoint, a hardware
+    /// breakpoint was triggered, or the debuggee stops for any other reason than the single step
+    /// or exits.
+    /// Returns how many instructions were stepped and the status of the last stop. Steps ending
+    /// with another signal or the exit of the debuggee aren't counted. If `n` is 0 nothing is
+    /// executed and the status is `WaitStatus::StillAlive`.
+    #[cfg(target_arch = "x86_64")]
+    pub fn step_instructions(&mut self, n: usize) -> Result<(usize, nix::sys::wait::WaitStatus)> {
+        let mut status = nix::sys::wait::WaitStatus::StillAlive;
+        for steps in 0..n {
+            let pc = self.read_regs()?.rip as usize;
+            status = self.step_over_breakpoint(pc)?;
+            if !is_sigtrap(status) {
+                return Ok((steps, status));
+            }
+
+            let pc = self.read_regs()?.rip as usize;
+            if self.breakpoints.contains_key(&pc) || self.hardware_breakpoint_hit()? {
+                return Ok((steps + 1, status));
+            }
+        }
+        Ok((n, status))
+    }
+
+    /// Checks whether one of the hardware breakpoints that are set caused the last debug
+    /// exception.
+    #[cfg(target_arch = "x86_64")]
+    fn hardware_breakpoint_hit(&self) -> Result<bool> {
+        let debug_status = self.debug_status()?;
+        Ok((0..SUPPORTED_HARDWARE_BREAKPOINTS).any(|index| {
+            self.hardware_breakpoints[index].is_some() && debug_status.condition_met(index)
+        }))
+    }
+
+    /// Single-steps until a call, return or jump has been executed, e.g. to sample the call
+    /// edges of a function. Every instruction is decoded with `disassemble` before stepping it,
+    /// conditional jumps count as branches whether they're taken or not.
+    ///
+    /// Software breakpoints are stepped over like with `step_instructions`, which stops early
+    /// for the same reasons.
+    /// Returns the branch, if one was executed, and the status of the last stop.
+    #[cfg(target_arch = "x86_64")]
+    pub fn step_until_branch(&mut self) -> Result<(Option<Branch>, nix::sys::wait::WaitStatus)> {
+        loop {
+            let pc = self.read_regs()?.rip as usize;
+            let insn = self
+                .disassemble(pc, 1)?
+                .pop()
+                .ok_or_else(|| Error::InvalidInstruction(pc))?;
+
+            let (steps, status) = self.step_instructions(1)?;
+            if steps == 0 {
+                return Ok((None, status));
+            }
+            let next_pc = self.read_regs()?.rip as usize;
+            let branch = insn.branch_kind().map(|kind| Branch {
+                kind,
+                address: pc,
+                target: next_pc,
+            });
+            if branch.is_some()
+                || self.breakpoints.contains_key(&next_pc)
+                || self.hardware_breakpoint_hit()?
+            {
+                return Ok((branch, status));
+            }
+        }
+    }
+
+    /// Runs the current function until it returns to `return_addr`, leaving the stack pointer
+    /// at `sp`.
+    ///
+    /// Deeper recursive calls returning to the same address are told apart by the stack pointer
+    /// still being below `sp`.
+    #[cfg(target_arch = "x86_64")]
+    fn run_to_return(&mut self, return_addr: usize, sp: u64) -> Result<nix::sys::wait::WaitStatus> {
+        let temporary = !self.breakpoints.contains_key(&return_addr);
+        if temporary {
+            self.set_breakpoint(return_addr)?;
+        }
+
+        let status = loop {
+            let status = self.unpause()?;
+            if !is_sigtrap(status) {
+                break status;
+            }
+            match self.rewind_breakpoint()? {
+                Some(addr) if addr == return_addr && self.read_regs()?.rsp < sp => {
+                    let status = self.step_over_breakpoint(addr)?;
+                    if !is_sigtrap(status) {
+                        break status;
+                    }
+                }
+                _ => break status,
+            }
+        };
+
+        if temporary {
+            match status {
+                nix::sys::wait::WaitStatus::Exited(..)
+                | nix::sys::wait::WaitStatus::Signaled(..) => {
+                    self.breakpoints.remove(&return_addr);
+                }
+                _ => self.clear_breakpoint(return_addr)?,
+            }
+        }
+        Ok(status)
+    }
+
+    /// Overwrites a single byte of the debuggee's code at `addr`, returning the previous value.
+    fn patch_byte(&self, addr: usize, byte: u8) -> Result<u8> {
+        let mut word = ptrace::read(self.pid, addr as *mut _)?.to_ne_bytes();
+        let orig_byte = word[0];
+        word[0] = byte;
+        ptrace::write(
+            self.pid,
+            addr as *mut _,
+            libc::c_long::from_ne_bytes(word) as *mut _,
+        )?;
+        Ok(orig_byte)
+    }
+
+    pub fn set_hardware_breakpoint(&mut self, breakpoint: HardwareBreakpoint) -> Result<usize> {
+        Ok(self.set_hardware_breakpoints(std::slice::from_ref(&breakpoint))?[0])
+    }
+
+    /// Sets all of `breakpoints` at once, returning their indices in the same order.
+    ///
+    /// On x86_64 the debug registers are written once for all of them: one `PTRACE_POKEUSER`
+    /// per address, one for DR7 and one to clear DR6. Either all breakpoints are set or, if
+    /// one of them is invalid or there aren't enough unused slots, none of them.
+    pub fn set_hardware_breakpoints(
+        &mut self,
+        breakpoints: &[HardwareBreakpoint],
+    ) -> Result<Vec<usize>> {
+        #[cfg(target_arch = "x86_64")]
+        {
+            for breakpoint in breakpoints {
+                breakpoint.check_x86_64_alignment()?;
+            }
+            let indices = match self.find_empty_watchpoints(breakpoints.len()) {
+                Some(indices) => indices,
+                None => return Err(HardwareBreakpointError::NoEmptyWatchpoint.into()),
+            };
+
+            let mut dr7: u64 = self.debug_reg(7)?;
+            for (&index, breakpoint) in indices.iter().zip(breakpoints) {
+                // Check if hardware watchpoint is already used
+                if dr7 & (1 << (2 * index)) != 0 {
+                    return Err(HardwareBreakpointError::RegisterStateMismatch(index).into());
+                }
+
+                let rw_bits: u64 = breakpoint.rw_bits(index);
+                let size_bits = breakpoint.size_bits(index);
+                let enable_bit: u64 = 1 << (2 * index);
+                let bit_mask = HardwareBreakpoint::bit_mask(index);
+                dr7 = (dr7 & !bit_mask) | (enable_bit | rw_bits | size_bits);
+            }
+
+            for (&index, breakpoint) in indices.iter().zip(breakpoints) {
+                self.set_debug_reg(index, breakpoint.addr as u64)?;
+            }
+            self.set_debug_reg(7, dr7)?;
+            self.set_debug_reg(6, 0)?;
+
+            for (&index, breakpoint) in indices.iter().zip(breakpoints) {
+                self.hardware_breakpoints[index] = Some(breakpoint.clone());
+            }
+
+            Ok(indices)
+        }
+        #[cfg(target_arch = "aarch64")]
+        {
+            let indices = match self.find_empty_watchpoints(breakpoints.len()) {
+                Some(indices) => indices,
+                None => return Err(HardwareBreakpointError::NoEmptyWatchpoint.into()),
+            };
+
+            for (&index, breakpoint) in indices.iter().zip(breakpoints) {
+                self.hardware_breakpoints[index] = Some(breakpoint.clone());
+            }
+            if let Err(err) = self.write_hardware_debug_regs() {
+                for &index in &indices {
+                    self.hardware_breakpoints[index] = None;
+                }
+                return Err(err);
+            }
+
+            Ok(indices)
+        }
+        #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+        {
+            let _ = breakpoints;
+            Err(HardwareBreakpointError::UnsupportedPlatform.into())
+        }
+    }
+
+    /// Sets an execute hardware breakpoint on the function or other symbol called `name`,
+    /// returning its index like `set_hardware_breakpoint`.
+    ///
+    /// The symbol is looked up in `symbols` with `ProcessSymbols::address_of`, so it has to be up
+    /// to date with the objects loaded into the debuggee, see `ProcessSymbols::update`. Unlike a
+    /// software breakpoint, this doesn't patch the code, so it also works on code that can't be
+    /// written to.
+    pub fn set_hardware_breakpoint_at_symbol(
+        &mut self,
+        symbols: &crate::symbol::ProcessSymbols,
+        name: &str,
+    ) -> Result<usize> {
+        let addr = symbols
+            .address_of(name)
+            .ok_or_else(|| Error::SymbolNotFound(name.to_string()))?;
+        self.set_hardware_breakpoint(HardwareBreakpoint {
+            typ: HardwareBreakpointType::Execute,
+            addr: addr as usize,
+            size: HardwareBreakpointSize::_1,
+        })
+    }
+
+    /// Sets a hardware watchpoint of type `typ` on the first `size` bytes of the variable or
+    /// other data symbol called `name`, returning its index like `set_hardware_breakpoint`.
+    ///
+    /// The symbol is looked up in `symbols` like with `set_hardware_breakpoint_at_symbol`. Fails
+    /// if it is smaller than `size`, including symbols without a size.
+    pub fn watch_symbol(
+        &mut self,
+        symbols: &crate::symbol::ProcessSymbols,
+        name: &str,
+        size: HardwareBreakpointSize,
+        typ: HardwareBreakpointType,
+    ) -> Result<usize> {
+        let (addr, symbol) = symbols
+            .symbol_by_name(name)
+            .ok_or_else(|| Error::SymbolNotFound(name.to_string()))?;
+        if symbol.size() < size.bytes() as u64 {
+            return Err(Error::SymbolTooSmall {
+                name: name.to_string(),
+                size: symbol.size(),
+                watched: size.bytes(),
+            });
+        }
+        self.set_hardware_breakpoint(HardwareBreakpoint {
+            typ,
+            addr: addr as usize,
+            size,
+        })
+    }
+
+    pub fn clear_hardware_breakpoint(&mut self, index: usize) -> Result<HardwareBreakpoint> {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if self.hardware_breakpoints[index].is_none() {
+                return Err(HardwareBreakpointError::DoesNotExist(index).into());
+            }
+
+            let mut dr7 = self.debug_reg(7)?;
+            let mut dr6 = self.debug_reg(6)?;
+
+            let dr7_bit_mask: u64 = HardwareBreakpoint::bit_mask(index);
+            dr7 = dr7 & !dr7_bit_mask;
+
+            let dr6_bit_mask: u64 = 1 << index;
+            dr6 = dr6 & !dr6_bit_mask as u64;
+
+            self.set_debug_reg(7, dr7)?;
+            self.set_debug_reg(6, dr6)?;
+
+            let watchpoint = std::mem::replace(&mut self.hardware_breakpoints[index], None);
+            Ok(watchpoint.unwrap())
+        }
+
+        #[cfg(target_arch = "aarch64")]
+        {
+            let watchpoint = match self.hardware_breakpoints[index].take() {
+                Some(watchpoint) => watchpoint,
+                None => return Err(HardwareBreakpointError::DoesNotExist(index).into()),
+            };
+            if let Err(err) = self.write_hardware_debug_regs() {
+                self.hardware_breakpoints[index] = Some(watchpoint);
+                return Err(err);
+            }
+            Ok(watchpoint)
+        }
+
+        #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+        Err(HardwareBreakpointError::UnsupportedPlatform.into())
+    }
+
+    /// Rereads the debug registers and updates the hardware breakpoints known to this target to
+    /// match them, e.g. after getting a `HardwareBreakpointError::RegisterStateMismatch`.
+    pub fn resync_hardware_breakpoints(&mut self) -> Result<()> {
+        #[cfg(target_arch = "x86_64")]
+        {
+            let dr7 = self.debug_reg(7)?;
+            for index in 0..SUPPORTED_HARDWARE_BREAKPOINTS {
+                let addr = self.debug_reg(index)? as usize;
+                self.hardware_breakpoints[index] = HardwareBreakpoint::from_dr7(dr7, index, addr);
+            }
+            Ok(())
+        }
+
+        #[cfg(not(target_arch = "x86_64"))]
+        Err(HardwareBreakpointError::UnsupportedPlatform.into())
+    }
+
+    pub fn clear_all_hardware_breakpoints(&mut self) -> Result<()> {
+        for index in 0..SUPPORTED_HARDWARE_BREAKPOINTS {
+            match self.hardware_breakpoints[index] {
+                Some(_) => {
+                    self.clear_hardware_breakpoint(index)?;
+                }
+                None => (),
+            };
+        }
+        Ok(())
+    }
+
+    /// Watches `addr..addr + len` for writes by making the pages containing it read-only with
+    /// an injected `mprotect`. Unlike hardware watchpoints, there's no limit on how many regions
+    /// are watched or on their size.
+    ///
+    /// A write to a guarded page stops the debuggee with a `SIGSEGV`. Use `soft_watch_fault` to
+    /// check whether it hit a watched region and `step_over_soft_watch` to let the write happen.
+    /// Writes to other parts of the guarded pages fault too, so watching frequently written
+    /// pages is slow. Writes by the kernel, like a `read` into the region, fail with `EFAULT`
+    /// instead, while writes of the debugger with `write` aren't affected.
+    #[cfg(target_arch = "x86_64")]
+    pub fn soft_watch_region(&mut self, addr: usize, len: usize) -> Result<()> {
+        if len == 0 {
+            return Err(Error::Other("Can't watch an empty region".to_string()));
+        }
+        let pages = page_range(addr, len)
+            .ok_or_else(|| Error::Other(format!("Region at 0x{:x} overflows", addr)))?;
+
+        let maps = self.memory_maps()?;
+        let mut protections = Vec::new();
+        for page in pages.step_by(*PAGE_SIZE) {
+            if self.guarded_pages.contains_key(&page) {
+                continue;
+            }
+            let map = maps
+                .iter()
+                .find(|map| map.contains(page))
+                .ok_or(Error::NotMapped(page))?;
+            let mut prot = libc::PROT_NONE;
+            if map.is_readable {
+                prot |= libc::PROT_READ;
+            }
+            if map.is_writable {
+                prot |= libc::PROT_WRITE;
+            }
+            if map.is_executable {
+                prot |= libc::PROT_EXEC;
+            }
+            protections.push((page, prot));
+        }
+
+        self.soft_watch_regions.push((addr, len));
+        for (page, prot) in protections {
+            if let Err(err) = self.protect_page(page, prot & !libc::PROT_WRITE) {
+                self.clear_soft_watch_region(addr)?;
+                return Err(err);
+            }
+            self.guarded_pages.insert(page, prot);
+        }
+        Ok(())
+    }
+
+    /// Stops watching the region starting at `addr` set with `soft_watch_region`, restoring the
+    /// original protection of the pages no other watched region is on.
+    #[cfg(target_arch = "x86_64")]
+    pub fn clear_soft_watch_region(&mut self, addr: usize) -> Result<()> {
+        let index = self
+            .soft_watch_regions
+            .iter()
+            .position(|&(start, _)| start == addr)
+            .ok_or(Error::NoWatchedRegion(addr))?;
+        let (_, len) = self.soft_watch_regions.remove(index);
+
+        for page in page_range(addr, len).unwrap().step_by(*PAGE_SIZE) {
+            let still_watched = self
+                .soft_watch_regions
+                .iter()
+                .any(|&(start, len)| page_range(start, len).unwrap().contains(&page));
+            if still_watched {
+                continue;
+            }
+            if let Some(prot) = self.guarded_pages.remove(&page) {
+                self.protect_page(page, prot)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the regions watched with `soft_watch_region`, as start and length.
+    pub fn soft_watch_regions(&self) -> &[(usize, usize)] {
+        &self.soft_watch_regions
+    }
+
+    /// Checks whether the debuggee is stopped by a write to a page guarded with
+    /// `soft_watch_region`.
+    ///
+    /// Only the address the write faulted at is known, not its size, so a write that starts
+    /// right before a watched region on the same page is reported without a `region`.
+    pub fn soft_watch_fault(&self) -> Result<Option<SoftWatchFault>> {
+        let siginfo = self.siginfo()?;
+        if siginfo.si_signo != libc::SIGSEGV || siginfo.si_code != SEGV_ACCERR {
+            return Ok(None);
+        }
+        let addr = unsafe { siginfo.si_addr() } as usize;
+        if !self.guarded_pages.contains_key(&(addr & !(*PAGE_SIZE - 1))) {
+            return Ok(None);
+        }
+
+        let region = self
+            .soft_watch_regions
+            .iter()
+            .copied()
+            .find(|&(start, len)| start <= addr && addr - start < len);
+        Ok(Some(SoftWatchFault { addr, region }))
+    }
+
+    /// Lets the write the debuggee is stopped at, as reported by `soft_watch_fault`, happen.
+    ///
+    /// The guarded pages around the faulting address get their original protection back for a
+    /// single step, which executes the write without the `SIGSEGV`, and are guarded again
+    /// afterwards. Other threads keep running, so their writes to those pages during the step
+    /// go unnoticed. Returns the status of the step.
+    #[cfg(target_arch = "x86_64")]
+    pub fn step_over_soft_watch(&mut self) -> Result<nix::sys::wait::WaitStatus> {
+        let fault = self.soft_watch_fault()?.ok_or_else(|| {
+            Error::Other("The debuggee isn't stopped by a write to a guarded page".to_string())
+        })?;
+
+        // A write crossing into the neighbouring page would fault on it again.
+        let page = fault.addr & !(*PAGE_SIZE - 1);
+        let pages: Vec<_> = [
+            page.checked_sub(*PAGE_SIZE),
+            Some(page),
+            page.checked_add(*PAGE_SIZE),
+        ]
+        .iter()
+        .flatten()
+        .filter_map(|page| Some((*page, *self.guarded_pages.get(page)?)))
+        .collect();
+        for &(page, prot) in &pages {
+            self.protect_page(page, prot)?;
+        }
+
+        let status = self.step()?;
+
+        match status {
+            // The debuggee is gone, there's nothing to guard anymore.
+            nix::sys::wait::WaitStatus::Exited(..) | nix::sys::wait::WaitStatus::Signaled(..) => {}
+            _ => {
+                for &(page, prot) in &pages {
+                    self.protect_page(page, prot & !libc::PROT_WRITE)?;
+                }
+            }
+        }
+
+        Ok(status)
+    }
+
+    /// Sets the protection of the debuggee's page at `page` to `prot`.
+    #[cfg(target_arch = "x86_64")]
+    fn protect_page(&self, page: usize, prot: libc::c_int) -> Result<()> {
+        let res = self.mprotect(page as *mut _, *PAGE_SIZE, prot)?;
+        if res != 0 {
+            return Err(Error::Other(format!(
+                "mprotect of page 0x{:x} failed: {}",
+                page, res as i64
+            )));
+        }
+        Ok(())
+    }
+
+    /// Returns how many hardware breakpoints the debuggee's CPU supports, used or not.
+    ///
+    /// This is 4 on x86_64. On aarch64 the number of breakpoint and watchpoint slots depends on
+    /// the CPU and is queried from the kernel, 0 if that fails. Execute breakpoints and
+    /// watchpoints don't share slots there, see `hardware_breakpoint_slots`.
+    pub fn available_hardware_breakpoints(&self) -> usize {
+        #[cfg(target_arch = "aarch64")]
+        {
+            self.hardware_breakpoint_slots()
+                .map(|(breakpoints, watchpoints)| {
+                    std::cmp::min(breakpoints + watchpoints, SUPPORTED_HARDWARE_BREAKPOINTS)
+                })
+                .unwrap_or(0)
+        }
+
+        #[cfg(not(target_arch = "aarch64"))]
+        SUPPORTED_HARDWARE_BREAKPOINTS
+    }
+
+    /// Returns how many more hardware breakpoints can be set, see
+    /// `available_hardware_breakpoints`.
+    pub fn free_hardware_breakpoints(&self) -> usize {
+        let used = self
+            .hardware_breakpoints
+            .iter()
+            .filter(|breakpoint| breakpoint.is_some())
+            .count();
+        self.available_hardware_breakpoints().saturating_sub(used)
+    }
+
+    /// Returns the index of a hardware breakpoint whose condition caused the last debug exception,
+    /// and resets its condition bit for the next one.
+    pub fn is_hardware_breakpoint_triggered(&self) -> Result<Option<usize>> {
+        #[cfg(target_arch = "x86_64")]
+        {
+            let mut dr6 = self.debug_status()?.0;
+
+            for i in 0..SUPPORTED_HARDWARE_BREAKPOINTS {
+                if dr6 & (1 << i) != 0 && self.hardware_breakpoints[i].is_some() {
+                    // Clear bit for this breakpoint
+                    dr6 &= !(1 << i);
+                    self.set_debug_reg(6, dr6)?;
+
+                    return Ok(Some(i));
+                }
+            }
+
+            Ok(None)
+        }
+
+        #[cfg(target_arch = "aarch64")]
+        {
+            // There's no status register on aarch64, the kernel reports the address instead.
+            let siginfo = ptrace::getsiginfo(self.pid)?;
+            if siginfo.si_signo != libc::SIGTRAP || siginfo.si_code != TRAP_HWBKPT {
+                return Ok(None);
+            }
+            let addr = unsafe { siginfo.si_addr() } as usize;
+
+            Ok(self
+                .hardware_breakpoints
+                .iter()
+                .position(|breakpoint| match breakpoint {
+                    Some(breakpoint) if breakpoint.typ == HardwareBreakpointType::Execute => {
+                        breakpoint.addr == addr
+                    }
+                    // The accessed address is only known to be in the watched doubleword.
+                    Some(breakpoint) => breakpoint.addr & !7 == addr & !7,
+                    None => false,
+                }))
+        }
+
+        #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+        Err(HardwareBreakpointError::UnsupportedPlatform.into())
+    }
+
+    /// Like [`is_hardware_breakpoint_triggered`], but returns the triggered breakpoint too.
+    ///
+    /// [`is_hardware_breakpoint_triggered`]: #method.is_hardware_breakpoint_triggered
+    pub fn triggered_breakpoint(&self) -> Result<Option<(usize, &HardwareBreakpoint)>> {
+        Ok(self.is_hardware_breakpoint_triggered()?.and_then(|index| {
+            self.hardware_breakpoints[index]
+                .as_ref()
+                .map(|breakpoint| (index, breakpoint))
+        }))
+    }
+
+    /// Reads the debug status register, without resetting any of its bits.
+    pub fn debug_status(&self) -> Result<DebugStatus> {
+        #[cfg(target_arch = "x86_64")]
+        {
+            Ok(DebugStatus(self.read_dr6()?))
+        }
+
+        #[cfg(not(target_arch = "x86_64"))]
+        Err(HardwareBreakpointError::UnsupportedPlatform.into())
+    }
+
+    /// Reads the raw value of the debug status register DR6.
+    ///
+    /// Bits 0-3 (B0-B3) tell which hardware breakpoint conditions were met, bit 13 (BD) is set
+    /// for an access to the debug registers, bit 14 (BS) for a single step and bit 15 (BT) for a
+    /// task switch, see `DebugStatus`. Use `clear_dr6` to reset them once the exception has been
+    /// handled.
+    #[cfg(target_arch = "x86_64")]
+    pub fn read_dr6(&self) -> Result<u64> {
+        self.debug_reg(6)
+    }
+
+    /// Resets all bits of the debug status register DR6, e.g. after dispatching a debug exception
+    /// with `read_dr6`.
+    #[cfg(target_arch = "x86_64")]
+    pub fn clear_dr6(&self) -> Result<()> {
+        self.set_debug_reg(6, 0)
+    }
+
+    /// Reads the word at `offset` in the debuggee's `user` area, as laid out in `libc::user`.
+    ///
+    /// `offset` has to be word aligned and point into the general purpose registers
+    /// (`regs`, which are more conveniently read with `read_regs`) or the debug registers
+    /// (`u_debugreg`). The kernel rejects other offsets with `EIO`.
+    #[cfg(target_arch = "x86_64")]
+    pub fn peek_user(&self, offset: usize) -> Result<u64> {
+        let ret = unsafe {
+            nix::errno::Errno::clear();
+            libc::ptrace(
+                libc::PTRACE_PEEKUSER,
+                libc::pid_t::from(self.pid),
+                offset as *mut libc::c_void,
+                std::ptr::null_mut::<libc::c_void>(),
+            )
+        };
+        // The word may be -1, so only `errno` tells whether the read failed.
+        match nix::errno::Errno::result(ret) {
+            Ok(..) | Err(nix::Error::Sys(nix::errno::Errno::UnknownErrno)) => Ok(ret as u64),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Writes `val` to the word at `offset` in the debuggee's `user` area, as laid out in
+    /// `libc::user`.
+    ///
+    /// The same offsets as for `peek_user` are accepted. The kernel checks the written values,
+    /// e.g. it rejects segment selectors the debuggee couldn't load and DR7 values enabling
+    /// breakpoints at kernel addresses. Writing DR4 or DR5 always fails.
+    #[cfg(target_arch = "x86_64")]
+    pub fn poke_user(&self, offset: usize, val: u64) -> Result<()> {
+        let ret = unsafe {
+            libc::ptrace(
+                libc::PTRACE_POKEUSER,
+                libc::pid_t::from(self.pid),
+                offset as *mut libc::c_void,
+                val as *mut libc::c_void,
+            )
+        };
+        nix::errno::Errno::result(ret)?;
+        Ok(())
+    }
+
+    /// Reads the debug register `DR<index>`.
+    ///
+    /// The debug registers are the `u_debugreg` array of `libc::user`, 8 bytes each, so
+    /// `DR<index>` is at `DEBUG_REG_OFFSET + index * 8` in the `user` area. DR4 and DR5 are
+    /// reserved.
+    #[cfg(target_arch = "x86_64")]
+    fn debug_reg(&self, index: usize) -> Result<u64> {
+        self.peek_user(*DEBUG_REG_OFFSET + index * 8)
+    }
+
+    /// Writes the debug register `DR<index>`.
+    #[cfg(target_arch = "x86_64")]
+    fn set_debug_reg(&self, index: usize, val: u64) -> Result<()> {
+        self.poke_user(*DEBUG_REG_OFFSET + index * 8, val)
+    }
+
+    /// Returns the number of hardware breakpoint and watchpoint slots of the debuggee's CPU.
+    #[cfg(target_arch = "aarch64")]
+    pub fn hardware_breakpoint_slots(&self) -> Result<(usize, usize)> {
+        let breakpoints = self.read_hwdebug_state(NT_ARM_HW_BREAK)?.dbg_info & 0xff;
+        let watchpoints = self.read_hwdebug_state(NT_ARM_HW_WATCH)?.dbg_info & 0xff;
+        Ok((breakpoints as usize, watchpoints as usize))
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    fn read_hwdebug_state(&self, regset: usize) -> Result<UserHwdebugState> {
+        let mut state = UserHwdebugState::default();
+        let mut iov = libc::iovec {
+            iov_base: &mut state as *mut _ as *mut libc::c_void,
+            iov_len: std::mem::size_of::<UserHwdebugState>(),
+        };
+        unsafe {
+            let ret = libc::ptrace(
+                libc::PTRACE_GETREGSET,
+                libc::pid_t::from(self.pid),
+                regset as *mut libc::c_void,
+                &mut iov as *mut _ as *mut libc::c_void,
+            );
+            nix::errno::Errno::result(ret)?;
+        }
+        Ok(state)
+    }
+
+    /// Writes all hardware breakpoints to the breakpoint and watchpoint registers.
+    #[cfg(target_arch = "aarch64")]
+    fn write_hardware_debug_regs(&self) -> Result<()> {
+        for &(regset, watchpoints) in &[(NT_ARM_HW_BREAK, false), (NT_ARM_HW_WATCH, true)] {
+            let mut state = self.read_hwdebug_state(regset)?;
+            let slots = (state.dbg_info & 0xff) as usize;
+
+            let breakpoints: Vec<_> = self
+                .hardware_breakpoints
+                .iter()
+                .flatten()
+                .filter(|breakpoint| {
+                    (breakpoint.typ != HardwareBreakpointType::Execute) == watchpoints
+                })
+                .collect();
+            if breakpoints.len() > slots {
+                return Err(HardwareBreakpointError::NoEmptyWatchpoint.into());
+            }
+
+            for (slot, reg) in state.dbg_regs.iter_mut().enumerate().take(slots) {
+                *reg = match breakpoints.get(slot) {
+                    Some(breakpoint) => {
+                        let (addr, ctrl) = breakpoint.aarch64_regs()?;
+                        UserHwdebugReg { addr, ctrl, pad: 0 }
+                    }
+                    None => UserHwdebugReg::default(),
+                };
+            }
+
+            let mut iov = libc::iovec {
+                iov_base: &mut state as *mut _ as *mut libc::c_void,
+                iov_len: 8 + slots * std::mem::size_of::<UserHwdebugReg>(),
+            };
+            unsafe {
+                let ret = libc::ptrace(
+                    libc::PTRACE_SETREGSET,
+                    libc::pid_t::from(self.pid),
+                    regset as *mut libc::c_void,
+                    &mut iov as *mut _ as *mut libc::c_void,
+                );
+                nix::errno::Errno::result(ret)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the indices of `count` unused hardware breakpoints, or `None` if there aren't
+    /// enough of them.
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    fn find_empty_watchpoints(&self, count: usize) -> Option<Vec<usize>> {
+        let empty: Vec<_> = (0..SUPPORTED_HARDWARE_BREAKPOINTS)
+            .filter(|&index| self.hardware_breakpoints[index].is_none())
+            .take(count)
+            .collect();
+        if empty.len() < count {
+            return None;
+        }
+        Some(empty)
+    }
+}
+
+/// Returns the value of the XMM register `index` from the floating point registers.
+///
+/// # Panics
+///
+/// Panics if `index` is not in range of XMM0-XMM15.
+#[cfg(target_arch = "x86_64")]
+pub fn xmm_register(fpregs: &libc::user_fpregs_struct, index: usize) -> [u8; 16] {
+    let mut xmm = [0; 16];
+    for (bytes, word) in xmm
+        .chunks_exact_mut(4)
+        .zip(&fpregs.xmm_space[index * 4..index * 4 + 4])
+    {
+        bytes.copy_from_slice(&word.to_ne_bytes());
+    }
+    xmm
+}
+
+/// Sets the value of the XMM register `index` in the floating point registers.
+/// Use `LinuxTarget::write_fpregs` to apply the change to the debuggee.
+///
+/// # Panics
+///
+/// Panics if `index` is not in range of XMM0-XMM15.
+#[cfg(target_arch = "x86_64")]
+pub fn set_xmm_register(fpregs: &mut libc::user_fpregs_struct, index: usize, value: [u8; 16]) {
+    for (word, bytes) in fpregs.xmm_space[index * 4..index * 4 + 4]
+        .iter_mut()
+        .zip(value.chunks_exact(4))
+    {
+        *word = u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+}
+
+/// Returns the field of `regs` for the register called `name`, like `"rax"`, `"rip"` or
+/// `"eflags"`.
+#[cfg(target_arch = "x86_64")]
+fn user_register_mut<'a>(regs: &'a mut libc::user_regs_struct, name: &str) -> Option<&'a mut u64> {
+    Some(match name {
+        "rax" => &mut regs.rax,
+        "rbx" => &mut regs.rbx,
+        "rcx" => &mut regs.rcx,
+        "rdx" => &mut regs.rdx,
+        "rsi" => &mut regs.rsi,
+        "rdi" => &mut regs.rdi,
+        "rbp" => &mut regs.rbp,
+        "rsp" => &mut regs.rsp,
+        "r8" => &mut regs.r8,
+        "r9" => &mut regs.r9,
+        "r10" => &mut regs.r10,
+        "r11" => &mut regs.r11,
+        "r12" => &mut regs.r12,
+        "r13" => &mut regs.r13,
+        "r14" => &mut regs.r14,
+        "r15" => &mut regs.r15,
+        "rip" => &mut regs.rip,
+        "eflags" => &mut regs.eflags,
+        "orig_rax" => &mut regs.orig_rax,
+        "cs" => &mut regs.cs,
+        "ss" => &mut regs.ss,
+        "ds" => &mut regs.ds,
+        "es" => &mut regs.es,
+        "fs" => &mut regs.fs,
+        "gs" => &mut regs.gs,
+        "fs_base" => &mut regs.fs_base,
+        "gs_base" => &mut regs.gs_base,
+        _ => return None,
+    })
+}
+
+/// Returns the addresses of the pages containing `addr..addr + len`, `None` on overflow.
+#[cfg(target_arch = "x86_64")]
+fn page_range(addr: usize, len: usize) -> Option<std::ops::Range<usize>> {
+    let end = addr.checked_add(len)?.checked_add(*PAGE_SIZE - 1)? & !(*PAGE_SIZE - 1);
+    Some(addr & !(*PAGE_SIZE - 1)..end)
+}
+
+/// Returns the start of a process's virtual memory address range.
+/// This can be useful for calculation of relative addresses in memory.
+pub fn get_addr_range(pid: Pid) -> Result<usize> {
+    let file = File::open(format!("/proc/{}/maps", pid))?;
+    let mut buf_read = BufReader::new(file);
+    let mut proc_map = String::new();
+
+    buf_read.read_line(&mut proc_map)?;
+
+    let proc_data: Vec<_> = proc_map.split(' ').collect();
+    let addr_range: Vec<_> = proc_data[0].split('-').collect();
+
+    usize::from_str_radix(addr_range[0], 16).map_err(|err| Error::Other(err.to_string()))
+}
+
+/// Splits the NUL-terminated strings of a `/proc` file like `cmdline`. Invalid UTF-8 sequences
+/// are replaced with `U+FFFD`.
+fn split_nul(data: &[u8]) -> Vec<String> {
+    let data = data.strip_suffix(&[0]).unwrap_or(data);
+    if data.is_empty() {
+        return Vec::new();
+    }
+    data.split(|&byte| byte == 0)
+        .map(|string| String::from_utf8_lossy(string).into_owned())
+        .collect()
+}
+
+#[cfg(target_arch = "x86_64")]
+fn is_sigtrap(status: nix::sys::wait::WaitStatus) -> bool {
+    matches!(
+        status,
+        nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use super::{
+        memory::{PAGE_SIZE, UIO_MAXIOV},
+        AttachOptions, LinuxTarget, ReadMemory,
+    };
+    use crate::target::MapFilter;
+    use nix::{
+        sys::{
+            mman::{mprotect, ProtFlags},
+            ptrace, signal, wait,
+        },
+        unistd::{fork, getpid, ForkResult},
+    };
+    use std::alloc::{alloc_zeroed, dealloc, Layout};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Barrier};
+    use std::{mem, ptr, thread, time};
+
+    #[test]
+    fn linux_target_is_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<LinuxTarget>();
+    }
+
+    #[test]
+    fn read_memory() {
+        let var: usize = 52;
+        let var2: u8 = 128;
+
+        let mut read_var_op: usize = 0;
+        let mut read_var2_op: u8 = 0;
+
+        unsafe {
+            let target = LinuxTarget::new(getpid());
+            ReadMemory::new(&target)
+                .read(&mut read_var_op, &var as *const _ as usize)
+                .read(&mut read_var2_op, &var2 as *const _ as usize)
+                .apply()
+                .expect("Failed to apply mem_op");
+        }
+
+        assert_eq!(read_var2_op, var2);
+        assert_eq!(read_var_op, var);
+    }
+
+    #[test]
+    fn cmdline() {
+        let cmdline = LinuxTarget::me().cmdline().unwrap();
+        assert_eq!(cmdline, std::env::args().collect::<Vec<_>>());
+
+        assert!(split_nul(b"").is_empty());
+        assert_eq!(split_nul(b"a\0\0b\0"), ["a", "", "b"]);
+    }
+
+    #[test]
+    fn environ() {
+        let environ = LinuxTarget::me().environ().unwrap();
+        assert_eq!(
+            environ.get("CARGO_PKG_NAME").map(String::as_str),
+            Some(env!("CARGO_PKG_NAME"))
+        );
+        for (key, value) in &environ {
+            assert!(!key.contains('='));
+            assert_eq!(std::env::var(key).as_ref(), Ok(value));
+        }
+    }
+
+    #[test]
+    fn auxv() {
+        let auxv = LinuxTarget::me().auxv().unwrap();
+        assert_eq!(auxv[&libc::AT_PAGESZ], *PAGE_SIZE as u64);
+        assert_eq!(
+            auxv[&libc::AT_SYSINFO_EHDR],
+            unsafe { libc::getauxval(libc::AT_SYSINFO_EHDR) } as u64
+        );
+    }
+
+    #[test]
+    fn read_write_bytes() {
+        let data: Vec<u8> = (0..10 * 1024).map(|i| i as u8).collect();
+        let mut buf = vec![0u8; data.len()];
+
+        let target = LinuxTarget::me();
+        target
+            .write_bytes(buf.as_mut_ptr() as usize, &data)
+            .expect("Failed to write bytes");
+        assert_eq!(buf, data);
+
+        let read = target
+            .read_bytes(buf.as_ptr() as usize, buf.len())
+            .expect("Failed to read bytes");
+        assert_eq!(read, data);
+    }
+
+    #[test]
+    fn read_string() {
+        static HELLO: &[u8] = b"Hello, world!\0";
+        let hello = std::ffi::CStr::from_bytes_with_nul(HELLO).unwrap();
+
+        let target = LinuxTarget::me();
+        let addr = hello.as_ptr() as usize;
+
+        assert_eq!(target.read_string(addr, 100).unwrap(), "Hello, world!");
+        assert_eq!(target.read_string(addr, 5).unwrap(), "Hello");
+        assert_eq!(target.read_string(addr, 0).unwrap(), "");
+    }
+
+    #[test]
+    fn read_string_until_unreadable_page() {
+        let layout = Layout::from_size_align(2 * *PAGE_SIZE, *PAGE_SIZE).unwrap();
+
+        unsafe {
+            let ptr = alloc_zeroed(layout);
+            let string_ptr = ptr.add(*PAGE_SIZE - 4);
+            ptr::copy_nonoverlapping(b"abcd".as_ptr(), string_ptr, 4);
+
+            // The string isn't terminated before the end of the readable memory.
+            mprotect(
+                ptr.add(*PAGE_SIZE) as *mut std::ffi::c_void,
+                *PAGE_SIZE,
+                ProtFlags::PROT_NONE,
+            )
+            .expect("Failed to mprotect");
+
+            let res = LinuxTarget::me().read_string(string_ptr as usize, 100);
+
+            mprotect(
+                ptr.add(*PAGE_SIZE) as *mut std::ffi::c_void,
+                *PAGE_SIZE,
+                ProtFlags::PROT_READ | ProtFlags::PROT_WRITE,
+            )
+            .expect("Failed to mprotect");
+            dealloc(ptr, layout);
+
+            assert_eq!(res.unwrap(), "abcd");
+        }
+    }
+
+    #[test]
+    fn read_slice_uninit() {
+        // More pages than fit into a single `process_vm_readv` call.
+        let len = 2 * UIO_MAXIOV * *PAGE_SIZE + 3;
+        let data: Vec<u8> = (0..len).map(|i| i as u8).collect();
+        let target = LinuxTarget::me();
+
+        // Reading 64 MiB from this process in a release build took ~35ms either way into a
+        // freshly allocated buffer, as `vec![0; len]` gets zeroed pages from the OS for free.
+        // Reusing a buffer it took ~23ms after zeroing it and ~17ms with `read_slice_uninit`.
+        let mut buf: Vec<mem::MaybeUninit<u8>> = Vec::with_capacity(len);
+        unsafe { buf.set_len(len) };
+        let read_len = target
+            .read()
+            .read_slice_uninit(&mut buf, data.as_ptr() as usize)
+            .apply_uninit()
+            .expect("Failed to apply mem_op");
+        assert_eq!(read_len, len);
+        let buf: Vec<u8> = buf
+            .into_iter()
+            .map(|byte| unsafe { byte.assume_init() })
+            .collect();
+        assert_eq!(buf, data);
+
+        let mut zeroed = vec![0u8; len];
+        target
+            .read()
+            .read_byte_slice(&mut zeroed, data.as_ptr() as usize)
+            .apply()
+            .expect("Failed to apply mem_op");
+        assert_eq!(zeroed, data);
+    }
+
+    #[test]
+    fn read_slice_uninit_until_unreadable_page() {
+        let layout = Layout::from_size_align(2 * *PAGE_SIZE, *PAGE_SIZE).unwrap();
+        let mut buf = vec![mem::MaybeUninit::<u8>::uninit(); 2 * *PAGE_SIZE];
+
+        unsafe {
+            let ptr = alloc_zeroed(layout);
+            mprotect(
+                ptr.add(*PAGE_SIZE) as *mut std::ffi::c_void,
+                *PAGE_SIZE,
+                ProtFlags::PROT_NONE,
+            )
+            .expect("Failed to mprotect");
+
+            // There's no ptrace fallback for this process, so the read stops at the second page.
+            let res = LinuxTarget::me()
+                .read()
+                .read_slice_uninit(&mut buf, ptr as usize)
+                .apply_uninit();
+
+            mprotect(
+                ptr.add(*PAGE_SIZE) as *mut std::ffi::c_void,
+                *PAGE_SIZE,
+                ProtFlags::PROT_READ | ProtFlags::PROT_WRITE,
+            )
+            .expect("Failed to mprotect");
+            dealloc(ptr, layout);
+
+            assert_eq!(res.unwrap(), *PAGE_SIZE);
+        }
+    }
+
+    #[test]
+    fn read_many_scattered() {
+        let data: Vec<u64> = (0..100 * 1024).map(|i| i * 3).collect();
+        let mut fields = [0u64; 100];
+        let target = LinuxTarget::me();
+
+        // Reading 100 fields 8 KiB apart took ~18µs with a single `apply` in a release build,
+        // and ~60µs with an `apply` per field.
+        let mut read_mem = target.read();
+        for (i, field) in fields.iter_mut().enumerate() {
+            read_mem = unsafe { read_mem.read(field, &data[i * 1024 + i] as *const _ as usize) };
+        }
+        read_mem.apply().expect("Failed to apply mem_op");
+
+        for (i, field) in fields.iter().enumerate() {
+            assert_eq!(*field, data[i * 1024 + i]);
+        }
+    }
+
+    #[test]
+    fn read_protected_memory() {
+        let mut read_var1_op: u8 = 0;
+        let mut read_var2_op: usize = 0;
+
+        let var1: u8 = 1;
+        let var2: usize = 2;
+
+        let layout = Layout::from_size_align(2 * *PAGE_SIZE, *PAGE_SIZE).unwrap();
+
+        unsafe {
+            let ptr = alloc_zeroed(layout);
+
+            match fork() {
+                Ok(ForkResult::Child) => {
+                    ptr::write(ptr, var1);
+
+                    mprotect(
+                        ptr as *mut std::ffi::c_void,
+                        *PAGE_SIZE,
+                        ProtFlags::PROT_WRITE,
+                    )
+                    .expect("Failed to mprotect");
+
+                    // Wait for the parent to read memory before terminating this process
+                    thread::sleep(time::Duration::from_millis(300));
+
+                    dealloc(ptr, layout);
+                }
+                Ok(ForkResult::Parent { child, .. }) => {
+                    thread::sleep(time::Duration::from_millis(100));
+
+                    let (target, _wait_status) = LinuxTarget::attach(
+                        child,
+                        AttachOptions {
+                            kill_on_exit: true,
+                            ..Default::default()
+                        },
+                    )
+                    .expect("Couldn't attach to child");
+
+                    target
+                        .read()
+                        .read(&mut read_var1_op, ptr as *const _ as usize)
+                        .read(&mut read_var2_op, &var2 as *const _ as usize)
+                        .apply()
+                        .expect("ReadMemory failed");
+
+                    assert_eq!(ptr::read_volatile(&read_var1_op), var1);
+                    assert_eq!(ptr::read_volatile(&read_var2_op), var2);
+
+                    dealloc(ptr, layout);
+
+                    ptrace::cont(child, Some(signal::Signal::SIGCONT)).unwrap();
+
+                    wait::waitpid(child, None).unwrap();
+                }
+                Err(x) => panic!(x),
+            }
+        }
+    }
+
+    /// This test attempts to read memory from 2 consecutive pages, one of which is read-protected.
+    /// `ReadMemory` implementation should properly choose the memory reading strategy to cover this case:
+    /// for read-protected page, it should use `ptrace()` and still return a valid result.
+    #[test]
+    fn read_cross_page_memory() {
+        let mut read_var_op = vec![0u32; *PAGE_SIZE + 2];
+
+        let mut var = vec![123u32; *PAGE_SIZE + 2];
+        var[0] = 321;
+        var[*PAGE_SIZE + 1] = 234;
+
+        unsafe {
+            let layout = Layout::from_size_align(*PAGE_SIZE * 3, *PAGE_SIZE).unwrap();
+            let ptr = alloc_zeroed(layout);
+
+            let array_ptr = ptr.offset((*PAGE_SIZE - mem::size_of::<u32>()) as isize);
+            let second_page_ptr = ptr.offset(*PAGE_SIZE as _);
+
+            match fork() {
+                Ok(ForkResult::Child) => {
+                    ptr::copy_nonoverlapping(var.as_ptr(), array_ptr as *mut u32, *PAGE_SIZE + 2);
+
+                    mprotect(second_page_ptr as *mut _, *PAGE_SIZE, ProtFlags::PROT_WRITE)
+                        .expect("Failed to mprotect");
+
+                    // Parent reads memory
+                    thread::sleep(time::Duration::from_millis(300));
+
+                    std::process::exit(0);
+                }
+                Ok(ForkResult::Parent { child, .. }) => {
+                    thread::sleep(time::Duration::from_millis(100));
+
+                    let (target, _wait_status) = LinuxTarget::attach(
+                        child,
+                        AttachOptions {
+                            kill_on_exit: true,
+                            ..Default::default()
+                        },
+                    )
+                    .expect("Couldn't attach to child");
+
+                    target
+                        .read()
+                        .read_slice(&mut read_var_op, array_ptr as *const _ as usize)
+                        .apply()
+                        .expect("Failed to apply mem_op");
+
+                    for i in 0..(*PAGE_SIZE + 2) {
+                        assert_eq!(var[i], read_var_op[i]);
+                    }
+
+                    dealloc(ptr, layout);
+
+                    ptrace::cont(child, Some(signal::Signal::SIGCONT)).unwrap();
+
+                    wait::waitpid(child, None).unwrap();
+                }
+                Err(x) => panic!(x),
+            }
+        }
+    }
+
+    #[test]
+    fn read_struct() {
+        #[repr(C)]
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        struct Header {
+            magic: u32,
+            flags: u16,
+            kind: u16,
+            len: u64,
+        }
+        unsafe impl bytemuck::Zeroable for Header {}
+        unsafe impl bytemuck::Pod for Header {}
+
+        let header = Header {
+            magic: 0x7f45_4c46,
+            flags: 3,
+            kind: 0xffff,
+            len: 1 << 40,
+        };
+        let read: Header = LinuxTarget::me()
+            .read_struct(&header as *const Header as usize)
+            .expect("Failed to read struct");
+        assert_eq!(read, header);
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn read_tls() {
+        thread_local! {
+            static COUNTER: std::cell::Cell<u64> = const { std::cell::Cell::new(0) };
+        }
+
+        let (read_fd, write_fd) = nix::unistd::pipe().unwrap();
+        match fork() {
+            Ok(ForkResult::Child) => {
+                ptrace::traceme().unwrap();
+                signal::kill(getpid(), signal::Signal::SIGSTOP).unwrap();
+
+                thread::spawn(move || {
+                    COUNTER.with(|counter| counter.set(1234));
+                    let addr = COUNTER.with(|counter| counter.as_ptr() as usize);
+                    // `%fs:0` points to itself, it's the thread pointer.
+                    let thread_pointer: usize;
+                    unsafe { std::arch::asm!("mov {}, fs:0", out(reg) thread_pointer) };
+                    let offset = addr.wrapping_sub(thread_pointer) as isize;
+                    let tid = nix::unistd::gettid().as_raw();
+
+                    let mut message = [0u8; 12];
+                    message[..4].copy_from_slice(&tid.to_ne_bytes());
+                    message[4..].copy_from_slice(&offset.to_ne_bytes());
+                    nix::unistd::write(write_fd, &message).unwrap();
+                    loop {
+                        thread::park();
+                    }
+                });
+                loop {
+                    thread::park();
+                }
+            }
+            Ok(ForkResult::Parent { child, .. }) => {
+                wait::waitpid(child, None).unwrap();
+                let target = LinuxTarget::new(child);
+                ptrace::cont(child, None).unwrap();
+
+                let mut message = [0u8; 12];
+                assert_eq!(nix::unistd::read(read_fd, &mut message).unwrap(), 12);
+                let mut tid = [0; 4];
+                tid.copy_from_slice(&message[..4]);
+                let tid = i32::from_ne_bytes(tid);
+                let mut offset = [0; 8];
+                offset.copy_from_slice(&message[4..]);
+                let offset = isize::from_ne_bytes(offset);
+
+                // Only the main thread is traced, so attach to the spawned one.
+                let thread = Pid::from_raw(tid);
+                ptrace::attach(thread).unwrap();
+                wait::waitpid(thread, Some(wait::WaitPidFlag::__WALL)).unwrap();
+                assert_eq!(target.read_tls::<u64>(tid, offset).unwrap(), 1234);
+                // An exited thread that is still traced has to be reaped before its leader.
+                ptrace::detach(thread, None).unwrap();
+
+                signal::kill(child, signal::Signal::SIGKILL).unwrap();
+                wait::waitpid(child, None).unwrap();
+            }
+            Err(x) => panic!("{}", x),
+        }
+    }
+
+    #[test]
+    fn breakpoint_at() {
+        static CODE: [u8; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+
+        match fork() {
+            Ok(ForkResult::Child) => {
+                ptrace::traceme().unwrap();
+                signal::kill(getpid(), signal::Signal::SIGSTOP).unwrap();
+                std::process::exit(0);
+            }
+            Ok(ForkResult::Parent { child, .. }) => {
+                wait::waitpid(child, None).unwrap();
+
+                let mut target = LinuxTarget::new(child);
+                let addr = CODE.as_ptr() as usize;
+                assert_eq!(target.breakpoint_at(addr + 2), None);
+                target.set_breakpoint(addr + 2).unwrap();
+                assert_eq!(target.breakpoint_at(addr + 2), Some(3));
+                assert_eq!(target.breakpoint_at(addr + 3), None);
+
+                // Reads hiding the breakpoints see the original byte instead of the `int3`.
+                let mut buf = [0u8; 8];
+                target
+                    .read()
+                    .hide_breakpoints()
+                    .read_byte_slice(&mut buf, addr)
+                    .apply()
+                    .unwrap();
+                assert_eq!(buf, CODE);
+
+                // The breakpoint is hidden in the range it falls into.
+                let mut before = [0u8; 2];
+                let mut after = [0u8; 4];
+                let read_len = target
+                    .read()
+                    .hide_breakpoints()
+                    .read_byte_slice(&mut before, addr)
+                    .read_byte_slice(&mut after, addr + 2)
+                    .apply_uninit()
+                    .unwrap();
+                assert_eq!(read_len, 6);
+                assert_eq!(before, [1, 2]);
+                assert_eq!(after, [3, 4, 5, 6]);
+
+                target.clear_breakpoint(addr + 2).unwrap();
+                assert_eq!(target.breakpoint_at(addr + 2), None);
+
+                let status = target.cont(None).unwrap();
+                assert_eq!(status, wait::WaitStatus::Exited(child, 0));
+            }
+            Err(x) => panic!("{}", x),
+        }
+    }
+
+    #[test]
+    fn open_files() {
+        let file = File::open(env!("CARGO_MANIFEST_DIR").to_string() + "/Cargo.toml").unwrap();
+        let files = LinuxTarget::me().open_files().unwrap();
+
+        let fds: Vec<_> = files.iter().map(|file| file.fd).collect();
+        assert!(fds.contains(&0) && fds.contains(&1), "{:?}", files);
+        let mut sorted = fds.clone();
+        sorted.sort();
+        assert_eq!(fds, sorted);
+
+        let cargo_toml = files
+            .iter()
+            .find(|open_file| open_file.fd == file.as_raw_fd())
+            .unwrap();
+        assert_eq!(
+            cargo_toml.path,
+            std::fs::canonicalize(env!("CARGO_MANIFEST_DIR").to_string() + "/Cargo.toml").unwrap()
+        );
+    }
+
+    #[test]
+    fn status() {
+        let status = LinuxTarget::me().status().unwrap();
+        assert!(status.vm_rss.unwrap() > 0);
+        assert!(status.vm_size.unwrap() >= status.vm_rss.unwrap());
+        assert!(status.threads >= 1);
+        assert_eq!(status.parent_pid, nix::unistd::getppid());
+        assert_eq!(status.tracer_pid, None);
+
+        match fork() {
+            Ok(ForkResult::Child) => {
+                ptrace::traceme().unwrap();
+                signal::kill(getpid(), signal::Signal::SIGSTOP).unwrap();
+                std::process::exit(0);
+            }
+            Ok(ForkResult::Parent { child, .. }) => {
+                wait::waitpid(child, None).unwrap();
+
+                let target = LinuxTarget::new(child);
+                let status = target.status().unwrap();
+                assert_eq!(status.parent_pid, getpid());
+                // The tracer is the thread that forked.
+                assert_eq!(status.tracer_pid, Some(nix::unistd::gettid()));
+                assert_eq!(status.threads, 1);
+
+                let status = target.cont(None).unwrap();
+                assert_eq!(status, wait::WaitStatus::Exited(child, 0));
+            }
+            Err(x) => panic!("{}", x),
+        }
+    }
+
+    #[test]
+    fn search_memory() {
+        static NEEDLE: [u8; 16] = *b"headcrab-needle!";
+        let target = LinuxTarget::me();
+        let needle_addr = NEEDLE.as_ptr() as usize;
+
+        let matches = target.search_memory(&NEEDLE, MapFilter::All).unwrap();
+        assert!(matches.contains(&needle_addr));
+        assert!(matches.windows(2).all(|pair| pair[0] < pair[1]));
+        // `NEEDLE` is read-only.
+        let matches = target.search_memory(&NEEDLE, MapFilter::Writable).unwrap();
+        assert!(!matches.contains(&needle_addr));
+
+        // Put another needle across a page boundary on the heap.
+        let mut heap = vec![0u8; 3 * *PAGE_SIZE];
+        let page = (heap.as_ptr() as usize + *PAGE_SIZE) & !(*PAGE_SIZE - 1);
+        let offset = page - heap.as_ptr() as usize - 5;
+        heap[offset..offset + 16].copy_from_slice(b"HEADCRAB-needle?");
+        let spanning_addr = heap.as_ptr() as usize + offset;
+        let matches = target
+            .search_memory(b"HEADCRAB-needle?", MapFilter::Anonymous)
+            .unwrap();
+        assert!(matches.contains(&spanning_addr), "{:?}", matches);
+
+        assert!(target
+            .search_memory(&[], MapFilter::All)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn search_memory_masked() {
+        static DATA: [u8; 13] = *b"crab\x01\x02\xabcrab!!";
+        let target = LinuxTarget::me();
+        let data_addr = DATA.as_ptr() as usize;
+
+        // `crab`, two wildcards, a byte with the upper nibble `0xa` and `crab`.
+        let pattern = b"crab\x00\x00\xa0crab";
+        let mask = b"\xff\xff\xff\xff\x00\x00\xf0\xff\xff\xff\xff";
+        let matches = target
+            .search_memory_masked(pattern, mask, MapFilter::All)
+            .unwrap();
+        assert!(matches.contains(&data_addr), "{:?}", matches);
+
+        let mut mismatch = *mask;
+        // `0xab` doesn't match `0xa0` exactly.
+        mismatch[6] = 0xff;
+        let matches = target
+            .search_memory_masked(pattern, &mismatch, MapFilter::All)
+            .unwrap();
+        assert!(!matches.contains(&data_addr));
+
+        assert!(matches!(
+            target.search_memory_masked(pattern, &mask[1..], MapFilter::All),
+            Err(Error::PatternMaskLength { .. })
+        ));
+    }
+
+    #[test]
+    fn read_proc_mem() {
+        let len = 4 << 20;
+        let data: Vec<u8> = (0..len).map(|i| (i * 7) as u8).collect();
+        let target = LinuxTarget::me();
+
+        // Reading 4 MiB from this process in a release build took ~0.8ms with `use_proc_mem`
+        // and ~0.65ms with `process_vm_readv`.
+        let mut proc_mem = vec![0u8; len];
+        target
+            .read()
+            .use_proc_mem()
+            .read_byte_slice(&mut proc_mem, data.as_ptr() as usize)
+            .apply()
+            .expect("Failed to apply mem_op");
+        let mut process_vm = vec![0u8; len];
+        target
+            .read()
+            .read_byte_slice(&mut process_vm, data.as_ptr() as usize)
+            .apply()
+            .expect("Failed to apply mem_op");
+        assert_eq!(proc_mem, process_vm);
+        assert_eq!(proc_mem, data);
+
+        let mut fields = [0u8; 100];
+        let mut read_mem = target.read().use_proc_mem();
+        for (i, field) in fields.iter_mut().enumerate() {
+            read_mem = unsafe { read_mem.read(field, &data[i * 1024] as *const _ as usize) };
+        }
+        read_mem.apply().expect("Failed to apply mem_op");
+        for (i, field) in fields.iter().enumerate() {
+            assert_eq!(*field, data[i * 1024]);
+        }
+    }
+
+    #[test]
+    fn read_ptr() {
+        #[repr(C)]
+        struct Inner {
+            _padding: u32,
+            value: u64,
+        }
+        #[repr(C)]
+        struct Outer {
+            inner: Box<Inner>,
+        }
+        let outer = Box::new(Outer {
+            inner: Box::new(Inner {
+                _padding: 0,
+                value: 0x1122_3344_5566_7788,
+            }),
+        });
+        let outer_ptr = &*outer as *const Outer as usize;
+        let target = LinuxTarget::me();
+
+        let inner_ptr = target.read_ptr(outer_ptr).unwrap();
+        assert_eq!(inner_ptr, &*outer.inner as *const Inner as usize);
+        let value_addr = inner_ptr + mem::size_of::<u64>();
+        assert_eq!(&outer.inner.value as *const u64 as usize, value_addr);
+        assert_eq!(
+            target.read_u64_le(value_addr).unwrap(),
+            0x1122_3344_5566_7788
+        );
+        assert_eq!(target.read_u32_le(value_addr).unwrap(), 0x5566_7788);
+
+        let mut dest = [0u8; 16];
+        let dest_addr = dest.as_mut_ptr() as usize;
+        target.write_u32_le(dest_addr, 0x1122_3344).unwrap();
+        target
+            .write_u64_le(dest_addr + 4, 0x5566_7788_99aa_bbcc)
+            .unwrap();
+        target.write_ptr(dest_addr + 8, inner_ptr).unwrap();
+        let dest = unsafe { ptr::read_volatile(&dest) };
+        assert_eq!(&dest[..4], &[0x44, 0x33, 0x22, 0x11]);
+        assert_eq!(&dest[4..8], &[0xcc, 0xbb, 0xaa, 0x99]);
+        assert_eq!(&dest[8..], &inner_ptr.to_ne_bytes());
+    }
+
+    #[test]
+    fn follow_chain() {
+        #[repr(C)]
+        struct Node {
+            value: u64,
+            next: Option<Box<Node>>,
+        }
+        let list = Box::new(Node {
+            value: 1,
+            next: Some(Box::new(Node {
+                value: 2,
+                next: Some(Box::new(Node {
+                    value: 3,
+                    next: None,
+                })),
+            })),
+        });
+        let head = &*list as *const Node;
+        let base = &head as *const *const Node as usize;
+        let next = mem::size_of::<u64>() as isize;
+        let target = LinuxTarget::me();
+
+        assert_eq!(target.follow_chain(base, &[]).unwrap(), head as usize);
+        assert_eq!(
+            target.follow_chain(base, &[next]).unwrap(),
+            head as usize + 8
+        );
+        let third = target.follow_chain(base, &[next, next, 0]).unwrap();
+        assert_eq!(target.read_u64_le(third).unwrap(), 3);
+        let second = target.follow_chain(base, &[next, 0]).unwrap();
+        assert_eq!(target.read_u64_le(second).unwrap(), 2);
+
+        // The next pointer of the third node is null, so it can't be followed.
+        assert_eq!(
+            target.follow_chain(base, &[next, next, next, 0]).unwrap(),
+            0
+        );
+        assert!(matches!(
+            target.follow_chain(base, &[next, next, next, 0, 0]),
+            Err(Error::BrokenChain {
+                level: 4,
+                addr: 0,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn apply_and_keep() {
+        let target = LinuxTarget::me();
+
+        let source = Box::new(1u64);
+        let source_ptr = Box::into_raw(source);
+        let mut dest = 0u64;
+        let dest_ptr = &mut dest as *mut u64;
+        let mut read_mem = unsafe { target.read().read_ptr(dest_ptr, source_ptr as usize) };
+        read_mem.apply_and_keep().expect("Failed to apply mem_op");
+        assert_eq!(unsafe { ptr::read_volatile(dest_ptr) }, 1);
+        unsafe { ptr::write_volatile(source_ptr, 2) };
+        read_mem.apply_and_keep().expect("Failed to apply mem_op");
+        assert_eq!(unsafe { ptr::read_volatile(dest_ptr) }, 2);
+
+        // Nothing is read anymore.
+        read_mem.clear();
+        unsafe { ptr::write_volatile(source_ptr, 3) };
+        read_mem.apply_and_keep().expect("Failed to apply mem_op");
+        assert_eq!(unsafe { ptr::read_volatile(dest_ptr) }, 2);
+        drop(unsafe { Box::from_raw(source_ptr) });
+
+        let value = Cell::new(5u64);
+        let mut write_mem = target.write().write(&value, dest_ptr as usize);
+        write_mem.apply_and_keep().expect("Failed to apply mem_op");
+        assert_eq!(unsafe { ptr::read_volatile(dest_ptr) }, 5);
+        value.set(6);
+        write_mem.apply_and_keep().expect("Failed to apply mem_op");
+        assert_eq!(unsafe { ptr::read_volatile(dest_ptr) }, 6);
+
+        write_mem.clear();
+        value.set(7);
+        write_mem.apply_and_keep().expect("Failed to apply mem_op");
+        assert_eq!(unsafe { ptr::read_volatile(dest_ptr) }, 6);
+    }
+
+    #[test]
+    fn read_proc_mem_until_unmapped_page() {
+        let mut buf = vec![mem::MaybeUninit::<u8>::uninit(); 2 * *PAGE_SIZE];
+
+        unsafe {
+            let ptr = libc::mmap(
+                ptr::null_mut(),
+                2 * *PAGE_SIZE,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            ) as *mut u8;
+            assert_ne!(ptr as *mut libc::c_void, libc::MAP_FAILED);
+            ptr::write_bytes(ptr, 42, *PAGE_SIZE);
+            libc::munmap(ptr.add(*PAGE_SIZE) as *mut libc::c_void, *PAGE_SIZE);
+
+            let res = LinuxTarget::me()
+                .read()
+                .use_proc_mem()
+                .read_slice_uninit(&mut buf, ptr as usize)
+                .apply_uninit();
+            libc::munmap(ptr as *mut libc::c_void, *PAGE_SIZE);
+
+            assert_eq!(res.unwrap(), *PAGE_SIZE);
+            assert!(buf[..*PAGE_SIZE]
+                .iter()
+                .all(|byte| byte.assume_init() == 42));
+        }
+    }
+
+    /// Like `read_cross_page_memory`, but with `/proc/<pid>/mem`.
+    #[test]
+    fn read_proc_mem_cross_page() {
+        let mut read_var_op = vec![0u32; *PAGE_SIZE + 2];
+
+        let mut var = vec![123u32; *PAGE_SIZE + 2];
+        var[0] = 321;
+        var[*PAGE_SIZE + 1] = 234;
+
+        unsafe {
+            let layout = Layout::from_size_align(*PAGE_SIZE * 3, *PAGE_SIZE).unwrap();
+            let ptr = alloc_zeroed(layout);
+
+            let array_ptr = ptr.add(*PAGE_SIZE - mem::size_of::<u32>());
+            let second_page_ptr = ptr.add(*PAGE_SIZE);
+
+            match fork() {
+                Ok(ForkResult::Child) => {
+                    ptrace::traceme().unwrap();
+                    ptr::copy_nonoverlapping(var.as_ptr(), array_ptr as *mut u32, *PAGE_SIZE + 2);
+                    mprotect(second_page_ptr as *mut _, *PAGE_SIZE, ProtFlags::PROT_WRITE)
+                        .expect("Failed to mprotect");
+
+                    signal::kill(getpid(), signal::Signal::SIGSTOP).unwrap();
+                    std::process::exit(0);
+                }
+                Ok(ForkResult::Parent { child, .. }) => {
+                    wait::waitpid(child, None).unwrap();
+                    let target = LinuxTarget::new(child);
+
+                    target
+                        .read()
+                        .use_proc_mem()
+                        .read_slice(&mut read_var_op, array_ptr as usize)
+                        .apply()
+                        .expect("Failed to apply mem_op");
+                    assert_eq!(read_var_op, var);
+
+                    dealloc(ptr, layout);
+
+                    ptrace::cont(child, None).unwrap();
+                    wait::waitpid(child, None).unwrap();
+                }
+                Err(x) => panic!("{}", x),
+            }
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn syscall_step() {
+        const BAD_FD: libc::c_int = 1234;
+        let buf = [0u8; 5];
+
+        match fork() {
+            Ok(ForkResult::Child) => {
+                ptrace::traceme().unwrap();
+                signal::kill(getpid(), signal::Signal::SIGSTOP).unwrap();
+
+                unsafe { libc::write(BAD_FD, buf.as_ptr() as *const libc::c_void, buf.len()) };
+                std::process::exit(0);
+            }
+            Ok(ForkResult::Parent { child, .. }) => {
+                wait::waitpid(child, None).unwrap();
+                let mut target = LinuxTarget::new(child);
+
+                // Skip the syscalls made before, like the one that raised the `SIGSTOP`.
+                loop {
+                    match target.syscall_step().unwrap() {
+                        (_, Some(SyscallStop::Entry { nr, args }))
+                            if nr == libc::SYS_write as u64 =>
+                        {
+                            assert_eq!(args[0], BAD_FD as u64);
+                            assert_eq!(args[1], buf.as_ptr() as u64);
+                            assert_eq!(args[2], buf.len() as u64);
+                            break;
+                        }
+                        (_, Some(_)) => {}
+                        (status, None) => panic!("Unexpected status: {:?}", status),
+                    }
+                }
+                match target.syscall_step().unwrap() {
+                    (_, Some(SyscallStop::Exit { nr, ret })) => {
+                        assert_eq!(nr, libc::SYS_write as u64);
+                        assert_eq!(ret, -libc::EBADF as i64);
+                    }
+                    status => panic!("Unexpected status: {:?}", status),
+                }
+
+                ptrace::cont(child, None).unwrap();
+                assert_eq!(
+                    wait::waitpid(child, None).unwrap(),
+                    wait::WaitStatus::Exited(child, 0)
+                );
+            }
+            Err(x) => panic!("{}", x),
+        }
+    }
+
+    #[test]
+    fn cont_forwards_signal() {
+        static SIGCONT_RECEIVED: AtomicBool = AtomicBool::new(false);
+
+        extern "C" fn handle_sigcont(_: libc::c_int) {
+            SIGCONT_RECEIVED.store(true, Ordering::SeqCst);
+        }
+
+        match fork() {
+            Ok(ForkResult::Child) => {
+                ptrace::traceme().unwrap();
+                unsafe {
+                    libc::signal(
+                        libc::SIGCONT,
+                        handle_sigcont as extern "C" fn(libc::c_int) as libc::sighandler_t,
+                    );
+                }
+
+                // Stops the child until the parent decides what to do with the signal.
+                signal::kill(getpid(), signal::Signal::SIGCONT).unwrap();
+
+                std::process::exit(if SIGCONT_RECEIVED.load(Ordering::SeqCst) {
+                    0
+                } else {
+                    100
+                });
+            }
+            Ok(ForkResult::Parent { child, .. }) => {
+                let status = wait::waitpid(child, None).unwrap();
+                assert_eq!(
+                    status,
+                    wait::WaitStatus::Stopped(child, signal::Signal::SIGCONT)
+                );
+
+                let target = LinuxTarget::new(child);
+                let status = target.cont(Some(signal::Signal::SIGCONT)).unwrap();
+                assert_eq!(status, wait::WaitStatus::Exited(child, 0));
+            }
+            Err(x) => panic!("{}", x),
+        }
+    }
+
+    #[test]
+    fn cont_suppresses_signal() {
+        static SIGUSR1_RECEIVED: AtomicBool = AtomicBool::new(false);
+
+        extern "C" fn handle_sigusr1(_: libc::c_int) {
+            SIGUSR1_RECEIVED.store(true, Ordering::SeqCst);
+        }
+
+        match fork() {
+            Ok(ForkResult::Child) => {
+                ptrace::traceme().unwrap();
+                unsafe {
+                    libc::signal(
+                        libc::SIGUSR1,
+                        handle_sigusr1 as extern "C" fn(libc::c_int) as libc::sighandler_t,
+                    );
+                }
+
+                // Stops the child until the parent decides what to do with the signal.
+                signal::kill(getpid(), signal::Signal::SIGUSR1).unwrap();
+
+                std::process::exit(if SIGUSR1_RECEIVED.load(Ordering::SeqCst) {
+                    100
+                } else {
+                    0
+                });
+            }
+            Ok(ForkResult::Parent { child, .. }) => {
+                let status = wait::waitpid(child, None).unwrap();
+                assert_eq!(
+                    status,
+                    wait::WaitStatus::Stopped(child, signal::Signal::SIGUSR1)
+                );
+
+                let target = LinuxTarget::new(child);
+                assert_eq!(target.siginfo().unwrap().si_signo, libc::SIGUSR1);
+                let status = target.cont_suppressing_signal().unwrap();
+                assert_eq!(status, wait::WaitStatus::Exited(child, 0));
+            }
+            Err(x) => panic!("{}", x),
+        }
+    }
+
+    #[test]
+    fn wait_status_exited() {
+        match fork() {
+            Ok(ForkResult::Child) => {
+                ptrace::traceme().unwrap();
+                signal::kill(getpid(), signal::Signal::SIGSTOP).unwrap();
+                std::process::exit(42);
+            }
+            Ok(ForkResult::Parent { child, .. }) => {
+                let target = LinuxTarget::new(child);
+                assert_eq!(
+                    target.wait_status().unwrap(),
+                    TargetStatus::Stopped(signal::Signal::SIGSTOP)
+                );
+
+                ptrace::cont(child, None).unwrap();
+                assert_eq!(target.wait_status().unwrap(), TargetStatus::Exited(42));
+            }
+            Err(x) => panic!("{}", x),
+        }
+    }
+
+    #[test]
+    fn wait_status_signaled() {
+        match fork() {
+            Ok(ForkResult::Child) => {
+                ptrace::traceme().unwrap();
+                signal::kill(getpid(), signal::Signal::SIGSTOP).unwrap();
+                std::process::abort();
+            }
+            Ok(ForkResult::Parent { child, .. }) => {
+                let target = LinuxTarget::new(child);
+                assert_eq!(
+                    target.wait_status().unwrap(),
+                    TargetStatus::Stopped(signal::Signal::SIGSTOP)
+                );
+
+                ptrace::cont(child, None).unwrap();
+                assert_eq!(
+                    target.wait_status().unwrap(),
+                    TargetStatus::Stopped(signal::Signal::SIGABRT)
+                );
+                ptrace::cont(child, Some(signal::Signal::SIGABRT)).unwrap();
+                assert_eq!(
+                    target.wait_status().unwrap(),
+                    TargetStatus::Signaled(signal::Signal::SIGABRT)
+                );
+            }
+            Err(x) => panic!("{}", x),
+        }
+    }
+
+    #[test]
+    fn fault_addr() {
+        match fork() {
+            Ok(ForkResult::Child) => {
+                ptrace::traceme().unwrap();
+                signal::kill(getpid(), signal::Signal::SIGSTOP).unwrap();
+                unsafe { std::ptr::read_volatile(std::ptr::null::<u8>()) };
+                std::process::exit(0);
+            }
+            Ok(ForkResult::Parent { child, .. }) => {
+                let target = LinuxTarget::new(child);
+                assert_eq!(
+                    target.wait_status().unwrap(),
+                    TargetStatus::Stopped(signal::Signal::SIGSTOP)
+                );
+                assert_eq!(target.fault_addr().unwrap(), None);
+
+                ptrace::cont(child, None).unwrap();
+                assert_eq!(
+                    target.wait_status().unwrap(),
+                    TargetStatus::Stopped(signal::Signal::SIGSEGV)
+                );
+                let siginfo = target.siginfo().unwrap();
+                assert_eq!(siginfo.si_signo, libc::SIGSEGV);
+                assert_eq!(target.fault_addr().unwrap(), Some(0));
+
+                ptrace::kill(child).unwrap();
+                assert_eq!(
+                    target.wait_status().unwrap(),
+                    TargetStatus::Signaled(signal::Signal::SIGKILL)
+                );
+            }
+            Err(x) => panic!("{}", x),
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn soft_watch_region() {
+        #[repr(align(4096))]
+        struct Page([u64; 512]);
+        let page = Box::leak(Box::new(Page([0; 512])));
+        let page_addr = page.0.as_ptr() as usize;
+        let watched_addr = page_addr + 100 * 8;
+
+        match fork() {
+            Ok(ForkResult::Child) => {
+                ptrace::traceme().unwrap();
+                signal::kill(getpid(), signal::Signal::SIGSTOP).unwrap();
+                unsafe {
+                    // Not watched, but on the same page.
+                    std::ptr::write_volatile(&mut page.0[0], 1);
+                    std::ptr::write_volatile(&mut page.0[100], 2);
+                    std::ptr::write_volatile(&mut page.0[100], 3);
+                }
+                std::process::exit(0);
+            }
+            Ok(ForkResult::Parent { child, .. }) => {
+                let mut target = LinuxTarget::new(child);
+                assert_eq!(
+                    target.wait_status().unwrap(),
+                    TargetStatus::Stopped(signal::Signal::SIGSTOP)
+                );
+                assert!(target.soft_watch_region(page_addr, 0).is_err());
+                target.soft_watch_region(watched_addr, 8).unwrap();
+                assert_eq!(target.soft_watch_regions(), &[(watched_addr, 8)]);
+                assert_eq!(target.soft_watch_fault().unwrap(), None);
+                assert!(target.step_over_soft_watch().is_err());
+
+                let mut hits = Vec::new();
+                loop {
+                    ptrace::cont(child, None).unwrap();
+                    match target.wait_status().unwrap() {
+                        TargetStatus::Stopped(signal::Signal::SIGSEGV) => {}
+                        TargetStatus::Exited(code) => {
+                            assert_eq!(code, 0);
+                            break;
+                        }
+                        status => panic!("Unexpected status {:?}", status),
+                    }
+                    let fault = target.soft_watch_fault().unwrap().unwrap();
+                    target.step_over_soft_watch().unwrap();
+                    let value: u64 = target.read_struct(fault.addr).unwrap();
+                    hits.push((fault, value));
+
+                    if hits.len() == 2 {
+                        // The second write to the watched region isn't reported anymore.
+                        target.clear_soft_watch_region(watched_addr).unwrap();
+                        assert!(matches!(
+                            target.clear_soft_watch_region(watched_addr),
+                            Err(Error::NoWatchedRegion(_))
+                        ));
+                    }
+                }
+
+                assert_eq!(
+                    hits,
+                    &[
+                        (
+                            SoftWatchFault {
+                                addr: page_addr,
+                                region: None,
+                            },
+                            1
+                        ),
+                        (
+                            SoftWatchFault {
+                                addr: watched_addr,
+                                region: Some((watched_addr, 8)),
+                            },
+                            2
+                        ),
+                    ]
+                );
+            }
+            Err(x) => panic!("{}", x),
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn invalidate_syscall_site() {
+        let sh = CString::new("/bin/sh").unwrap();
+        let args = [
+            sh.clone(),
+            CString::new("-c").unwrap(),
+            CString::new("kill -STOP $$").unwrap(),
+        ];
+
+        match fork() {
+            Ok(ForkResult::Child) => {
+                ptrace::traceme().unwrap();
+                signal::kill(getpid(), signal::Signal::SIGSTOP).unwrap();
+                nix::unistd::execv(&sh, &[args[0].as_c_str(), &args[1], &args[2]]).unwrap();
+            }
+            Ok(ForkResult::Parent { child, .. }) => {
+                wait::waitpid(child, None).unwrap();
+                let mut target = LinuxTarget::new(child);
+                target.set_exec_tracing(true).unwrap();
+                let in_executable_map = |target: &LinuxTarget, site: usize| {
+                    let map = target.map_containing(site).unwrap();
+                    matches!(map, Some(map) if map.is_executable)
+                };
+
+                let getpid = |target: &LinuxTarget| {
+                    target
+                        .syscall(libc::SYS_getpid as _, 0, 0, 0, 0, 0, 0)
+                        .unwrap()
+                };
+                assert_eq!(getpid(&target), child.as_raw() as u64);
+                let old_site = target.syscall_site.get().unwrap();
+                assert!(in_executable_map(&target, old_site));
+
+                // Stopping at the exec without `wait` keeps the cached site.
+                ptrace::cont(child, None).unwrap();
+                match wait::waitpid(child, None).unwrap() {
+                    wait::WaitStatus::PtraceEvent(_, _, libc::PTRACE_EVENT_EXEC) => {}
+                    status => panic!("Unexpected status {:?}", status),
+                }
+                assert_eq!(target.syscall_site.get(), Some(old_site));
+                target.invalidate_syscall_site();
+                assert_eq!(target.syscall_site.get(), None);
+
+                // The shell stops itself.
+                ptrace::cont(child, None).unwrap();
+                assert_eq!(
+                    target.wait_status().unwrap(),
+                    TargetStatus::Stopped(signal::Signal::SIGSTOP)
+                );
+                assert_eq!(getpid(&target), child.as_raw() as u64);
+                let new_site = target.syscall_site.get().unwrap();
+                assert!(in_executable_map(&target, new_site));
+                let code = target.read_bytes(new_site, 2).unwrap();
+                assert_eq!(code, [0x0f, 0x05]);
+
+                ptrace::kill(child).unwrap();
+                wait::waitpid(child, None).unwrap();
+            }
+            Err(x) => panic!("{}", x),
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn segment_registers() {
+        let cs: u16;
+        unsafe { std::arch::asm!("mov {0:x}, cs", out(reg) cs) };
+
+        match fork() {
+            Ok(ForkResult::Child) => {
+                ptrace::traceme().unwrap();
+                signal::kill(getpid(), signal::Signal::SIGSTOP).unwrap();
+                std::process::exit(0);
+            }
+            Ok(ForkResult::Parent { child, .. }) => {
+                wait::waitpid(child, None).unwrap();
+                let target = LinuxTarget::new(child);
+
+                let mut segments = target.read_segment_registers().unwrap();
+                assert_eq!(segments.cs, cs);
+                assert_eq!(segments.cs, crate::target::SegmentRegisters::USER_CS);
+                assert!(target.is_64bit_mode().unwrap());
+
+                // A null selector, which the kernel accepts for `es`.
+                segments.es = 0;
+                target.write_segment_registers(segments).unwrap();
+                assert_eq!(target.read_segment_registers().unwrap(), segments);
+                // Not a selector of user space.
+                segments.cs = 0x10;
+                assert!(target.write_segment_registers(segments).is_err());
+
+                ptrace::cont(child, None).unwrap();
+                wait::waitpid(child, None).unwrap();
+            }
+            Err(x) => panic!("{}", x),
+        }
+    }
+
+    #[test]
+    fn catch_signals() {
+        use std::sync::atomic::AtomicUsize;
+
+        static HANDLED: AtomicUsize = AtomicUsize::new(0);
+        extern "C" fn handler(_: libc::c_int) {
+            HANDLED.fetch_add(1, Ordering::SeqCst);
+        }
+
+        match fork() {
+            Ok(ForkResult::Child) => {
+                ptrace::traceme().unwrap();
+                let action = signal::SigAction::new(
+                    signal::SigHandler::Handler(handler),
+                    signal::SaFlags::empty(),
+                    signal::SigSet::empty(),
+                );
+                unsafe {
+                    signal::sigaction(signal::Signal::SIGUSR1, &action).unwrap();
+                    signal::sigaction(signal::Signal::SIGUSR2, &action).unwrap();
+                }
+                signal::kill(getpid(), signal::Signal::SIGSTOP).unwrap();
+                signal::kill(getpid(), signal::Signal::SIGUSR1).unwrap();
+                signal::kill(getpid(), signal::Signal::SIGUSR2).unwrap();
+                std::process::exit(HANDLED.load(Ordering::SeqCst) as i32);
+            }
+            Ok(ForkResult::Parent { child, .. }) => {
+                let mut target = LinuxTarget::new(child);
+                assert_eq!(
+                    target.wait().unwrap(),
+                    StopEvent::Signal(signal::Signal::SIGSTOP)
+                );
+                target.catch_signals(&[signal::Signal::SIGUSR2]);
+                assert!(!target.is_signal_caught(signal::Signal::SIGUSR1));
+                assert!(target.is_signal_caught(signal::Signal::SIGTRAP));
+
+                // `SIGUSR1` is handled by the debuggee without the debugger noticing.
+                ptrace::cont(child, None).unwrap();
+                assert_eq!(
+                    target.wait().unwrap(),
+                    StopEvent::Signal(signal::Signal::SIGUSR2)
+                );
+
+                target.catch_all_signals();
+                assert!(target.is_signal_caught(signal::Signal::SIGUSR1));
+                ptrace::cont(child, Some(signal::Signal::SIGUSR2)).unwrap();
+                assert_eq!(target.wait().unwrap(), StopEvent::Exited(2));
+            }
+            Err(x) => panic!("{}", x),
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn thread_pointer() {
+        // The child is a copy of this thread, including its thread local storage.
+        let errno_addr = unsafe { libc::__errno_location() } as u64;
+        let mut fs_base = 0u64;
+        unsafe { libc::syscall(libc::SYS_arch_prctl, 0x1003, &mut fs_base as *mut u64) };
+
+        match fork() {
+            Ok(ForkResult::Child) => {
+                ptrace::traceme().unwrap();
+                signal::kill(getpid(), signal::Signal::SIGSTOP).unwrap();
+                std::process::exit(0);
+            }
+            Ok(ForkResult::Parent { child, .. }) => {
+                wait::waitpid(child, None).unwrap();
+
+                let target = LinuxTarget::new(child);
+                let thread_pointer = target.thread_pointer(child.as_raw()).unwrap();
+                assert_eq!(thread_pointer, fs_base);
+                assert_eq!(target.arch_get_fs().unwrap(), fs_base);
+
+                // `errno` is in the static TLS blocks right below the thread pointer, which
+                // points to itself.
+                assert!(errno_addr < thread_pointer && thread_pointer - errno_addr < 0x10000);
+                let mut self_ptr = 0u64;
+                unsafe {
+                    target
+                        .read()
+                        .read(&mut self_ptr, thread_pointer as usize)
+                        .apply()
+                        .unwrap()
+                };
+                assert_eq!(self_ptr, thread_pointer);
+
+                ptrace::cont(child, None).unwrap();
+                wait::waitpid(child, None).unwrap();
+            }
+            Err(x) => panic!("{}", x),
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn peek_poke_user() {
+        static WATCHED: u8 = 0;
+
+        match fork() {
+            Ok(ForkResult::Child) => {
+                ptrace::traceme().unwrap();
+                signal::kill(getpid(), signal::Signal::SIGSTOP).unwrap();
+                std::process::exit(0);
+            }
+            Ok(ForkResult::Parent { child, .. }) => {
+                wait::waitpid(child, None).unwrap();
+
+                let target = LinuxTarget::new(child);
+                let dr0 = *DEBUG_REG_OFFSET;
+                let addr = &WATCHED as *const u8 as u64;
+                assert_eq!(target.peek_user(dr0).unwrap(), 0);
+                target.poke_user(dr0, addr).unwrap();
+                assert_eq!(target.peek_user(dr0).unwrap(), addr);
+                target.poke_user(dr0, 0).unwrap();
+                assert_eq!(target.peek_user(dr0).unwrap(), 0);
+
+                // DR4 and DR5 are reserved.
+                assert!(target.poke_user(dr0 + 4 * 8, 0).is_err());
+
+                ptrace::cont(child, None).unwrap();
+                wait::waitpid(child, None).unwrap();
+            }
+            Err(x) => panic!("{}", x),
+        }
+    }
+
+    #[test]
+    fn attach_by_name() {
+        // `comm` is limited to 15 bytes.
+        let name = format!("hc-abn-{}", getpid());
+        let (read_fd, write_fd) = nix::unistd::pipe().unwrap();
+        match fork() {
+            Ok(ForkResult::Child) => {
+                let comm = CString::new(name).unwrap();
+                unsafe { libc::prctl(libc::PR_SET_NAME, comm.as_ptr()) };
+                nix::unistd::write(write_fd, &[0]).unwrap();
+                loop {
+                    nix::unistd::pause();
+                }
+            }
+            Ok(ForkResult::Parent { child, .. }) => {
+                nix::unistd::read(read_fd, &mut [0]).unwrap();
+
+                let targets = LinuxTarget::attach_by_name(&name, AttachOptions::default()).unwrap();
+                assert_eq!(targets.len(), 1);
+                assert_eq!(targets[0].pid, child);
+                assert!(LinuxTarget::attach_by_name(
+                    "hc-no-such-process",
+                    AttachOptions::default()
+                )
+                .unwrap()
+                .is_empty());
+
+                signal::kill(child, signal::Signal::SIGKILL).unwrap();
+                wait::waitpid(child, None).unwrap();
+            }
+            Err(x) => panic!("{}", x),
+        }
+    }
+
+    #[test]
+    fn seize_and_interrupt() {
+        match fork() {
+            Ok(ForkResult::Child) => loop {
+                thread::sleep(std::time::Duration::from_millis(10));
+            },
+            Ok(ForkResult::Parent { child, .. }) => {
+                let target = LinuxTarget::seize(child, AttachOptions::default()).unwrap();
+                // Seizing doesn't stop the debuggee.
+                thread::sleep(std::time::Duration::from_millis(50));
+                assert_eq!(target.try_wait().unwrap(), None);
+                let state = Process::new(child.as_raw()).unwrap().stat.state;
+                assert_ne!(state, 't', "Debuggee is stopped");
+
+                assert_eq!(
+                    target.interrupt().unwrap(),
+                    wait::WaitStatus::PtraceEvent(
+                        child,
+                        signal::Signal::SIGTRAP,
+                        PTRACE_EVENT_STOP
+                    )
+                );
+                let state = Process::new(child.as_raw()).unwrap().stat.state;
+                assert_eq!(state, 't');
+                target.read_regs().unwrap();
+
+                // It can be resumed and interrupted again.
+                ptrace::cont(child, None).unwrap();
+                assert!(matches!(
+                    target.interrupt().unwrap(),
+                    wait::WaitStatus::PtraceEvent(_, _, PTRACE_EVENT_STOP)
+                ));
+
+                signal::kill(child, signal::Signal::SIGKILL).unwrap();
+                wait::waitpid(child, None).unwrap();
+            }
+            Err(x) => panic!("{}", x),
+        }
+    }
+
+    #[test]
+    fn try_wait() {
+        match fork() {
+            Ok(ForkResult::Child) => {
+                ptrace::traceme().unwrap();
+                signal::kill(getpid(), signal::Signal::SIGSTOP).unwrap();
+
+                thread::sleep(time::Duration::from_millis(100));
+                signal::kill(getpid(), signal::Signal::SIGSTOP).unwrap();
+                std::process::exit(0);
+            }
+            Ok(ForkResult::Parent { child, .. }) => {
+                wait::waitpid(child, None).unwrap();
+                let target = LinuxTarget::new(child);
+                let pidfd = target.pidfd().unwrap();
+
+                ptrace::cont(child, None).unwrap();
+                assert_eq!(target.try_wait().unwrap(), None);
+                let status = loop {
+                    if let Some(status) = target.try_wait().unwrap() {
+                        break status;
+                    }
+                    thread::sleep(time::Duration::from_millis(10));
+                };
+                assert_eq!(
+                    status,
+                    wait::WaitStatus::Stopped(child, signal::Signal::SIGSTOP)
+                );
+
+                // The pidfd becomes readable once the child exits.
+                let mut poll_fd = [nix::poll::PollFd::new(
+                    pidfd.as_raw_fd(),
+                    nix::poll::PollFlags::POLLIN,
+                )];
+                assert_eq!(nix::poll::poll(&mut poll_fd, 0).unwrap(), 0);
+                ptrace::cont(child, None).unwrap();
+                assert_eq!(nix::poll::poll(&mut poll_fd, 5000).unwrap(), 1);
+                assert_eq!(
+                    target.try_wait().unwrap(),
+                    Some(wait::WaitStatus::Exited(child, 0))
+                );
+            }
+            Err(x) => panic!("{}", x),
+        }
+    }
+
+    #[test]
+    fn launch_kill_on_exit() {
+        let (target, _) = LinuxTarget::launch("/bin/true").unwrap();
+        assert!(target.options.contains(ptrace::Options::PTRACE_O_EXITKILL));
+        target.cont(None).unwrap();
+
+        let options = LaunchOptions {
+            kill_on_exit: false,
+            ..LaunchOptions::default()
+        };
+        let (target, _, _) =
+            LinuxTarget::launch_with_options("/bin/true", &[], None, options).unwrap();
+        assert!(!target.options.contains(ptrace::Options::PTRACE_O_EXITKILL));
+        let status = target.cont(None).unwrap();
+        assert_eq!(status, wait::WaitStatus::Exited(target.pid, 0));
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn set_hardware_breakpoints() {
+        static WATCHED: [u64; 4] = [0; 4];
+
+        match fork() {
+            Ok(ForkResult::Child) => {
+                ptrace::traceme().unwrap();
+                signal::kill(getpid(), signal::Signal::SIGSTOP).unwrap();
+                std::process::exit(0);
+            }
+            Ok(ForkResult::Parent { child, .. }) => {
+                wait::waitpid(child, None).unwrap();
+
+                let mut target = LinuxTarget::new(child);
+                let sizes = [
+                    HardwareBreakpointSize::_1,
+                    HardwareBreakpointSize::_2,
+                    HardwareBreakpointSize::_4,
+                    HardwareBreakpointSize::_8,
+                ];
+                let watchpoints: Vec<_> = WATCHED
+                    .iter()
+                    .zip(&sizes)
+                    .map(|(watched, &size)| HardwareBreakpoint {
+                        typ: HardwareBreakpointType::Write,
+                        addr: watched as *const u64 as usize,
+                        size,
+                    })
+                    .collect();
+
+                // Nothing is set if one of the breakpoints is invalid.
+                let mut invalid = watchpoints.clone();
+                invalid[3].addr += 4;
+                assert!(target.set_hardware_breakpoints(&invalid).is_err());
+                assert!(target.hardware_breakpoints.iter().all(Option::is_none));
+                assert_eq!(target.debug_reg(7).unwrap(), 0);
+
+                assert_eq!(
+                    target.set_hardware_breakpoints(&watchpoints).unwrap(),
+                    [0, 1, 2, 3]
+                );
+                let dr7 = target.debug_reg(7).unwrap();
+                for (index, watchpoint) in watchpoints.iter().enumerate() {
+                    assert_eq!(target.debug_reg(index).unwrap(), watchpoint.addr as u64);
+                    let decoded =
+                        HardwareBreakpoint::from_dr7(dr7, index, watchpoint.addr).unwrap();
+                    assert_eq!(decoded.typ, watchpoint.typ);
+                    assert_eq!(decoded.size, watchpoint.size);
+                }
+
+                let err = target
+                    .set_hardware_breakpoints(&watchpoints[..1])
+                    .unwrap_err();
+                match err {
+                    Error::HardwareBreakpoint(HardwareBreakpointError::NoEmptyWatchpoint) => {}
+                    _ => panic!("Unexpected error: {}", err),
+                }
+
+                target.clear_all_hardware_breakpoints().unwrap();
+                assert_eq!(target.debug_reg(7).unwrap(), 0);
+
+                let status = target.cont(None).unwrap();
+                assert_eq!(status, wait::WaitStatus::Exited(child, 0));
+            }
+            Err(x) => panic!("{}", x),
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn free_hardware_breakpoints() {
+        static WATCHED: u64 = 0;
+
+        match fork() {
+            Ok(ForkResult::Child) => {
+                ptrace::traceme().unwrap();
+                signal::kill(getpid(), signal::Signal::SIGSTOP).unwrap();
+                std::process::exit(0);
+            }
+            Ok(ForkResult::Parent { child, .. }) => {
+                wait::waitpid(child, None).unwrap();
+
+                let mut target = LinuxTarget::new(child);
+                assert_eq!(target.available_hardware_breakpoints(), 4);
+                assert_eq!(target.free_hardware_breakpoints(), 4);
+
+                let index = target
+                    .set_hardware_breakpoint(HardwareBreakpoint {
+                        typ: HardwareBreakpointType::Write,
+                        addr: &WATCHED as *const u64 as usize,
+                        size: HardwareBreakpointSize::_8,
+                    })
+                    .unwrap();
+                assert_eq!(target.available_hardware_breakpoints(), 4);
+                assert_eq!(target.free_hardware_breakpoints(), 3);
+
+                target.clear_hardware_breakpoint(index).unwrap();
+                assert_eq!(target.free_hardware_breakpoints(), 4);
+
+                let status = target.cont(None).unwrap();
+                assert_eq!(status, wait::WaitStatus::Exited(child, 0));
+            }
+            Err(x) => panic!("{}", x),
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn resync_hardware_breakpoints() {
+        static WATCHED: u8 = 0;
+
+        match fork() {
+            Ok(ForkResult::Child) => {
+                ptrace::traceme().unwrap();
+                signal::kill(getpid(), signal::Signal::SIGSTOP).unwrap();
+                std::process::exit(0);
+            }
+            Ok(ForkResult::Parent { child, .. }) => {
+                wait::waitpid(child, None).unwrap();
+
+                let mut target = LinuxTarget::new(child);
+                let watched_addr = &WATCHED as *const u8 as usize;
+                let watchpoint = || HardwareBreakpoint {
+                    typ: HardwareBreakpointType::Write,
+                    addr: watched_addr,
+                    size: HardwareBreakpointSize::_1,
+                };
+                assert_eq!(target.set_hardware_breakpoint(watchpoint()).unwrap(), 0);
+
+                // Forget about the breakpoint while it's still set in the debug registers.
+                target.hardware_breakpoints[0] = None;
+                let err = target.set_hardware_breakpoint(watchpoint()).unwrap_err();
+                match err {
+                    Error::HardwareBreakpoint(HardwareBreakpointError::RegisterStateMismatch(
+                        0,
+                    )) => {}
+                    _ => panic!("Unexpected error: {}", err),
+                }
+
+                target.resync_hardware_breakpoints().unwrap();
+                let resynced = target.hardware_breakpoints[0].as_ref().unwrap();
+                assert_eq!(resynced.typ, HardwareBreakpointType::Write);
+                assert_eq!(resynced.addr, watched_addr);
+                assert_eq!(resynced.size, HardwareBreakpointSize::_1);
+                assert_eq!(target.set_hardware_breakpoint(watchpoint()).unwrap(), 1);
+
+                // Disable all breakpoints behind the target's back.
+                target.poke_user(*DEBUG_REG_OFFSET + 7 * 8, 0).unwrap();
+                target.resync_hardware_breakpoints().unwrap();
+                assert!(target.hardware_breakpoints.iter().all(Option::is_none));
+
+                let err = target
+                    .set_hardware_breakpoint(HardwareBreakpoint {
+                        typ: HardwareBreakpointType::Write,
+                        addr: 0x1002,
+                        size: HardwareBreakpointSize::_4,
+                    })
+                    .unwrap_err();
+                match err {
+                    Error::HardwareBreakpoint(HardwareBreakpointError::Misaligned {
+                        addr: 0x1002,
+                        size: 4,
+                    }) => {}
+                    _ => panic!("Unexpected error: {}", err),
+                }
+                assert!(target.hardware_breakpoints.iter().all(Option::is_none));
+
+                let status = target.cont(None).unwrap();
+                assert_eq!(status, wait::WaitStatus::Exited(child, 0));
+            }
+            Err(x) => panic!("{}", x),
+        }
+    }
+
+    #[test]
+    fn thread_state() -> Result<()> {
+        let proc = LinuxTarget::me();
+        let threads = proc.threads()?;
+        let this_tid = unsafe { libc::syscall(libc::SYS_gettid) } as i32;
+
+        // This thread is reading its own state, so it's running.
+        let this_thread = threads.iter().find(|t| t.thread_id() == this_tid).unwrap();
+        assert_eq!(this_thread.state()?, Some(ThreadState::Running));
+        assert!(this_thread.cpu()?.is_some());
+
+        // The main thread of the test harness is waiting for the tests to finish, unless it's
+        // this one.
+        let main_thread = threads
+            .iter()
+            .find(|t| t.thread_id() == proc.pid().as_raw())
+            .unwrap();
+        let main_state = main_thread.state()?;
+        assert!(
+            main_state == Some(ThreadState::Running) || main_state == Some(ThreadState::Sleeping),
+            "Unexpected state of the main thread: {:?}",
+            main_state
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn reads_threads() -> Result<()> {
+        let start_barrier = Arc::new(Barrier::new(2));
+        let end_barrier = Arc::new(Barrier::new(2));
+
+        let t1_start = start_barrier.clone();
+        let t1_end = end_barrier.clone();
+
+        let thread_name = "thread_name";
+        let t1_handle = thread::Builder::new()
+            .name(thread_name.to_string())
+            .spawn(move || {
+                t1_start.wait();
+                t1_end.wait();
+            })
+            .unwrap();
+
+        start_barrier.wait();
+
+        let proc = LinuxTarget::me();
+        let threads = proc.threads()?;
+
+        let threads: Vec<_> = threads
+            .iter()
+            .map(|t| {
+                let name = t.name().unwrap().unwrap_or_else(String::new);
+                let id = t.thread_id();
+                (name, id)
+            })
+            .collect();
+
+        assert!(
+            threads.len() >= 2,
+            "Expected at least 2 threads in {:?}",
+            threads
+        );
+
+        // Find test pid in result:
+        let proc_pid = proc.pid().as_raw();
+        assert!(
+            threads.iter().any(|&(_, tid)| tid == proc_pid),
+            "Expected to find main pid={} in {:?}",
+            proc_pid,
+            threads
+        );
+
+        // Find thread name
+        assert!(
+            threads.iter().any(|(name, _)| name == thread_name),
+            "Expected to find thread name={} in {:?}",
+            thread_name,
+            threads
+        );
+
+        end_barrier.wait();
+        t1_handle.join().unwrap();
+        Ok(())
+    }
+}
@@ -0,0 +1,277 @@
+//! Bookkeeping of software breakpoints, see `LinuxTarget::breakpoints`.
+
+use super::{software_breakpoint::SoftwareBreakpointError, LinuxTarget};
+use crate::Result;
+use std::{collections::HashMap, sync::Arc};
+
+/// A software breakpoint managed with `Breakpoints`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Breakpoint {
+    /// Identifies the breakpoint, ids aren't reused after the breakpoint is removed.
+    pub id: usize,
+    pub addr: usize,
+    /// Disabled breakpoints keep their id and hit count, but the original instruction is
+    /// restored in the debuggee.
+    pub enabled: bool,
+    /// How many times `Breakpoints::record_hit` or `Breakpoints::cont` found the debuggee
+    /// stopped on this breakpoint.
+    pub hit_count: usize,
+    /// Name of the symbol the breakpoint was set on, if any.
+    pub symbol: Option<String>,
+    /// Whether a condition was set with `Breakpoints::set_condition`.
+    pub has_condition: bool,
+    /// Temporary breakpoints are removed when they are hit, see
+    /// `Breakpoints::set_temp_breakpoint`.
+    pub temporary: bool,
+}
+
+/// A predicate deciding whether a hit of a breakpoint is reported, see
+/// `Breakpoints::set_condition`.
+#[derive(Clone)]
+#[cfg_attr(not(target_arch = "x86_64"), allow(dead_code))]
+struct Condition(Arc<dyn Fn(&LinuxTarget) -> bool + Send + Sync>);
+
+impl std::fmt::Debug for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("Condition")
+    }
+}
+
+/// The breakpoints managed with `Breakpoints`, kept by `LinuxTarget`.
+#[derive(Debug, Default, Clone)]
+pub(super) struct BreakpointList {
+    next_id: usize,
+    /// Sorted by id.
+    breakpoints: Vec<Breakpoint>,
+    /// Conditions of breakpoints, keyed by id.
+    conditions: HashMap<usize, Condition>,
+}
+
+/// Manages the software breakpoints of a debuggee, keeping track of ids, hit counts and
+/// whether they are enabled.
+///
+/// Enabled breakpoints are set with `LinuxTarget::set_breakpoint` and can be handled like any
+/// other software breakpoint. Clearing one of them with `LinuxTarget::clear_breakpoint`
+/// bypasses this bookkeeping and leaves it marked as enabled, use `disable` or `remove` instead.
+pub struct Breakpoints<'a> {
+    target: &'a mut LinuxTarget,
+}
+
+impl<'a> Breakpoints<'a> {
+    pub(super) fn new(target: &'a mut LinuxTarget) -> Self {
+        Breakpoints { target }
+    }
+
+    /// Sets an enabled breakpoint at `addr`, returning its id. `symbol` is only kept for
+    /// display.
+    pub fn add(&mut self, addr: usize, symbol: Option<String>) -> Result<usize> {
+        self.insert(addr, symbol, false)
+    }
+
+    /// Sets a temporary breakpoint at `addr`, returning its id, e.g. to run to a location.
+    ///
+    /// The first hit found by `record_hit` or `cont` is counted and reported like that of any
+    /// other breakpoint, but also removes the breakpoint, restoring the original instruction. A
+    /// hit whose condition doesn't hold doesn't remove it.
+    pub fn set_temp_breakpoint(&mut self, addr: usize) -> Result<usize> {
+        self.insert(addr, None, true)
+    }
+
+    fn insert(&mut self, addr: usize, symbol: Option<String>, temporary: bool) -> Result<usize> {
+        if self.list().iter().any(|breakpoint| breakpoint.addr == addr) {
+            return Err(SoftwareBreakpointError::AlreadyExists(addr).into());
+        }
+
+        self.target.set_breakpoint(addr)?;
+        let list = &mut self.target.breakpoint_list;
+        let id = list.next_id;
+        list.next_id += 1;
+        list.breakpoints.push(Breakpoint {
+            id,
+            addr,
+            enabled: true,
+            hit_count: 0,
+            symbol,
+            has_condition: false,
+            temporary,
+        });
+        Ok(id)
+    }
+
+    /// Removes the breakpoint `id`, restoring the original instruction if it's enabled.
+    pub fn remove(&mut self, id: usize) -> Result<Breakpoint> {
+        self.disable(id)?;
+        let index = self.index(id)?;
+        self.target.breakpoint_list.conditions.remove(&id);
+        Ok(self.target.breakpoint_list.breakpoints.remove(index))
+    }
+
+    /// Enables the breakpoint `id`. Enabling an enabled breakpoint is a no-op.
+    pub fn enable(&mut self, id: usize) -> Result<()> {
+        let index = self.index(id)?;
+        let breakpoint = &self.target.breakpoint_list.breakpoints[index];
+        if !breakpoint.enabled {
+            let addr = breakpoint.addr;
+            self.target.set_breakpoint(addr)?;
+            self.target.breakpoint_list.breakpoints[index].enabled = true;
+        }
+        Ok(())
+    }
+
+    /// Disables the breakpoint `id`, restoring the original instruction. Disabling a disabled
+    /// breakpoint is a no-op.
+    pub fn disable(&mut self, id: usize) -> Result<()> {
+        let index = self.index(id)?;
+        let breakpoint = &self.target.breakpoint_list.breakpoints[index];
+        if breakpoint.enabled {
+            let addr = breakpoint.addr;
+            self.target.clear_breakpoint(addr)?;
+            self.target.breakpoint_list.breakpoints[index].enabled = false;
+        }
+        Ok(())
+    }
+
+    /// Makes `Breakpoints::cont` only stop on the breakpoint `id` if `condition` returns `true`
+    /// when it's hit, replacing any previous condition.
+    ///
+    /// `condition` is called with the debuggee stopped on the breakpoint, RIP already moved back
+    /// onto it, so it can inspect registers and memory, e.g. the arguments of a function:
+    /// `|target| target.read_regs().unwrap().rdi == 5`.
+    pub fn set_condition(
+        &mut self,
+        id: usize,
+        condition: impl Fn(&LinuxTarget) -> bool + Send + Sync + 'static,
+    ) -> Result<()> {
+        let index = self.index(id)?;
+        let list = &mut self.target.breakpoint_list;
+        list.conditions.insert(id, Condition(Arc::new(condition)));
+        list.breakpoints[index].has_condition = true;
+        Ok(())
+    }
+
+    /// Removes the condition of the breakpoint `id`, so every hit is reported again.
+    pub fn clear_condition(&mut self, id: usize) -> Result<()> {
+        let index = self.index(id)?;
+        let list = &mut self.target.breakpoint_list;
+        list.conditions.remove(&id);
+        list.breakpoints[index].has_condition = false;
+        Ok(())
+    }
+
+    /// Continues the debuggee until it stops on an enabled breakpoint whose condition holds, or
+    /// for any other reason, and returns the status of that stop.
+    ///
+    /// If the debuggee is stopped on a software breakpoint, it's stepped over first. A hit of a
+    /// breakpoint whose condition doesn't hold is neither counted nor reported: the debuggee is
+    /// stepped over the breakpoint and continued right away. A reported hit is counted and RIP
+    /// is moved back onto the breakpoint like with `record_hit`, which also removes a temporary
+    /// breakpoint.
+    #[cfg(target_arch = "x86_64")]
+    pub fn cont(&mut self) -> Result<nix::sys::wait::WaitStatus> {
+        use crate::target::UnixTarget;
+
+        loop {
+            let pc = self.target.read_regs()?.rip as usize;
+            if self.target.breakpoints.contains_key(&pc) {
+                let status = self.target.step_over_breakpoint(pc)?;
+                if !super::is_sigtrap(status) {
+                    return Ok(status);
+                }
+            }
+
+            let status = self.target.unpause()?;
+            if !super::is_sigtrap(status) {
+                return Ok(status);
+            }
+            let index = match self.rewind_hit()? {
+                Some(index) => index,
+                None => return Ok(status),
+            };
+
+            let id = self.list()[index].id;
+            let condition = self.target.breakpoint_list.conditions.get(&id).cloned();
+            let report = match condition {
+                Some(condition) => (condition.0)(self.target),
+                None => true,
+            };
+            if report {
+                self.count_hit(index)?;
+                return Ok(status);
+            }
+        }
+    }
+
+    /// Returns all breakpoints, ordered by id.
+    pub fn list(&self) -> &[Breakpoint] {
+        &self.target.breakpoint_list.breakpoints
+    }
+
+    /// Returns the breakpoint `id`.
+    pub fn get(&self, id: usize) -> Option<&Breakpoint> {
+        self.index(id).ok().map(|index| &self.list()[index])
+    }
+
+    /// Checks whether the debuggee has just stopped on one of the enabled breakpoints, and if
+    /// so, counts the hit and moves RIP back onto the breakpoint like
+    /// `LinuxTarget::rewind_breakpoint`.
+    ///
+    /// Returns the breakpoint that was hit. Use `LinuxTarget::step_over_breakpoint` to resume
+    /// from it, unless it was a temporary breakpoint, which is removed right away and can be
+    /// resumed from with `LinuxTarget::unpause`.
+    ///
+    /// The breakpoint is returned by value, as a temporary one isn't in the list anymore.
+    #[cfg(target_arch = "x86_64")]
+    pub fn record_hit(&mut self) -> Result<Option<Breakpoint>> {
+        match self.rewind_hit()? {
+            Some(index) => self.count_hit(index).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Counts a hit of the breakpoint at `index`, removing it if it's temporary.
+    #[cfg(target_arch = "x86_64")]
+    fn count_hit(&mut self, index: usize) -> Result<Breakpoint> {
+        self.target.breakpoint_list.breakpoints[index].hit_count += 1;
+        let breakpoint = &self.target.breakpoint_list.breakpoints[index];
+        if breakpoint.temporary {
+            self.remove(breakpoint.id)
+        } else {
+            Ok(breakpoint.clone())
+        }
+    }
+
+    /// Moves RIP back onto the enabled breakpoint the debuggee has just stopped at, returning
+    /// its index.
+    #[cfg(target_arch = "x86_64")]
+    fn rewind_hit(&mut self) -> Result<Option<usize>> {
+        let addr = (self.target.read_regs()?.rip as usize).wrapping_sub(1);
+        let index = match self
+            .list()
+            .iter()
+            .position(|breakpoint| breakpoint.enabled && breakpoint.addr == addr)
+        {
+            Some(index) => index,
+            None => return Ok(None),
+        };
+        Ok(self.target.rewind_breakpoint()?.map(|_| index))
+    }
+
+    fn index(&self, id: usize) -> Result<usize> {
+        self.list()
+            .binary_search_by_key(&id, |breakpoint| breakpoint.id)
+            .map_err(|_| SoftwareBreakpointError::UnknownId(id).into())
+    }
+}
+
+impl BreakpointList {
+    /// Returns all breakpoints, ordered by id.
+    pub(super) fn iter(&self) -> impl Iterator<Item = &Breakpoint> {
+        self.breakpoints.iter()
+    }
+
+    /// Forgets all breakpoints, e.g. after the debuggee replaced its image with `execve`.
+    pub(super) fn clear(&mut self) {
+        self.breakpoints.clear();
+        self.conditions.clear();
+    }
+}
@@ -0,0 +1,172 @@
+//! Writing ELF core files of a debuggee.
+
+use super::LinuxTarget;
+use crate::{Error, Result};
+use object::elf;
+use std::{
+    cmp,
+    fs::File,
+    io::{BufWriter, Write},
+    mem::{self, MaybeUninit},
+    path::Path,
+};
+
+const EHDR_SIZE: u64 = 64;
+const PHDR_SIZE: u64 = 56;
+/// Size of `struct elf_prstatus` on x86_64.
+pub(super) const PRSTATUS_SIZE: usize = 336;
+/// Offset of `pr_pid` in `struct elf_prstatus` on x86_64.
+pub(super) const PRSTATUS_PID_OFFSET: usize = 32;
+/// Offset of `pr_reg` in `struct elf_prstatus` on x86_64.
+pub(super) const PRSTATUS_REG_OFFSET: usize = 112;
+/// Memory is copied to the core file in chunks of this size.
+const CHUNK_SIZE: usize = 1 << 20;
+
+/// Writes a core file of `target` to `path`, see `LinuxTarget::write_core_dump`.
+pub(super) fn write_core_dump(target: &LinuxTarget, path: &Path) -> Result<()> {
+    let maps: Vec<_> = target
+        .memory_maps()?
+        .into_iter()
+        .filter(|map| map.is_readable)
+        .collect();
+    let note = prstatus_note(target)?;
+
+    let phnum = maps.len() + 1;
+    if phnum >= elf::PN_XNUM as usize {
+        return Err(Error::Other(format!(
+            "Too many memory maps for a core file: {}",
+            maps.len()
+        )));
+    }
+    let note_offset = EHDR_SIZE + PHDR_SIZE * phnum as u64;
+    let page_size = *super::PAGE_SIZE as u64;
+    let data_offset = (note_offset + note.len() as u64 + page_size - 1) & !(page_size - 1);
+
+    let mut out = BufWriter::new(File::create(path)?);
+
+    // ELF header
+    let mut ident = [0u8; 16];
+    ident[..4].copy_from_slice(&elf::ELFMAG);
+    ident[4] = elf::ELFCLASS64;
+    ident[5] = elf::ELFDATA2LSB;
+    ident[6] = elf::EV_CURRENT;
+    out.write_all(&ident)?;
+    out.write_all(&elf::ET_CORE.to_le_bytes())?;
+    out.write_all(&elf::EM_X86_64.to_le_bytes())?;
+    out.write_all(&(elf::EV_CURRENT as u32).to_le_bytes())?;
+    out.write_all(&0u64.to_le_bytes())?; // e_entry
+    out.write_all(&EHDR_SIZE.to_le_bytes())?; // e_phoff
+    out.write_all(&0u64.to_le_bytes())?; // e_shoff
+    out.write_all(&0u32.to_le_bytes())?; // e_flags
+    out.write_all(&(EHDR_SIZE as u16).to_le_bytes())?;
+    out.write_all(&(PHDR_SIZE as u16).to_le_bytes())?;
+    out.write_all(&(phnum as u16).to_le_bytes())?;
+    out.write_all(&[0; 6])?; // e_shentsize, e_shnum, e_shstrndx
+
+    // Program headers
+    write_phdr(
+        &mut out,
+        elf::PT_NOTE,
+        0,
+        note_offset,
+        0,
+        note.len() as u64,
+        4,
+    )?;
+    let mut offset = data_offset;
+    for map in &maps {
+        let mut flags = elf::PF_R;
+        if map.is_writable {
+            flags |= elf::PF_W;
+        }
+        if map.is_executable {
+            flags |= elf::PF_X;
+        }
+        let size = map.address.1 - map.address.0;
+        write_phdr(
+            &mut out,
+            elf::PT_LOAD,
+            flags,
+            offset,
+            map.address.0,
+            size,
+            page_size,
+        )?;
+        offset += size;
+    }
+
+    out.write_all(&note)?;
+    out.write_all(&vec![0; (data_offset - note_offset) as usize - note.len()])?;
+
+    // Memory contents. Parts that can't be read, like pages that are mapped past the end of their
+    // file, are left zeroed.
+    let mut buf: Vec<MaybeUninit<u8>> = Vec::with_capacity(CHUNK_SIZE);
+    unsafe { buf.set_len(CHUNK_SIZE) };
+    for map in &maps {
+        let mut addr = map.address.0 as usize;
+        let end = map.address.1 as usize;
+        while addr < end {
+            let len = cmp::min(end - addr, CHUNK_SIZE);
+            let chunk = &mut buf[..len];
+            let read_len = target
+                .read()
+                .read_slice_uninit(chunk, addr)
+                .apply_uninit()?;
+            // `apply_uninit` initialized this many bytes.
+            let read = unsafe { &*(&chunk[..read_len] as *const [MaybeUninit<u8>] as *const [u8]) };
+            out.write_all(read)?;
+            out.write_all(&vec![0; len - read_len])?;
+            addr += len;
+        }
+    }
+
+    out.flush()?;
+    Ok(())
+}
+
+fn write_phdr(
+    out: &mut impl Write,
+    typ: u32,
+    flags: u32,
+    offset: u64,
+    vaddr: u64,
+    size: u64,
+    align: u64,
+) -> std::io::Result<()> {
+    out.write_all(&typ.to_le_bytes())?;
+    out.write_all(&flags.to_le_bytes())?;
+    out.write_all(&offset.to_le_bytes())?;
+    out.write_all(&vaddr.to_le_bytes())?; // p_vaddr
+    out.write_all(&vaddr.to_le_bytes())?; // p_paddr
+    out.write_all(&size.to_le_bytes())?; // p_filesz
+    out.write_all(&size.to_le_bytes())?; // p_memsz
+    out.write_all(&align.to_le_bytes())
+}
+
+/// Builds an `NT_PRSTATUS` note with the registers of the debuggee's main thread.
+fn prstatus_note(target: &LinuxTarget) -> Result<Vec<u8>> {
+    let regs = target.read_regs()?;
+    let regs_bytes = unsafe {
+        std::slice::from_raw_parts(
+            &regs as *const libc::user_regs_struct as *const u8,
+            mem::size_of::<libc::user_regs_struct>(),
+        )
+    };
+
+    let mut prstatus = vec![0u8; PRSTATUS_SIZE];
+    let pid = target.pid.as_raw();
+    prstatus[PRSTATUS_PID_OFFSET..PRSTATUS_PID_OFFSET + 4].copy_from_slice(&pid.to_le_bytes());
+    prstatus[PRSTATUS_REG_OFFSET..PRSTATUS_REG_OFFSET + regs_bytes.len()]
+        .copy_from_slice(regs_bytes);
+
+    let name = b"CORE\0";
+    let mut note = Vec::with_capacity(12 + 8 + PRSTATUS_SIZE);
+    note.extend_from_slice(&(name.len() as u32).to_le_bytes());
+    note.extend_from_slice(&(PRSTATUS_SIZE as u32).to_le_bytes());
+    note.extend_from_slice(&elf::NT_PRSTATUS.to_le_bytes());
+    // The name and the descriptor are both padded to 4 bytes.
+    note.extend_from_slice(name);
+    note.extend_from_slice(&[0; 3]);
+    note.extend_from_slice(&prstatus);
+    Ok(note)
+}
@@ -0,0 +1,217 @@
+//! Reading ELF core files, see `CoreTarget`.
+
+use super::core_dump::{PRSTATUS_PID_OFFSET, PRSTATUS_REG_OFFSET, PRSTATUS_SIZE};
+use crate::{Error, Result};
+use object::{
+    elf,
+    read::elf::{FileHeader, ProgramHeader},
+    Bytes, LittleEndian,
+};
+use std::{fs::File, mem, path::Path};
+
+/// A `PT_LOAD` segment of a core file.
+#[derive(Debug, Clone, Copy)]
+struct Segment {
+    address: u64,
+    /// Size of the segment in memory. Only the first `file_size` bytes are in the file, the
+    /// rest is zeroed.
+    size: u64,
+    offset: u64,
+    file_size: u64,
+}
+
+impl Segment {
+    fn contains(&self, addr: u64) -> bool {
+        self.address <= addr && addr - self.address < self.size
+    }
+}
+
+/// A process as saved in an x86_64 ELF core file, e.g. by `LinuxTarget::write_core_dump` or by
+/// the kernel, for post-mortem debugging.
+///
+/// The registers of every thread are read from the `NT_PRSTATUS` notes, the memory from the
+/// `PT_LOAD` segments.
+pub struct CoreTarget {
+    mmap: memmap::Mmap,
+    /// Sorted by address.
+    segments: Vec<Segment>,
+    /// Thread ids and registers, in the order of the notes, so the thread that caused the dump
+    /// comes first.
+    threads: Vec<(i32, libc::user_regs_struct)>,
+}
+
+impl CoreTarget {
+    /// Opens the core file at `path`.
+    pub fn open(path: &Path) -> Result<CoreTarget> {
+        let file = File::open(path)?;
+        // Safety: Not really, this assumes that the core file will not be truncated or written
+        // to while it is used by us.
+        let mmap = unsafe { memmap::Mmap::map(&file)? };
+
+        let data = Bytes(&mmap);
+        let header = elf::FileHeader64::<LittleEndian>::parse(data)?;
+        let endian = LittleEndian;
+        if header.e_type(endian) != elf::ET_CORE || header.e_machine(endian) != elf::EM_X86_64 {
+            return Err(Error::Other(format!(
+                "{} is not an x86_64 core file",
+                path.display()
+            )));
+        }
+
+        let mut segments = Vec::new();
+        let mut threads = Vec::new();
+        for phdr in header.program_headers(endian, data)? {
+            match phdr.p_type(endian) {
+                elf::PT_LOAD => {
+                    let (offset, file_size) = phdr.file_range(endian);
+                    if offset + file_size > mmap.len() as u64 {
+                        return Err(Error::Other(format!(
+                            "Segment at 0x{:x} is out of bounds of the core file",
+                            phdr.p_vaddr(endian)
+                        )));
+                    }
+                    segments.push(Segment {
+                        address: phdr.p_vaddr(endian),
+                        size: phdr.p_memsz(endian),
+                        offset,
+                        file_size,
+                    });
+                }
+                elf::PT_NOTE => {
+                    let notes = phdr.data(endian, data).map_err(|()| {
+                        Error::Other("Note segment is out of bounds of the core file".to_string())
+                    })?;
+                    parse_prstatus_notes(notes.0, &mut threads)?;
+                }
+                _ => {}
+            }
+        }
+        if threads.is_empty() {
+            return Err(Error::Other(format!(
+                "{} has no NT_PRSTATUS note",
+                path.display()
+            )));
+        }
+        segments.sort_by_key(|segment| segment.address);
+
+        Ok(CoreTarget {
+            mmap,
+            segments,
+            threads,
+        })
+    }
+
+    /// Returns the id of the process the core file was written for.
+    pub fn pid(&self) -> i32 {
+        self.threads[0].0
+    }
+
+    /// Returns the ids of the threads that have registers in the core file.
+    pub fn thread_ids(&self) -> Vec<i32> {
+        self.threads.iter().map(|&(tid, _)| tid).collect()
+    }
+
+    /// Returns the registers of the first thread in the core file, which is the one that caused
+    /// the dump.
+    pub fn read_regs(&self) -> libc::user_regs_struct {
+        self.threads[0].1
+    }
+
+    /// Returns the general purpose registers of the first thread in the core file.
+    pub fn read_registers(&self) -> super::super::Registers {
+        self.read_regs().into()
+    }
+
+    /// Returns the registers of the thread `tid`, or `None` if the core file has none for it.
+    pub fn read_thread_regs(&self, tid: i32) -> Option<libc::user_regs_struct> {
+        self.threads
+            .iter()
+            .find(|&&(thread_id, _)| thread_id == tid)
+            .map(|&(_, regs)| regs)
+    }
+
+    /// Reads `buf.len()` bytes of the saved memory at `addr`. The range may span several
+    /// adjacent segments.
+    ///
+    /// Parts of a segment that weren't saved in the file, like memory that couldn't be read
+    /// when the core file was written, are read as zeros. Fails if a part of the range isn't
+    /// in any segment.
+    pub fn read_memory(&self, addr: usize, buf: &mut [u8]) -> Result<()> {
+        let mut addr = addr as u64;
+        let mut buf = buf;
+        while !buf.is_empty() {
+            let segment = self
+                .segments
+                .iter()
+                .find(|segment| segment.contains(addr))
+                .ok_or_else(|| {
+                    Error::Other(format!("Address 0x{:x} is not in the core file", addr))
+                })?;
+            let start = addr - segment.address;
+            let len = std::cmp::min(buf.len() as u64, segment.size - start) as usize;
+            let (chunk, rest) = buf.split_at_mut(len);
+
+            let in_file = std::cmp::min(segment.file_size.saturating_sub(start), len as u64);
+            let offset = (segment.offset + start) as usize;
+            chunk[..in_file as usize]
+                .copy_from_slice(&self.mmap[offset..offset + in_file as usize]);
+            for byte in &mut chunk[in_file as usize..] {
+                *byte = 0;
+            }
+
+            addr += len as u64;
+            buf = rest;
+        }
+        Ok(())
+    }
+
+    /// Reads a `T` from the saved memory at `addr`, see `LinuxTarget::read_struct`.
+    pub fn read_struct<T: bytemuck::Pod>(&self, addr: usize) -> Result<T> {
+        let mut value = T::zeroed();
+        self.read_memory(addr, bytemuck::bytes_of_mut(&mut value))?;
+        Ok(value)
+    }
+}
+
+/// Appends the thread id and registers of every `NT_PRSTATUS` note in `notes` to `threads`.
+fn parse_prstatus_notes(
+    mut notes: &[u8],
+    threads: &mut Vec<(i32, libc::user_regs_struct)>,
+) -> Result<()> {
+    let align = |len: usize| (len + 3) & !3;
+    let read_u32 = |data: &[u8], offset: usize| {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(&data[offset..offset + 4]);
+        u32::from_le_bytes(bytes)
+    };
+
+    while notes.len() >= 12 {
+        let name_size = read_u32(notes, 0) as usize;
+        let desc_size = read_u32(notes, 4) as usize;
+        let typ = read_u32(notes, 8);
+        let desc_offset = 12 + align(name_size);
+        let end = desc_offset + align(desc_size);
+        if desc_offset + desc_size > notes.len() {
+            return Err(Error::Other("Truncated note in the core file".to_string()));
+        }
+
+        if typ == elf::NT_PRSTATUS && desc_size >= PRSTATUS_SIZE {
+            let desc = &notes[desc_offset..desc_offset + desc_size];
+            let pid = read_u32(desc, PRSTATUS_PID_OFFSET) as i32;
+            let mut regs: libc::user_regs_struct = unsafe { mem::zeroed() };
+            let regs_len = mem::size_of::<libc::user_regs_struct>();
+            // Safety: `user_regs_struct` only consists of integers, any bytes are valid.
+            unsafe {
+                std::ptr::copy_nonoverlapping(
+                    desc[PRSTATUS_REG_OFFSET..PRSTATUS_REG_OFFSET + regs_len].as_ptr(),
+                    &mut regs as *mut libc::user_regs_struct as *mut u8,
+                    regs_len,
+                );
+            }
+            threads.push((pid, regs));
+        }
+
+        notes = notes.get(end..).unwrap_or(&[]);
+    }
+    Ok(())
+}
@@ -0,0 +1,309 @@
+use crate::Result;
+
+/// Ptrace register set of the aarch64 hardware breakpoint registers.
+#[cfg(target_arch = "aarch64")]
+pub(super) const NT_ARM_HW_BREAK: usize = 0x402;
+/// Ptrace register set of the aarch64 hardware watchpoint registers.
+#[cfg(target_arch = "aarch64")]
+pub(super) const NT_ARM_HW_WATCH: usize = 0x403;
+
+/// `struct user_hwdebug_state` read and written with the aarch64 register sets above.
+#[cfg(target_arch = "aarch64")]
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub(super) struct UserHwdebugState {
+    /// The number of available slots is in the lowest byte.
+    pub dbg_info: u32,
+    pub pad: u32,
+    pub dbg_regs: [UserHwdebugReg; 16],
+}
+
+#[cfg(target_arch = "aarch64")]
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub(super) struct UserHwdebugReg {
+    pub addr: u64,
+    pub ctrl: u32,
+    pub pad: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct HardwareBreakpoint {
+    pub typ: HardwareBreakpointType,
+    pub addr: usize,
+    pub size: HardwareBreakpointSize,
+}
+
+impl HardwareBreakpoint {
+    #[cfg(target_arch = "x86_64")]
+    pub(super) fn size_bits(&self, index: usize) -> u64 {
+        (self.size as u64) << (18 + index * 4)
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    pub(super) const fn bit_mask(index: usize) -> u64 {
+        (0b11 << (2 * index)) | (0b1111 << (16 + 4 * index))
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    pub(super) fn rw_bits(&self, index: usize) -> u64 {
+        let type_bites = match self.typ {
+            HardwareBreakpointType::Execute => 0b00,
+            HardwareBreakpointType::Read => 0b11,
+            HardwareBreakpointType::ReadWrite => 0b11,
+            HardwareBreakpointType::Write => 0b01,
+        };
+        type_bites << 16 + index * 4
+    }
+
+    /// Encodes the address and control register values of this breakpoint on aarch64.
+    ///
+    /// Watchpoints are set on the doubleword containing `addr`, so the watched bytes can't cross
+    /// a multiple of 8.
+    #[cfg(any(target_arch = "aarch64", test))]
+    pub(super) fn aarch64_regs(&self) -> Result<(u64, u32), HardwareBreakpointError> {
+        // Enabled for EL0, i.e. user space, only.
+        const ENABLE_EL0: u32 = (0b10 << 1) | 1;
+
+        let lsc = match self.typ {
+            // A64 instructions are always 4 bytes long.
+            HardwareBreakpointType::Execute => {
+                return Ok((self.addr as u64, (0b1111 << 5) | ENABLE_EL0))
+            }
+            HardwareBreakpointType::Read => 0b01,
+            HardwareBreakpointType::Write => 0b10,
+            HardwareBreakpointType::ReadWrite => 0b11,
+        };
+        let offset = self.addr & 7;
+        let len = self.size.bytes();
+        if offset + len > 8 {
+            return Err(HardwareBreakpointError::UnalignedWatchpoint(self.addr));
+        }
+        let byte_select = ((1 << len) - 1) << offset;
+        Ok((
+            (self.addr & !7) as u64,
+            (byte_select << 5) | (lsc << 3) | ENABLE_EL0,
+        ))
+    }
+
+    /// Checks that the breakpoint is aligned to its size, which x86_64 requires for the debug
+    /// registers to match the watched bytes.
+    #[cfg(any(target_arch = "x86_64", test))]
+    pub(super) fn check_x86_64_alignment(&self) -> Result<(), HardwareBreakpointError> {
+        let size = self.size.bytes();
+        if self.addr & (size - 1) != 0 {
+            return Err(HardwareBreakpointError::Misaligned {
+                addr: self.addr,
+                size,
+            });
+        }
+        Ok(())
+    }
+
+    /// Decodes the breakpoint at `index` from DR7 and the address in its debug register.
+    /// Returns `None` if it's disabled or watches I/O ports.
+    #[cfg(target_arch = "x86_64")]
+    pub(super) fn from_dr7(dr7: u64, index: usize, addr: usize) -> Option<Self> {
+        if dr7 & (0b11 << (2 * index)) == 0 {
+            return None;
+        }
+        let typ = match (dr7 >> (16 + index * 4)) & 0b11 {
+            0b00 => HardwareBreakpointType::Execute,
+            0b01 => HardwareBreakpointType::Write,
+            0b11 => HardwareBreakpointType::ReadWrite,
+            _ => return None,
+        };
+        let size = match (dr7 >> (18 + index * 4)) & 0b11 {
+            0b00 => HardwareBreakpointSize::_1,
+            0b01 => HardwareBreakpointSize::_2,
+            0b11 => HardwareBreakpointSize::_4,
+            _ => HardwareBreakpointSize::_8,
+        };
+        Some(HardwareBreakpoint { typ, addr, size })
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HardwareBreakpointSize {
+    _1 = 0b00,
+    _2 = 0b01,
+    _4 = 0b11,
+    _8 = 0b10,
+}
+impl HardwareBreakpointSize {
+    /// Returns the number of bytes watched.
+    pub fn bytes(self) -> usize {
+        match self {
+            Self::_1 => 1,
+            Self::_2 => 2,
+            Self::_4 => 4,
+            Self::_8 => 8,
+        }
+    }
+
+    pub fn from_usize(size: usize) -> Result<Self> {
+        match size {
+            1 => Ok(Self::_1),
+            2 => Ok(Self::_2),
+            4 => Ok(Self::_4),
+            8 => Ok(Self::_8),
+            x => Err(HardwareBreakpointError::UnsupportedWatchSize(x).into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HardwareBreakpointType {
+    Execute,
+    Write,
+    Read,
+    ReadWrite,
+}
+
+/// The debug status register (DR6), telling which debug conditions caused the last debug exception.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DebugStatus(pub u64);
+
+impl DebugStatus {
+    /// Returns whether the condition of the hardware breakpoint at `index` was met.
+    pub fn condition_met(&self, index: usize) -> bool {
+        self.0 & (1 << index) != 0
+    }
+
+    /// Returns whether the debug exception was raised by an access to the debug registers (BD).
+    pub fn debug_register_access(&self) -> bool {
+        self.0 & (1 << 13) != 0
+    }
+
+    /// Returns whether the debug exception was raised by single stepping.
+    pub fn single_step(&self) -> bool {
+        self.0 & (1 << 14) != 0
+    }
+
+    /// Returns whether the debug exception was raised by a task switch (BT).
+    pub fn task_switch(&self) -> bool {
+        self.0 & (1 << 15) != 0
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum HardwareBreakpointError {
+    NoEmptyWatchpoint,
+    DoesNotExist(usize),
+    UnsupportedPlatform,
+    UnsupportedWatchSize(usize),
+    RegisterStateMismatch(usize),
+    UnalignedWatchpoint(usize),
+    Misaligned { addr: usize, size: usize },
+}
+
+impl std::fmt::Display for HardwareBreakpointError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let string = match self {
+            HardwareBreakpointError::NoEmptyWatchpoint => {
+                "No unused hardware breakpoints left".to_string()
+            }
+            HardwareBreakpointError::DoesNotExist(index) => format!(
+                "Hardware breakpoint at specified index ({}) does not exist",
+                index
+            ),
+            HardwareBreakpointError::UnsupportedPlatform => {
+                "Hardware breakpoints not supported on this platform".to_string()
+            }
+            HardwareBreakpointError::UnsupportedWatchSize(size) => {
+                format!("Hardware breakpoint size of {} is not supported", size)
+            }
+            HardwareBreakpointError::UnalignedWatchpoint(addr) => format!(
+                "Hardware watchpoint at 0x{:x} crosses an 8 byte boundary",
+                addr
+            ),
+            HardwareBreakpointError::Misaligned { addr, size } => format!(
+                "Hardware breakpoint at 0x{:x} is not aligned to its size of {} bytes",
+                addr, size
+            ),
+            HardwareBreakpointError::RegisterStateMismatch(index) => format!(
+                "Debug registers don't match the hardware breakpoint at index ({})",
+                index
+            ),
+        };
+        write!(f, "{}", string)
+    }
+}
+
+impl std::error::Error for HardwareBreakpointError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aarch64_regs(
+        typ: HardwareBreakpointType,
+        addr: usize,
+        size: usize,
+    ) -> Result<(u64, u32), HardwareBreakpointError> {
+        HardwareBreakpoint {
+            typ,
+            addr,
+            size: HardwareBreakpointSize::from_usize(size).unwrap(),
+        }
+        .aarch64_regs()
+    }
+
+    #[test]
+    fn x86_64_alignment() {
+        let check = |addr, size| {
+            HardwareBreakpoint {
+                typ: HardwareBreakpointType::Write,
+                addr,
+                size: HardwareBreakpointSize::from_usize(size).unwrap(),
+            }
+            .check_x86_64_alignment()
+        };
+
+        for &size in &[1, 2, 4, 8] {
+            assert!(check(0x1000, size).is_ok());
+            assert!(check(0x1000 + size, size).is_ok());
+        }
+        for &(addr, size) in &[
+            (0x1001, 2),
+            (0x1002, 4),
+            (0x1003, 4),
+            (0x1004, 8),
+            (0x1007, 8),
+        ] {
+            match check(addr, size) {
+                Err(HardwareBreakpointError::Misaligned {
+                    addr: err_addr,
+                    size: err_size,
+                }) if err_addr == addr && err_size == size => {}
+                result => panic!("Unexpected result for {:?}: {:?}", (addr, size), result),
+            }
+        }
+    }
+
+    #[test]
+    fn aarch64_breakpoint_regs() {
+        let (addr, ctrl) = aarch64_regs(HardwareBreakpointType::Execute, 0x1004, 1).unwrap();
+        assert_eq!(addr, 0x1004);
+        assert_eq!(ctrl, 0b1_1110_0101);
+    }
+
+    #[test]
+    fn aarch64_watchpoint_regs() {
+        let (addr, ctrl) = aarch64_regs(HardwareBreakpointType::Write, 0x1002, 2).unwrap();
+        assert_eq!(addr, 0x1000);
+        assert_eq!(ctrl, (0b1100 << 5) | (0b10 << 3) | 0b101);
+
+        let (addr, ctrl) = aarch64_regs(HardwareBreakpointType::Read, 0x1008, 8).unwrap();
+        assert_eq!(addr, 0x1008);
+        assert_eq!(ctrl, (0xff << 5) | (0b01 << 3) | 0b101);
+
+        let (_, ctrl) = aarch64_regs(HardwareBreakpointType::ReadWrite, 0x1007, 1).unwrap();
+        assert_eq!(ctrl, (0b1000_0000 << 5) | (0b11 << 3) | 0b101);
+
+        match aarch64_regs(HardwareBreakpointType::Write, 0x1006, 4) {
+            Err(HardwareBreakpointError::UnalignedWatchpoint(0x1006)) => {}
+            regs => panic!("Unexpected result: {:?}", regs),
+        }
+    }
+}
@@ -0,0 +1,448 @@
+use super::{
+    memory::{split_protected, MemoryOp, UIO_MAXIOV},
+    LinuxTarget,
+};
+use crate::Result;
+use nix::{sys::ptrace, unistd::Pid};
+use std::{marker::PhantomData, mem, os::unix::fs::FileExt};
+
+/// Read operations don't have any unique properties at this time.
+/// If needed, later this can be replaced with `struct ReadOp(MemoryOp, <extra props>)`.
+type ReadOp = MemoryOp;
+
+/// The way `ReadMemory` reads the debuggee's memory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Backend {
+    /// `process_vm_readv`, falling back to ptrace for protected pages.
+    ProcessVm,
+    /// `pread` on `/proc/<pid>/mem`.
+    ProcMem,
+}
+
+/// Allows to read memory from different locations in debuggee's memory as a single operation.
+pub struct ReadMemory<'a> {
+    target: &'a LinuxTarget,
+    read_ops: Vec<ReadOp>,
+    backend: Backend,
+    /// Whether software breakpoints are replaced with the original bytes, see `hide_breakpoints`.
+    hide_breakpoints: bool,
+    /// This requires a mutable reference because we rewrite values of variables in `ReadOp`.
+    _marker: PhantomData<&'a mut ()>,
+}
+
+impl<'a> ReadMemory<'a> {
+    pub(in crate::target) fn new(target: &'a LinuxTarget) -> Self {
+        ReadMemory {
+            target,
+            read_ops: Vec::new(),
+            backend: Backend::ProcessVm,
+            hide_breakpoints: false,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Reads with `pread` on `/proc/<pid>/mem` instead of `process_vm_readv`.
+    ///
+    /// This needs a system call per contiguous range, so it's slower for many scattered reads,
+    /// but works where `process_vm_readv` isn't available and, like ptrace, can read protected
+    /// pages too. The file is opened once and kept open by the target. If some of the memory
+    /// still can't be read, `apply` falls back to the default way of reading it.
+    pub fn use_proc_mem(mut self) -> Self {
+        self.backend = Backend::ProcMem;
+        self
+    }
+
+    /// Makes the memory read as if there were no software breakpoints: the `int3` of the
+    /// breakpoints in the read ranges is replaced with the original bytes, see
+    /// `LinuxTarget::breakpoint_at`.
+    pub fn hide_breakpoints(mut self) -> Self {
+        self.hide_breakpoints = true;
+        self
+    }
+
+    /// Reads a value of type `T` from debuggee's memory at location `remote_base`.
+    /// This value will be written to the provided variable `val`.
+    /// You should call `apply` in order to execute the memory read operation.
+    /// The provided variable `val` can't be accessed until either `apply` is called or `self` is
+    /// dropped.
+    ///
+    /// # Safety
+    ///
+    /// The type `T` must not have any invalid values.
+    /// For example, `T` must not be a `bool`, as `transmute::<u8, bool>(2)` is not a valid value for a bool.
+    /// In case of doubt, wrap the type in [`mem::MaybeUninit`].
+    // todo: further document mem safety - e.g., what happens in the case of partial read
+    pub unsafe fn read<T>(mut self, val: &'a mut T, remote_base: usize) -> Self {
+        MemoryOp::split_on_page_boundary(
+            &MemoryOp {
+                remote_base,
+                local_ptr: val as *mut T as *mut libc::c_void,
+                local_ptr_len: mem::size_of::<T>(),
+            },
+            &mut self.read_ops,
+        );
+        self
+    }
+
+    /// Reads a value of type `*mut T` from debuggee's memory at location `remote_base`.
+    /// This value will be written to the provided pointer `ptr`.
+    /// You should call `apply` in order to execute the memory read operation.
+    /// The provided pointer `ptr` can't be accessed until either `apply` is called or `self` is
+    /// dropped.
+    ///
+    /// # Safety
+    ///
+    /// Memory location at `ptr` must be of valid size and must not be outlived by `ReadMem`.
+    /// You need to ensure the lifetime guarantees, and generally you should prefer using `read<T>(&mut val)`.
+    // todo: further document mem safety - e.g., what happens in the case of partial read
+    pub unsafe fn read_ptr<T>(mut self, ptr: *mut T, remote_base: usize) -> Self {
+        MemoryOp::split_on_page_boundary(
+            &MemoryOp {
+                remote_base,
+                local_ptr: ptr as *mut _,
+                local_ptr_len: mem::size_of::<T>(),
+            },
+            &mut self.read_ops,
+        );
+        self
+    }
+
+    /// Reads a slice of type `&mut [T]` from debuggee's memory at location `remote_base`.
+    /// This value will be written to the provided slice `val`.
+    /// You should call `apply` in order to execute the memory read operation.
+    /// The provided value `val` can't be accessed until either `apply` is called or `self` is
+    /// dropped.
+    ///
+    /// # Safety
+    ///
+    /// The type `T` must not have any invalid values.
+    /// For example, `T` must not be a `bool`, as `transmute::<u8, bool>(2)` is not a valid value for a bool.
+    /// In case of doubt, wrap the type in [`mem::MaybeUninit`].
+    // todo: further document mem safety - e.g., what happens in the case of partial read
+    pub unsafe fn read_slice<T>(mut self, val: &'a mut [T], remote_base: usize) -> Self {
+        MemoryOp::split_on_page_boundary(
+            &MemoryOp {
+                remote_base,
+                local_ptr: val.as_mut_ptr() as *mut _,
+                local_ptr_len: val.len() * mem::size_of::<T>(),
+            },
+            &mut self.read_ops,
+        );
+        self
+    }
+
+    /// Reads a slice of type `&mut [MaybeUninit<T>]` from debuggee's memory at location
+    /// `remote_base`.
+    /// Unlike `read_slice`, the slice doesn't have to be initialized beforehand, which spares
+    /// zeroing large buffers. You should call `apply_uninit` in order to execute the memory read
+    /// operation and find out how much of `val` has been initialized.
+    pub fn read_slice_uninit<T>(
+        mut self,
+        val: &'a mut [mem::MaybeUninit<T>],
+        remote_base: usize,
+    ) -> Self {
+        MemoryOp::split_on_page_boundary(
+            &MemoryOp {
+                remote_base,
+                local_ptr: val.as_mut_ptr() as *mut _,
+                local_ptr_len: val.len() * mem::size_of::<T>(),
+            },
+            &mut self.read_ops,
+        );
+        self
+    }
+
+    /// Reads a `u8` byte slice from debuggee's memory at location `remote_base`.
+    /// This value will be written to the provided slice `val`.
+    /// You should call `apply` in order to execute the memory read operation.
+    pub fn read_byte_slice(mut self, val: &'a mut [u8], remote_base: usize) -> Self {
+        MemoryOp::split_on_page_boundary(
+            &MemoryOp {
+                remote_base,
+                local_ptr: val.as_mut_ptr() as *mut _,
+                local_ptr_len: val.len(),
+            },
+            &mut self.read_ops,
+        );
+        self
+    }
+
+    /// Executes the memory read operation.
+    ///
+    /// All reads are attempted with a single `process_vm_readv` call first (or one per
+    /// `UIO_MAXIOV` read ops). If some pages can't be read this way, the reads that didn't
+    /// complete are split by the debuggee's memory maps: readable pages are read with another
+    /// `process_vm_readv` call and protected pages are read with ptrace.
+    pub fn apply(mut self) -> Result<()> {
+        self.apply_and_keep()
+    }
+
+    /// Executes the memory read operation like `apply`, but keeps the reads, so the same
+    /// locations can be read again by another call, e.g. to poll the values shown in a watch
+    /// window.
+    ///
+    /// Destinations added with `read` or `read_slice` stay borrowed as long as `self` lives, so
+    /// use `read_ptr` for values that are looked at between the calls.
+    pub fn apply_and_keep(&mut self) -> Result<()> {
+        self.read_all()?;
+        if self.hide_breakpoints {
+            self.restore_breakpoint_bytes(total_len(&self.read_ops));
+        }
+        Ok(())
+    }
+
+    /// Removes all reads added so far, keeping the other options.
+    pub fn clear(&mut self) {
+        self.read_ops.clear();
+    }
+
+    /// Executes the memory read operation up to the first location that can't be read.
+    ///
+    /// Returns the number of bytes read. Reads are executed in the order they were added, so
+    /// this many bytes of the destinations, taken in that order, have been initialized.
+    pub fn apply_uninit(self) -> Result<usize> {
+        let bytes_read = self.read_until_error()?;
+        if self.hide_breakpoints {
+            self.restore_breakpoint_bytes(bytes_read);
+        }
+        Ok(bytes_read)
+    }
+
+    fn read_all(&self) -> Result<()> {
+        let pid = self.target.pid;
+        let read_len = total_len(&self.read_ops);
+
+        if read_len > isize::MAX as usize {
+            panic!("Read size too big");
+        };
+
+        // FIXME: Probably a better way to do this - see if we can get info about pages protection from
+        // cache and predict whether this operation will require ptrace or plain read_process_vm would work.
+        let bytes_read = match self.backend {
+            Backend::ProcMem => Self::read_proc_mem(self.target, &self.read_ops)?,
+            Backend::ProcessVm => match Self::read_process_vm(pid, &self.read_ops) {
+                Ok(bytes_read) => bytes_read as usize,
+                Err(nix::Error::Sys(nix::errno::Errno::EFAULT)) => 0,
+                Err(err) => return Err(err.into()),
+            },
+        };
+        if bytes_read == read_len {
+            return Ok(());
+        }
+
+        let protected_maps = self
+            .target
+            .memory_maps()?
+            .into_iter()
+            .filter(|map| !map.is_readable)
+            .collect::<Vec<_>>();
+
+        let unread = &self.read_ops[completed_ops(&self.read_ops, bytes_read)..];
+        let (mut protected, readable) = split_protected(&protected_maps, unread.iter().copied())?;
+
+        if !readable.is_empty() {
+            let bytes_read = match Self::read_process_vm(pid, &readable) {
+                Ok(bytes_read) => bytes_read as usize,
+                Err(nix::Error::Sys(nix::errno::Errno::EFAULT)) => 0,
+                Err(err) => return Err(err.into()),
+            };
+            // Memory maps may be out of date, so some of the pages weren't readable after all.
+            protected.extend_from_slice(&readable[completed_ops(&readable, bytes_read)..]);
+        }
+        Self::read_ptrace(pid, &protected)?;
+
+        Ok(())
+    }
+
+    fn read_until_error(&self) -> Result<usize> {
+        let pid = self.target.pid;
+        if self.backend == Backend::ProcMem {
+            // Protected pages don't need a ptrace fallback here.
+            return Self::read_proc_mem(self.target, &self.read_ops);
+        }
+        let mut bytes_read = 0;
+        let mut read_ops = &self.read_ops[..];
+
+        while !read_ops.is_empty() {
+            let read_len = match Self::read_process_vm(pid, read_ops) {
+                Ok(read_len) => read_len as usize,
+                Err(nix::Error::Sys(nix::errno::Errno::EFAULT)) => 0,
+                Err(err) => return Err(err.into()),
+            };
+            bytes_read += read_len;
+
+            read_ops = &read_ops[completed_ops(read_ops, read_len)..];
+
+            // The first unread op may be in a protected page, which only ptrace can read.
+            match read_ops.split_first() {
+                Some((read_op, rest)) => {
+                    if Self::read_ptrace(pid, std::slice::from_ref(read_op)).is_err() {
+                        break;
+                    }
+                    bytes_read += read_op.local_ptr_len;
+                    read_ops = rest;
+                }
+                None => break,
+            }
+        }
+
+        Ok(bytes_read)
+    }
+
+    /// Writes the original bytes of the software breakpoints into the first `read_len` bytes of
+    /// the destinations, taken in the order the reads were added.
+    fn restore_breakpoint_bytes(&self, read_len: usize) {
+        let mut remaining = read_len;
+        for read_op in &self.read_ops {
+            let len = std::cmp::min(read_op.local_ptr_len, remaining);
+            for (&addr, &orig_byte) in &self.target.breakpoints {
+                if let Some(offset) = addr
+                    .checked_sub(read_op.remote_base)
+                    .filter(|&offset| offset < len)
+                {
+                    // Safety: the read op points to a buffer borrowed by `ReadMemory`, which is
+                    // at least `len` bytes long.
+                    unsafe { *(read_op.local_ptr as *mut u8).add(offset) = orig_byte };
+                }
+            }
+            remaining -= len;
+            if remaining == 0 {
+                break;
+            }
+        }
+    }
+
+    /// Allows to read from several different locations with one system call per `UIO_MAXIOV`
+    /// read ops.
+    /// It will error on pages that are not readable. Returns number of bytes read at granularity of ReadOps.
+    fn read_process_vm(pid: Pid, read_ops: &[ReadOp]) -> Result<isize, nix::Error> {
+        let mut bytes_read = 0;
+
+        for read_ops in read_ops.chunks(UIO_MAXIOV) {
+            let remote_iov = read_ops
+                .iter()
+                .map(|read_op| read_op.as_remote_iovec())
+                .collect::<Vec<_>>();
+
+            let local_iov = read_ops
+                .iter()
+                .map(|read_op| read_op.as_local_iovec())
+                .collect::<Vec<_>>();
+
+            let chunk_read = unsafe {
+                // todo: document unsafety
+                libc::process_vm_readv(
+                    pid.into(),
+                    local_iov.as_ptr(),
+                    local_iov.len() as libc::c_ulong,
+                    remote_iov.as_ptr(),
+                    remote_iov.len() as libc::c_ulong,
+                    0,
+                )
+            };
+
+            if chunk_read == -1 {
+                if bytes_read > 0 {
+                    break;
+                }
+                return Err(nix::Error::last());
+            }
+
+            bytes_read += chunk_read;
+            if chunk_read as usize != total_len(read_ops) {
+                break;
+            }
+        }
+
+        Ok(bytes_read)
+    }
+
+    /// Reads with `pread` on `/proc/<pid>/mem` up to the first location that can't be read.
+    /// Returns the number of bytes read.
+    fn read_proc_mem(target: &LinuxTarget, read_ops: &[ReadOp]) -> Result<usize> {
+        let mem_file = target.mem_file()?;
+        let mut bytes_read = 0;
+
+        for read_op in read_ops {
+            let len = read_op.local_ptr_len;
+            // Safety: every read op points to a buffer borrowed by `ReadMemory`. Read ops are
+            // never merged, as adjacent ones may be distinct buffers.
+            let buf = unsafe { std::slice::from_raw_parts_mut(read_op.local_ptr as *mut u8, len) };
+            let mut offset = 0;
+            while offset < len {
+                match mem_file.read_at(&mut buf[offset..], (read_op.remote_base + offset) as u64) {
+                    Ok(0) | Err(_) => break,
+                    Ok(read) => offset += read,
+                }
+            }
+            bytes_read += offset;
+
+            if offset != len {
+                break;
+            }
+        }
+
+        Ok(bytes_read)
+    }
+
+    /// Allows to read from protected memory pages.
+    /// This operation results in multiple system calls and is inefficient.
+    fn read_ptrace(pid: Pid, read_ops: &[MemoryOp]) -> Result<()> {
+        let long_size = std::mem::size_of::<std::os::raw::c_long>();
+
+        for read_op in read_ops {
+            let mut offset: usize = 0;
+            // Read until all of the data is read
+            while offset < read_op.local_ptr_len {
+                let data =
+                    ptrace::read(pid, (read_op.remote_base + offset) as *mut std::ffi::c_void)?;
+
+                // Read full word. No need to preserve other data
+                if (read_op.local_ptr_len - offset) >= long_size {
+                    // todo: document unsafety
+                    // The local buffer can have any alignment, e.g. when reading into a byte slice.
+                    unsafe {
+                        ((read_op.local_ptr as usize + offset) as *mut i64).write_unaligned(data);
+                    }
+
+                // Read part smaller than word. Need to preserve other data
+                } else {
+                    // todo: document unsafety
+                    unsafe {
+                        let previous_bytes: &mut [u8] = std::slice::from_raw_parts_mut(
+                            (read_op.local_ptr as usize + offset) as *mut u8,
+                            read_op.local_ptr_len - offset,
+                        );
+                        let data_bytes = data.to_ne_bytes();
+
+                        previous_bytes[0..(read_op.local_ptr_len - offset)]
+                            .clone_from_slice(&data_bytes[0..(read_op.local_ptr_len - offset)]);
+                    }
+                }
+                offset += long_size;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the number of bytes read by `read_ops`.
+fn total_len(read_ops: &[ReadOp]) -> usize {
+    read_ops
+        .iter()
+        .fold(0, |sum, read_op| sum + read_op.local_ptr_len)
+}
+
+/// Returns the number of `read_ops` completed by a vector read of `bytes_read` bytes.
+///
+/// Each read op resides in a single page, so it's either read completely or not at all.
+fn completed_ops(read_ops: &[ReadOp], bytes_read: usize) -> usize {
+    let mut len = 0;
+    read_ops
+        .iter()
+        .take_while(|read_op| {
+            len += read_op.local_ptr_len;
+            len <= bytes_read
+        })
+        .count()
+}
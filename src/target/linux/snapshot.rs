@@ -0,0 +1,175 @@
+//! Snapshots of a debuggee's registers and writable memory, for finding out what changed.
+
+use super::LinuxTarget;
+use crate::target::Registers;
+use crate::Result;
+use std::{cmp, mem::MaybeUninit, ops::Range};
+
+/// The registers and writable memory of a debuggee at one point in time, as captured by
+/// [`LinuxTarget::snapshot`].
+///
+/// [`LinuxTarget::snapshot`]: struct.LinuxTarget.html#method.snapshot
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    registers: Registers,
+    /// Start addresses and contents of the captured memory, sorted by address.
+    regions: Vec<(usize, Vec<u8>)>,
+}
+
+/// The differences between two snapshots, as returned by [`Snapshot::diff`].
+///
+/// [`Snapshot::diff`]: struct.Snapshot.html#method.diff
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SnapshotDiff {
+    /// Names of the registers with different values.
+    pub registers: Vec<&'static str>,
+    /// Address ranges with different contents, sorted by address. Memory captured by only one
+    /// of the snapshots counts as changed.
+    pub memory: Vec<Range<usize>>,
+}
+
+impl Snapshot {
+    /// Captures the general purpose registers and the contents of the writable memory maps, in
+    /// the order of their addresses, until `memory_limit` bytes are captured.
+    pub(super) fn capture(target: &LinuxTarget, memory_limit: usize) -> Result<Self> {
+        let registers = target.read_registers()?;
+        let mut regions = Vec::new();
+        let mut captured = 0;
+
+        for map in target.memory_maps()? {
+            if captured == memory_limit {
+                break;
+            }
+            if !map.is_writable {
+                continue;
+            }
+
+            let start = map.address.0 as usize;
+            let len = cmp::min(
+                (map.address.1 - map.address.0) as usize,
+                memory_limit - captured,
+            );
+            let mut bytes: Vec<u8> = Vec::with_capacity(len);
+            let read_len = {
+                let buf = unsafe {
+                    std::slice::from_raw_parts_mut(bytes.as_mut_ptr() as *mut MaybeUninit<u8>, len)
+                };
+                target.read().read_slice_uninit(buf, start).apply_uninit()?
+            };
+            // `apply_uninit` initialized this many bytes.
+            unsafe { bytes.set_len(read_len) };
+
+            if !bytes.is_empty() {
+                captured += bytes.len();
+                regions.push((start, bytes));
+            }
+        }
+
+        Ok(Snapshot { registers, regions })
+    }
+
+    /// Returns the captured registers.
+    pub fn registers(&self) -> &Registers {
+        &self.registers
+    }
+
+    /// Returns the start addresses and contents of the captured memory, sorted by address.
+    pub fn regions(&self) -> &[(usize, Vec<u8>)] {
+        &self.regions
+    }
+
+    /// Returns the registers and memory ranges that differ between `self` and `other`.
+    pub fn diff(&self, other: &Snapshot) -> SnapshotDiff {
+        let registers = self
+            .registers
+            .named()
+            .into_iter()
+            .zip(other.registers.named())
+            .filter(|(reg, other_reg)| reg.1 != other_reg.1)
+            .map(|(reg, _)| reg.0)
+            .collect();
+
+        let mut memory = Vec::new();
+        for (start, bytes) in &self.regions {
+            let other_bytes = match other.region_at(*start) {
+                Some(other_bytes) => other_bytes,
+                None => {
+                    memory.push(*start..start + bytes.len());
+                    continue;
+                }
+            };
+
+            let common_len = cmp::min(bytes.len(), other_bytes.len());
+            let mut changed_start = None;
+            for (offset, (byte, other_byte)) in bytes.iter().zip(other_bytes).enumerate() {
+                match (byte != other_byte, changed_start) {
+                    (true, None) => changed_start = Some(offset),
+                    (false, Some(changed)) => {
+                        memory.push(start + changed..start + offset);
+                        changed_start = None;
+                    }
+                    _ => {}
+                }
+            }
+            if let Some(changed) = changed_start {
+                memory.push(start + changed..start + common_len);
+            }
+            if bytes.len() != other_bytes.len() {
+                memory.push(start + common_len..start + cmp::max(bytes.len(), other_bytes.len()));
+            }
+        }
+        for (start, bytes) in &other.regions {
+            if self.region_at(*start).is_none() {
+                memory.push(*start..start + bytes.len());
+            }
+        }
+        memory.sort_by_key(|range| range.start);
+
+        SnapshotDiff { registers, memory }
+    }
+
+    fn region_at(&self, start: usize) -> Option<&[u8]> {
+        self.regions
+            .iter()
+            .find(|(region_start, _)| *region_start == start)
+            .map(|(_, bytes)| &bytes[..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Snapshot;
+    use crate::target::Registers;
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn diff() {
+        let before = Snapshot {
+            registers: Registers::default(),
+            regions: vec![(0x1000, vec![0; 16]), (0x2000, vec![0; 4])],
+        };
+        let mut after = before.clone();
+        after.registers = Registers {
+            rip: 2,
+            ..Registers::default()
+        };
+        after.regions[0].1[..8].copy_from_slice(&[0, 1, 1, 0, 0, 0, 0, 1]);
+        after.regions[0].1[15] = 1;
+        after.regions[1].1.truncate(2);
+        after.regions.push((0x3000, vec![0; 8]));
+
+        let diff = before.diff(&after);
+        assert_eq!(diff.registers, ["rip"]);
+        assert_eq!(
+            diff.memory,
+            [
+                0x1001..0x1003,
+                0x1007..0x1008,
+                0x100f..0x1010,
+                0x2002..0x2004,
+                0x3000..0x3008
+            ]
+        );
+        assert_eq!(after.diff(&after), Default::default());
+    }
+}
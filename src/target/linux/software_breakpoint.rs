@@ -0,0 +1,31 @@
+/// The `int3` instruction which raises `SIGTRAP` in the debuggee when executed.
+pub(super) const INT3: u8 = 0xcc;
+
+#[derive(Debug, Clone)]
+pub enum SoftwareBreakpointError {
+    DoesNotExist(usize),
+    AlreadyExists(usize),
+    UnknownId(usize),
+}
+
+impl std::fmt::Display for SoftwareBreakpointError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SoftwareBreakpointError::DoesNotExist(addr) => write!(
+                f,
+                "Software breakpoint at specified address (0x{:x}) does not exist",
+                addr
+            ),
+            SoftwareBreakpointError::AlreadyExists(addr) => write!(
+                f,
+                "Software breakpoint at specified address (0x{:x}) already exists",
+                addr
+            ),
+            SoftwareBreakpointError::UnknownId(id) => {
+                write!(f, "Software breakpoint with id {} does not exist", id)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SoftwareBreakpointError {}
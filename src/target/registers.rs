@@ -0,0 +1,445 @@
+//! General purpose registers with accessors that don't depend on the architecture.
+
+use std::fmt;
+
+/// Registers holding addresses or flags, which are shown in hexadecimal only by `Display`.
+#[cfg(target_arch = "x86_64")]
+const HEX_REGISTERS: [&str; 4] = ["rip", "rsp", "rbp", "eflags"];
+#[cfg(target_arch = "aarch64")]
+const HEX_REGISTERS: [&str; 4] = ["pc", "sp", "x29", "pstate"];
+
+/// The general purpose registers of an x86_64 thread.
+#[cfg(target_arch = "x86_64")]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Registers {
+    pub rax: u64,
+    pub rbx: u64,
+    pub rcx: u64,
+    pub rdx: u64,
+    pub rsi: u64,
+    pub rdi: u64,
+    pub rbp: u64,
+    pub rsp: u64,
+    pub r8: u64,
+    pub r9: u64,
+    pub r10: u64,
+    pub r11: u64,
+    pub r12: u64,
+    pub r13: u64,
+    pub r14: u64,
+    pub r15: u64,
+    pub rip: u64,
+    pub eflags: u64,
+}
+
+#[cfg(target_arch = "x86_64")]
+impl Registers {
+    /// Returns the program counter.
+    pub fn pc(&self) -> u64 {
+        self.rip
+    }
+
+    /// Returns the stack pointer.
+    pub fn sp(&self) -> u64 {
+        self.rsp
+    }
+
+    /// Returns the frame pointer.
+    pub fn fp(&self) -> u64 {
+        self.rbp
+    }
+
+    /// Returns the `n`th integer argument of a function following the System V calling
+    /// convention, when it's passed in a register.
+    pub fn arg(&self, n: usize) -> Option<u64> {
+        [self.rdi, self.rsi, self.rdx, self.rcx, self.r8, self.r9]
+            .get(n)
+            .copied()
+    }
+
+    /// Returns the name and value of every register.
+    pub fn named(&self) -> Vec<(&'static str, u64)> {
+        vec![
+            ("rax", self.rax),
+            ("rbx", self.rbx),
+            ("rcx", self.rcx),
+            ("rdx", self.rdx),
+            ("rsi", self.rsi),
+            ("rdi", self.rdi),
+            ("rbp", self.rbp),
+            ("rsp", self.rsp),
+            ("r8", self.r8),
+            ("r9", self.r9),
+            ("r10", self.r10),
+            ("r11", self.r11),
+            ("r12", self.r12),
+            ("r13", self.r13),
+            ("r14", self.r14),
+            ("r15", self.r15),
+            ("rip", self.rip),
+            ("eflags", self.eflags),
+        ]
+    }
+}
+
+/// The status flags of the x86_64 `RFLAGS` register, which is called `eflags` in
+/// `user_regs_struct`.
+///
+/// Bits without a field are kept as they are by `from_bits` and `bits`.
+#[cfg(target_arch = "x86_64")]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Flags {
+    /// CF
+    pub carry: bool,
+    /// PF
+    pub parity: bool,
+    /// AF
+    pub adjust: bool,
+    /// ZF
+    pub zero: bool,
+    /// SF
+    pub sign: bool,
+    /// TF, single-steps the thread when set.
+    pub trap: bool,
+    /// IF
+    pub interrupt: bool,
+    /// DF
+    pub direction: bool,
+    /// OF
+    pub overflow: bool,
+    other: u64,
+}
+
+#[cfg(target_arch = "x86_64")]
+impl Flags {
+    pub const CF: u64 = 1 << 0;
+    pub const PF: u64 = 1 << 2;
+    pub const AF: u64 = 1 << 4;
+    pub const ZF: u64 = 1 << 6;
+    pub const SF: u64 = 1 << 7;
+    pub const TF: u64 = 1 << 8;
+    pub const IF: u64 = 1 << 9;
+    pub const DF: u64 = 1 << 10;
+    pub const OF: u64 = 1 << 11;
+
+    const NAMED: u64 = Self::CF
+        | Self::PF
+        | Self::AF
+        | Self::ZF
+        | Self::SF
+        | Self::TF
+        | Self::IF
+        | Self::DF
+        | Self::OF;
+
+    /// Decodes the value of the `eflags` register.
+    pub fn from_bits(bits: u64) -> Flags {
+        Flags {
+            carry: bits & Self::CF != 0,
+            parity: bits & Self::PF != 0,
+            adjust: bits & Self::AF != 0,
+            zero: bits & Self::ZF != 0,
+            sign: bits & Self::SF != 0,
+            trap: bits & Self::TF != 0,
+            interrupt: bits & Self::IF != 0,
+            direction: bits & Self::DF != 0,
+            overflow: bits & Self::OF != 0,
+            other: bits & !Self::NAMED,
+        }
+    }
+
+    /// Encodes the flags as a value of the `eflags` register.
+    pub fn bits(&self) -> u64 {
+        [
+            (self.carry, Self::CF),
+            (self.parity, Self::PF),
+            (self.adjust, Self::AF),
+            (self.zero, Self::ZF),
+            (self.sign, Self::SF),
+            (self.trap, Self::TF),
+            (self.interrupt, Self::IF),
+            (self.direction, Self::DF),
+            (self.overflow, Self::OF),
+        ]
+        .iter()
+        .filter(|(set, _)| *set)
+        .fold(self.other, |bits, (_, bit)| bits | bit)
+    }
+}
+
+/// The segment selectors of an x86_64 thread.
+///
+/// Only `fs` and `gs` are used for addressing in 64-bit mode, by their base addresses, which
+/// aren't part of the selectors. `cs` tells whether the thread runs 64-bit or 32-bit code.
+#[cfg(target_arch = "x86_64")]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SegmentRegisters {
+    pub cs: u16,
+    pub ss: u16,
+    pub ds: u16,
+    pub es: u16,
+    pub fs: u16,
+    pub gs: u16,
+}
+
+#[cfg(target_arch = "x86_64")]
+impl SegmentRegisters {
+    /// The code segment of 64-bit user space code on Linux, `__USER_CS`.
+    pub const USER_CS: u16 = 0x33;
+    /// The code segment of 32-bit user space code on Linux, `__USER32_CS`.
+    pub const USER32_CS: u16 = 0x23;
+
+    /// Checks whether `cs` selects the code segment of 64-bit code, as opposed to that of
+    /// 32-bit compatibility mode.
+    pub fn is_64bit_mode(&self) -> bool {
+        self.cs == Self::USER_CS
+    }
+
+    /// Writes the selectors into `regs`, leaving the other registers as they are.
+    #[cfg(target_os = "linux")]
+    pub fn write_to(&self, regs: &mut libc::user_regs_struct) {
+        regs.cs = self.cs.into();
+        regs.ss = self.ss.into();
+        regs.ds = self.ds.into();
+        regs.es = self.es.into();
+        regs.fs = self.fs.into();
+        regs.gs = self.gs.into();
+    }
+}
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+impl From<libc::user_regs_struct> for SegmentRegisters {
+    fn from(regs: libc::user_regs_struct) -> Self {
+        SegmentRegisters {
+            cs: regs.cs as u16,
+            ss: regs.ss as u16,
+            ds: regs.ds as u16,
+            es: regs.es as u16,
+            fs: regs.fs as u16,
+            gs: regs.gs as u16,
+        }
+    }
+}
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+impl From<libc::user_regs_struct> for Registers {
+    fn from(regs: libc::user_regs_struct) -> Self {
+        Registers {
+            rax: regs.rax,
+            rbx: regs.rbx,
+            rcx: regs.rcx,
+            rdx: regs.rdx,
+            rsi: regs.rsi,
+            rdi: regs.rdi,
+            rbp: regs.rbp,
+            rsp: regs.rsp,
+            r8: regs.r8,
+            r9: regs.r9,
+            r10: regs.r10,
+            r11: regs.r11,
+            r12: regs.r12,
+            r13: regs.r13,
+            r14: regs.r14,
+            r15: regs.r15,
+            rip: regs.rip,
+            eflags: regs.eflags,
+        }
+    }
+}
+
+/// The general purpose registers of an aarch64 thread.
+#[cfg(target_arch = "aarch64")]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Registers {
+    /// `x0` to `x30`, where `x29` is the frame pointer and `x30` the link register.
+    pub x: [u64; 31],
+    pub sp: u64,
+    pub pc: u64,
+    pub pstate: u64,
+}
+
+#[cfg(target_arch = "aarch64")]
+impl Registers {
+    /// Returns the program counter.
+    pub fn pc(&self) -> u64 {
+        self.pc
+    }
+
+    /// Returns the stack pointer.
+    pub fn sp(&self) -> u64 {
+        self.sp
+    }
+
+    /// Returns the frame pointer.
+    pub fn fp(&self) -> u64 {
+        self.x[29]
+    }
+
+    /// Returns the `n`th integer argument of a function following the AAPCS64 calling
+    /// convention, when it's passed in a register.
+    pub fn arg(&self, n: usize) -> Option<u64> {
+        self.x[..8].get(n).copied()
+    }
+
+    /// Returns the name and value of every register.
+    pub fn named(&self) -> Vec<(&'static str, u64)> {
+        const X_NAMES: [&str; 31] = [
+            "x0", "x1", "x2", "x3", "x4", "x5", "x6", "x7", "x8", "x9", "x10", "x11", "x12", "x13",
+            "x14", "x15", "x16", "x17", "x18", "x19", "x20", "x21", "x22", "x23", "x24", "x25",
+            "x26", "x27", "x28", "x29", "x30",
+        ];
+        let mut named: Vec<_> = X_NAMES
+            .iter()
+            .copied()
+            .zip(self.x.iter().copied())
+            .collect();
+        named.push(("sp", self.sp));
+        named.push(("pc", self.pc));
+        named.push(("pstate", self.pstate));
+        named
+    }
+}
+
+#[cfg(all(target_os = "linux", target_arch = "aarch64"))]
+impl From<libc::user_regs_struct> for Registers {
+    fn from(regs: libc::user_regs_struct) -> Self {
+        Registers {
+            x: regs.regs,
+            sp: regs.sp,
+            pc: regs.pc,
+            pstate: regs.pstate,
+        }
+    }
+}
+
+/// Lists every register with its value in hexadecimal and, for registers that don't hold
+/// addresses or flags, in signed decimal, like the `info registers` command of gdb:
+///
+/// ```text
+/// rax            0x1c                28
+/// rip            0x401136            0x401136
+/// ```
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+impl fmt::Display for Registers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, value) in self.named() {
+            let hex = format!("0x{:x}", value);
+            if HEX_REGISTERS.contains(&name) {
+                writeln!(f, "{:<15}{:<20}{}", name, hex, hex)?;
+            } else {
+                writeln!(f, "{:<15}{:<20}{}", name, hex, value as i64)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::Registers;
+    #[cfg(target_arch = "x86_64")]
+    use super::{Flags, SegmentRegisters};
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn from_user_regs_struct() {
+        let mut regs: libc::user_regs_struct = unsafe { std::mem::zeroed() };
+        regs.rip = 0x1000;
+        regs.rsp = 0x7ff0;
+        regs.rbp = 0x7ff8;
+        regs.rdi = 1;
+        regs.rsi = 2;
+        regs.rdx = 3;
+        regs.rcx = 4;
+        regs.r8 = 5;
+        regs.r9 = 6;
+        regs.r10 = 7;
+
+        let registers = Registers::from(regs);
+        assert_eq!(registers.pc(), 0x1000);
+        assert_eq!(registers.sp(), 0x7ff0);
+        assert_eq!(registers.fp(), 0x7ff8);
+        let args: Vec<_> = (0..6).map(|n| registers.arg(n).unwrap()).collect();
+        assert_eq!(args, [1, 2, 3, 4, 5, 6]);
+        assert_eq!(registers.arg(6), None);
+        assert_eq!(registers.r10, 7);
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn display() {
+        let registers = Registers {
+            rax: 0x1c,
+            rcx: -1i64 as u64,
+            rip: 0x401136,
+            ..Registers::default()
+        };
+        let dump = registers.to_string();
+        let lines: Vec<_> = dump.lines().collect();
+        assert_eq!(lines.len(), registers.named().len());
+        assert_eq!(lines[0], "rax            0x1c                28");
+        assert_eq!(lines[2], "rcx            0xffffffffffffffff  -1");
+        assert_eq!(lines[16], "rip            0x401136            0x401136");
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn flags_bits() {
+        // IF, ZF, PF and the reserved bit 1, which is always set.
+        let bits = 0x246;
+        let mut flags = Flags::from_bits(bits);
+        assert!(flags.interrupt && flags.zero && flags.parity);
+        assert!(!flags.carry && !flags.sign && !flags.trap && !flags.overflow);
+        assert_eq!(flags.bits(), bits);
+
+        flags.zero = false;
+        flags.carry = true;
+        assert_eq!(flags.bits(), bits & !Flags::ZF | Flags::CF);
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn segment_registers() {
+        let mut regs: libc::user_regs_struct = unsafe { std::mem::zeroed() };
+        regs.cs = 0x33;
+        regs.ss = 0x2b;
+        regs.rax = 1;
+
+        let mut segments = SegmentRegisters::from(regs);
+        assert_eq!(
+            segments,
+            SegmentRegisters {
+                cs: 0x33,
+                ss: 0x2b,
+                ..SegmentRegisters::default()
+            }
+        );
+        assert!(segments.is_64bit_mode());
+
+        segments.cs = SegmentRegisters::USER32_CS;
+        segments.ds = 0x2b;
+        assert!(!segments.is_64bit_mode());
+        segments.write_to(&mut regs);
+        assert_eq!((regs.cs, regs.ds, regs.ss, regs.rax), (0x23, 0x2b, 0x2b, 1));
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    #[test]
+    fn from_user_regs_struct() {
+        let mut regs: libc::user_regs_struct = unsafe { std::mem::zeroed() };
+        regs.pc = 0x1000;
+        regs.sp = 0x7ff0;
+        regs.regs[29] = 0x7ff8;
+        for n in 0..8 {
+            regs.regs[n] = n as u64 + 1;
+        }
+
+        let registers = Registers::from(regs);
+        assert_eq!(registers.pc(), 0x1000);
+        assert_eq!(registers.sp(), 0x7ff0);
+        assert_eq!(registers.fp(), 0x7ff8);
+        let args: Vec<_> = (0..8).map(|n| registers.arg(n).unwrap()).collect();
+        assert_eq!(args, [1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(registers.arg(8), None);
+    }
+}
@@ -0,0 +1,40 @@
+use crate::Result;
+
+pub trait Thread {
+    type ThreadId;
+
+    fn name(&self) -> Result<Option<String>>;
+    fn thread_id(&self) -> Self::ThreadId;
+
+    /// What the thread is currently doing, or `None` if it's unknown, e.g. because the thread
+    /// has exited in the meantime.
+    fn state(&self) -> Result<Option<ThreadState>> {
+        Ok(None)
+    }
+
+    /// The CPU the thread last ran on, or `None` if it's unknown.
+    fn cpu(&self) -> Result<Option<usize>> {
+        Ok(None)
+    }
+}
+
+/// Scheduling state of a thread, like the state column shown by `top`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThreadState {
+    Running,
+    /// Waiting for an event, e.g. sleeping or blocked on a lock.
+    Sleeping,
+    /// Waiting uninterruptibly, usually for disk I/O.
+    DiskSleep,
+    /// Stopped by a signal.
+    Stopped,
+    /// Stopped by a debugger.
+    TracingStop,
+    /// Exited, but not waited for yet.
+    Zombie,
+    Dead,
+    /// An idle kernel thread.
+    Idle,
+    /// A state this version doesn't know about, with the letter used by the OS.
+    Other(char),
+}
@@ -0,0 +1,117 @@
+use crate::Result;
+use nix::{
+    sys::ptrace,
+    sys::wait::{waitpid, WaitStatus},
+    unistd::{dup2, execv, execve, fork, ForkResult, Pid},
+};
+use std::ffi::{CStr, CString};
+use std::iter;
+use std::os::unix::io::RawFd;
+use std::process;
+
+/// This trait defines the common behavior for all *nix targets
+pub trait UnixTarget {
+    /// Provides the Pid of the debugee process
+    fn pid(&self) -> Pid;
+
+    /// Step the debuggee one instruction further.
+    fn step(&self) -> Result<WaitStatus> {
+        ptrace::step(self.pid(), None)?;
+        let status = waitpid(self.pid(), None)?;
+        Ok(status)
+    }
+
+    /// Continues execution of a debuggee.
+    fn unpause(&self) -> Result<WaitStatus> {
+        ptrace::cont(self.pid(), None)?;
+        let status = waitpid(self.pid(), None)?;
+        Ok(status)
+    }
+
+    /// Detach from the debuggee, continuing its execution.
+    fn detach(&self) -> Result<()> {
+        ptrace::detach(self.pid(), None)?;
+        Ok(())
+    }
+
+    /// Kills the debuggee.
+    fn kill(&self) -> Result<WaitStatus> {
+        ptrace::kill(self.pid())?;
+        let status = waitpid(self.pid(), None)?;
+        Ok(status)
+    }
+}
+
+/// Launch a new debuggee process.
+///
+/// `args` are passed after `path`, which is used as `argv[0]`. If `env` is `None`, the debuggee
+/// inherits the environment of this process. The file descriptors in `stdio` replace stdin,
+/// stdout and stderr of the debuggee, `None` keeps the stream of this process.
+pub(in crate::target) fn launch(
+    path: CString,
+    args: &[CString],
+    env: Option<&[CString]>,
+    stdio: [Option<RawFd>; 3],
+) -> Result<(Pid, WaitStatus)> {
+    // Everything is prepared before forking, so that the child doesn't need to allocate.
+    let argv: Vec<&CStr> = iter::once(path.as_c_str())
+        .chain(args.iter().map(CString::as_c_str))
+        .collect();
+    let envp: Option<Vec<&CStr>> = env.map(|env| env.iter().map(CString::as_c_str).collect());
+
+    // We start the debuggee by forking the parent process.
+    // The child process invokes `ptrace(2)` with the `PTRACE_TRACEME` parameter to enable debugging features for the parent.
+    // This requires a user to have a `SYS_CAP_PTRACE` permission. See `man capabilities(7)` for more information.
+    match fork()? {
+        ForkResult::Parent { child, .. } => {
+            let status = waitpid(child, None)?;
+
+            Ok((child, status))
+        }
+        ForkResult::Child => {
+            if let Err(err) = ptrace::traceme() {
+                println!("ptrace traceme failed: {:?}", err);
+                process::abort()
+            }
+
+            // Disable ASLR
+            #[cfg(target_os = "linux")]
+            unsafe {
+                const ADDR_NO_RANDOMIZE: libc::c_ulong = 0x0040000;
+                libc::personality(ADDR_NO_RANDOMIZE);
+            }
+
+            for (fd, stdio) in stdio.iter().enumerate() {
+                if let Some(stdio) = stdio {
+                    if dup2(*stdio, fd as RawFd).is_err() {
+                        // stdout may be redirected already, and formatting would allocate.
+                        let msg = b"dup2 failed\n";
+                        unsafe { libc::write(2, msg.as_ptr() as *const libc::c_void, msg.len()) };
+                        process::abort();
+                    }
+                }
+            }
+
+            let res = match &envp {
+                Some(envp) => execve(&path, &argv, envp),
+                None => execv(&path, &argv),
+            };
+            // exec replaces the process image, so it only ever returns an error.
+            match res {
+                Ok(never) => match never {},
+                Err(_) => {
+                    let msg = b"exec failed\n";
+                    unsafe { libc::write(2, msg.as_ptr() as *const libc::c_void, msg.len()) };
+                    process::abort();
+                }
+            }
+        }
+    }
+}
+
+/// Attach existing process as a debugee.
+pub(in crate::target) fn attach(pid: Pid) -> Result<WaitStatus> {
+    ptrace::attach(pid)?;
+    let status = waitpid(pid, None)?;
+    Ok(status)
+}
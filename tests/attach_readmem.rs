@@ -30,8 +30,13 @@ fn attach_readmem() -> Result<(), Box<dyn std::error::Error>> {
             use std::{thread, time};
             thread::sleep(time::Duration::from_millis(50));
 
-            let (target, status) =
-                LinuxTarget::attach(child, AttachOptions { kill_on_exit: true })?;
+            let (target, status) = LinuxTarget::attach(
+                child,
+                AttachOptions {
+                    kill_on_exit: true,
+                    ..Default::default()
+                },
+            )?;
             match status {
                 nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
                 _ => panic!("Status: {:?}", status),
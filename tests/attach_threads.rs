@@ -0,0 +1,76 @@
+//! This is a test for attaching to all threads of a running process.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::{
+    symbol::RelocatedDwarf,
+    target::{AttachOptions, LinuxTarget, UnixTarget},
+};
+
+static BIN_PATH: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/testees/thread_breakpoint"
+);
+
+// FIXME: Running this test just for linux because of privileges issue on macOS. Enable for everything after fixing.
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn attach_threads() -> Result<(), Box<dyn std::error::Error>> {
+    use nix::sys::{
+        signal::{self, Signal},
+        wait::WaitStatus,
+    };
+    use nix::unistd::Pid;
+
+    test_utils::ensure_testees();
+
+    let child = std::process::Command::new(BIN_PATH).spawn()?;
+    let pid = Pid::from_raw(child.id() as i32);
+    // Wait for the main thread and the 3 spawned ones.
+    let task_dir = format!("/proc/{}/task", pid);
+    for _ in 0..500 {
+        if std::fs::read_dir(&task_dir)?.count() == 4 {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+
+    let (mut target, _status) = LinuxTarget::attach(
+        pid,
+        AttachOptions {
+            kill_on_exit: true,
+            all_threads: true,
+        },
+    )?;
+    assert_eq!(target.traced_threads().len(), 3);
+
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    let breakpoint_addr = debuginfo.get_symbol_address("thread_work").unwrap();
+    target.set_breakpoint(breakpoint_addr)?;
+
+    target.cont_all_threads()?;
+    let tid = match target.wait_any_thread()? {
+        WaitStatus::Stopped(tid, Signal::SIGTRAP) => tid,
+        status => panic!("No thread has stopped on the breakpoint: {:?}", status),
+    };
+    assert_ne!(tid, target.pid());
+    assert!(target.traced_threads().contains(&tid.as_raw()));
+    assert_eq!(
+        target.read_thread_regs(tid.as_raw())?.rip as usize,
+        breakpoint_addr + 1
+    );
+
+    // The other threads may have hit the breakpoint too before the process is gone.
+    signal::kill(pid, Signal::SIGKILL)?;
+    loop {
+        match target.wait_any_thread()? {
+            WaitStatus::Signaled(exited, Signal::SIGKILL, _) if exited == pid => break,
+            WaitStatus::Stopped(..) => {}
+            status => panic!("Unexpected status: {:?}", status),
+        }
+    }
+    assert!(target.traced_threads().is_empty());
+
+    Ok(())
+}
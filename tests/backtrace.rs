@@ -0,0 +1,59 @@
+//! This is a simple test for walking the stack of a child process.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::{symbol::RelocatedDwarf, target::UnixTarget};
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/backtrace");
+
+// FIXME: Running this test just for linux because of privileges issue on macOS. Enable for everything after fixing.
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn backtrace() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(BIN_PATH);
+
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    let breakpoint_addr = debuginfo.get_symbol_address("breakpoint").unwrap();
+    target.set_breakpoint(breakpoint_addr)?;
+
+    match target.unpause()? {
+        nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+        status => panic!("Process hasn't stopped on breakpoint: {:?}", status),
+    }
+    assert_eq!(target.handle_breakpoint_hit()?, Some(breakpoint_addr));
+
+    // Run the prologue of `breakpoint`, so it gets a frame of its own.
+    loop {
+        let regs = target.read_regs()?;
+        if regs.rbp == regs.rsp {
+            break;
+        }
+        target.step()?;
+    }
+
+    let names: Vec<_> = target
+        .backtrace()?
+        .into_iter()
+        .map(|addr| debuginfo.get_address_symbol_name(addr))
+        .collect();
+    assert_eq!(
+        names[..4],
+        [
+            Some("recurse".to_string()),
+            Some("recurse".to_string()),
+            Some("recurse".to_string()),
+            Some("recurse".to_string()),
+        ],
+        "{:?}",
+        names
+    );
+    // The walk goes all the way up to the C `main` calling into the Rust runtime.
+    assert!(names.contains(&Some("main".to_string())), "{:?}", names);
+
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
@@ -0,0 +1,173 @@
+//! This is a test for managing software breakpoints of a child process.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::{symbol::RelocatedDwarf, target::UnixTarget};
+
+static LOOP_BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/breakpoint_loop");
+
+// FIXME: Running this test just for linux because of privileges issue on macOS. Enable for everything after fixing.
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn breakpoints() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(LOOP_BIN_PATH);
+
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    let loop_body_addr = debuginfo.get_symbol_address("loop_body").unwrap();
+    let orig_byte = target.read_bytes(loop_body_addr, 1)?[0];
+
+    let id = target
+        .breakpoints()
+        .add(loop_body_addr, Some("loop_body".to_string()))?;
+    assert!(target.breakpoints().add(loop_body_addr, None).is_err());
+    assert_eq!(target.read_bytes(loop_body_addr, 1)?, [0xcc]);
+
+    // Disabling restores the original instruction, enabling puts the breakpoint back.
+    target.breakpoints().disable(id)?;
+    assert!(!target.breakpoints().get(id).unwrap().enabled);
+    assert_eq!(target.read_bytes(loop_body_addr, 1)?, [orig_byte]);
+    target.breakpoints().enable(id)?;
+    target.breakpoints().enable(id)?;
+    assert!(target.breakpoints().get(id).unwrap().enabled);
+    assert_eq!(target.read_bytes(loop_body_addr, 1)?, [0xcc]);
+
+    for hit_count in 1..=3 {
+        match target.unpause()? {
+            nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+            status => panic!(
+                "Process hasn't stopped on software breakpoint: {:?}",
+                status
+            ),
+        }
+        let breakpoint = target.breakpoints().record_hit()?.unwrap();
+        assert_eq!(breakpoint.id, id);
+        assert_eq!(breakpoint.addr, loop_body_addr);
+        assert_eq!(breakpoint.hit_count, hit_count);
+        assert_eq!(breakpoint.symbol.as_deref(), Some("loop_body"));
+        assert_eq!(target.read_regs()?.rip as usize, loop_body_addr);
+
+        target.step_over_breakpoint(loop_body_addr)?;
+    }
+
+    // A disabled breakpoint isn't hit anymore, but keeps its hit count.
+    target.breakpoints().disable(id)?;
+    assert_eq!(target.breakpoints().list().len(), 1);
+    assert_eq!(target.breakpoints().list()[0].hit_count, 3);
+
+    let removed = target.breakpoints().remove(id)?;
+    assert_eq!(removed.id, id);
+    assert!(target.breakpoints().list().is_empty());
+    assert!(target.breakpoints().enable(id).is_err());
+
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn conditional_breakpoint() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(LOOP_BIN_PATH);
+
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    let loop_body_addr = debuginfo.get_symbol_address("loop_body").unwrap();
+
+    let mut breakpoints = target.breakpoints();
+    let id = breakpoints.add(loop_body_addr, Some("loop_body".to_string()))?;
+    // `loop_body` gets the loop counter as its first argument.
+    breakpoints.set_condition(id, |target| target.read_regs().unwrap().rdi == 5)?;
+    assert!(breakpoints.get(id).unwrap().has_condition);
+
+    match breakpoints.cont()? {
+        nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+        status => panic!(
+            "Process hasn't stopped on software breakpoint: {:?}",
+            status
+        ),
+    }
+    assert_eq!(breakpoints.get(id).unwrap().hit_count, 1);
+    let regs = target.read_regs()?;
+    assert_eq!(regs.rip as usize, loop_body_addr);
+    assert_eq!(regs.rdi, 5);
+
+    // The condition doesn't hold for the remaining iterations.
+    match target.breakpoints().cont()? {
+        nix::sys::wait::WaitStatus::Exited(_, 0) => {}
+        status => panic!("Status: {:?}", status),
+    }
+    assert_eq!(target.breakpoints().get(id).unwrap().hit_count, 1);
+
+    Ok(())
+}
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn temp_breakpoint() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(LOOP_BIN_PATH);
+
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    let loop_body_addr = debuginfo.get_symbol_address("loop_body").unwrap();
+    let orig_byte = target.read_bytes(loop_body_addr, 1)?[0];
+
+    let id = target.breakpoints().set_temp_breakpoint(loop_body_addr)?;
+    assert!(target.breakpoints().get(id).unwrap().temporary);
+
+    // The first hit is reported, but removes the breakpoint.
+    match target.breakpoints().cont()? {
+        nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+        status => panic!(
+            "Process hasn't stopped on software breakpoint: {:?}",
+            status
+        ),
+    }
+    let regs = target.read_regs()?;
+    assert_eq!(regs.rip as usize, loop_body_addr);
+    assert_eq!(regs.rdi, 0);
+    assert!(target.breakpoints().list().is_empty());
+    assert_eq!(target.breakpoint_at(loop_body_addr), None);
+    assert_eq!(target.read_bytes(loop_body_addr, 1)?, [orig_byte]);
+
+    // The remaining iterations run without stopping.
+    match target.breakpoints().cont()? {
+        nix::sys::wait::WaitStatus::Exited(_, 0) => {}
+        status => panic!("Status: {:?}", status),
+    }
+
+    Ok(())
+}
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn temp_breakpoint_record_hit() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(LOOP_BIN_PATH);
+
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    let loop_body_addr = debuginfo.get_symbol_address("loop_body").unwrap();
+    let id = target.breakpoints().set_temp_breakpoint(loop_body_addr)?;
+
+    match target.unpause()? {
+        nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+        status => panic!(
+            "Process hasn't stopped on software breakpoint: {:?}",
+            status
+        ),
+    }
+    let breakpoint = target.breakpoints().record_hit()?.unwrap();
+    assert_eq!(breakpoint.id, id);
+    assert_eq!(breakpoint.hit_count, 1);
+    assert!(target.breakpoints().list().is_empty());
+    assert_eq!(target.read_regs()?.rip as usize, loop_body_addr);
+
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
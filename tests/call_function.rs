@@ -0,0 +1,90 @@
+//! This is a simple test for calling functions in a child process.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::{symbol::RelocatedDwarf, target::UnixTarget};
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/call_function");
+
+// FIXME: Running this test just for linux because of privileges issue on macOS. Enable for everything after fixing.
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn call_function() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(BIN_PATH);
+
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    let breakpoint_addr = debuginfo.get_symbol_address("breakpoint").unwrap();
+    target.set_breakpoint(breakpoint_addr)?;
+
+    match target.unpause()? {
+        nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+        status => panic!("Process hasn't stopped on breakpoint: {:?}", status),
+    }
+    assert_eq!(target.handle_breakpoint_hit()?, Some(breakpoint_addr));
+    let regs = target.read_regs()?;
+
+    let weighted_sum = debuginfo.get_symbol_address("weighted_sum").unwrap();
+    assert_eq!(
+        target.call_function(weighted_sum, &[1, 2, 3, 4, 5, 6])?,
+        1 + 2 * 2 + 3 * 3 + 4 * 4 + 5 * 5 + 6 * 6
+    );
+
+    // libc is loaded by now, so its functions can be called too.
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    let malloc = debuginfo.get_symbol_address("malloc").unwrap();
+    let free = debuginfo.get_symbol_address("free").unwrap();
+    let ptr = target.call_function(malloc, &[64])?;
+    assert_ne!(ptr, 0);
+    target.write_bytes(ptr as usize, &[42; 64])?;
+    assert_eq!(target.read_bytes(ptr as usize, 64)?, vec![42; 64]);
+    target.call_function(free, &[ptr])?;
+
+    assert!(target.call_function(weighted_sum, &[0; 7]).is_err());
+
+    let new_regs = target.read_regs()?;
+    assert_eq!(new_regs.rip, regs.rip);
+    assert_eq!(new_regs.rsp, regs.rsp);
+
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn call_injected_code() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let target = test_utils::launch(BIN_PATH);
+
+    let page_size = 4096;
+    let addr = target.mmap(
+        std::ptr::null_mut(),
+        page_size,
+        libc::PROT_READ | libc::PROT_WRITE,
+        libc::MAP_ANONYMOUS | libc::MAP_PRIVATE,
+        0,
+        0,
+    )?;
+    // mov rax, 42; ret
+    target.write_bytes(addr as usize, &[0x48, 0xc7, 0xc0, 42, 0, 0, 0, 0xc3])?;
+
+    // The page isn't executable yet.
+    assert!(target.call_function(addr as usize, &[]).is_err());
+    assert!(target
+        .mprotect((addr + 1) as *mut _, page_size, libc::PROT_READ)
+        .is_err());
+
+    assert_eq!(
+        target.mprotect(addr as *mut _, page_size, libc::PROT_READ | libc::PROT_EXEC)?,
+        0
+    );
+    assert_eq!(target.call_function(addr as usize, &[])?, 42);
+
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
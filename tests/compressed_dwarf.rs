@@ -0,0 +1,67 @@
+//! This is a test for reading the DWARF sections of binaries built with compressed debug info.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::symbol::{Dwarf, LineTable, SymbolTable};
+#[cfg(target_os = "linux")]
+use object::{elf, Object, ObjectSection, SectionFlags};
+
+static ZLIB_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/compressed");
+static ZSTD_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/compressed_zstd");
+
+#[cfg(target_os = "linux")]
+fn is_compressed(object: &object::File, name: &str) -> bool {
+    match object.section_by_name(name).map(|section| section.flags()) {
+        Some(SectionFlags::Elf { sh_flags }) => sh_flags & u64::from(elf::SHF_COMPRESSED) != 0,
+        _ => false,
+    }
+}
+
+/// Checks that the line table and the variables of the testee at `path` can be read.
+#[cfg(target_os = "linux")]
+fn check_debug_info(path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let bytes = std::fs::read(path)?;
+    let object = object::File::parse(&bytes)?;
+    assert!(is_compressed(&object, ".debug_info"));
+    assert!(is_compressed(&object, ".debug_line"));
+
+    let lines = LineTable::new(&object)?;
+    let addr = SymbolTable::new(&object)
+        .address_of("compressed_function")
+        .unwrap();
+    let (file, line) = lines.location(addr).unwrap();
+    assert!(file.ends_with("tests/testees/compressed.rs"), "{:?}", file);
+    assert_eq!(line, 5);
+
+    let debuginfo = Dwarf::new(path)?;
+    assert!(debuginfo.get_var_address("COMPRESSED_VAR")?.is_some());
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn zlib_compressed_dwarf() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+    check_debug_info(ZLIB_PATH)
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn zstd_compressed_dwarf() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+    if !std::path::Path::new(ZSTD_PATH).exists() {
+        eprintln!("Skipping the test, the linker doesn't support zstd");
+        return Ok(());
+    }
+
+    if cfg!(feature = "zstd") {
+        check_debug_info(ZSTD_PATH)
+    } else {
+        // The sections must not silently be read as empty.
+        let bytes = std::fs::read(ZSTD_PATH)?;
+        let err = LineTable::new(&object::File::parse(&bytes)?).unwrap_err();
+        assert!(err.to_string().contains("zstd"), "{}", err);
+        Ok(())
+    }
+}
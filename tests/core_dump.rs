@@ -0,0 +1,106 @@
+//! This is a simple test for writing a core file of a child process.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::{symbol::RelocatedDwarf, target::UnixTarget};
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/hello");
+
+// FIXME: Running this test just for linux because of privileges issue on macOS. Enable for everything after fixing.
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn write_core_dump() -> Result<(), Box<dyn std::error::Error>> {
+    use object::{Object, ObjectSegment};
+
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(BIN_PATH);
+
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    let breakpoint_addr = debuginfo.get_symbol_address("breakpoint").unwrap();
+    target.set_breakpoint(breakpoint_addr)?;
+    match target.unpause()? {
+        nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+        status => panic!("Status: {:?}", status),
+    }
+    target.handle_breakpoint_hit()?;
+
+    let path = std::env::temp_dir().join(format!("headcrab-core.{}", target.pid()));
+    target.write_core_dump(&path)?;
+    let data = std::fs::read(&path)?;
+    std::fs::remove_file(&path)?;
+
+    let core = object::File::parse(&data)?;
+    let readable_maps = target
+        .memory_maps()?
+        .into_iter()
+        .filter(|map| map.is_readable)
+        .count();
+    assert_eq!(core.segments().count(), readable_maps);
+
+    // The code at the breakpoint can be read back from the core file.
+    let segment = core
+        .segments()
+        .find(|segment| {
+            segment.address() <= breakpoint_addr as u64
+                && (breakpoint_addr as u64) < segment.address() + segment.size()
+        })
+        .unwrap();
+    let offset = breakpoint_addr - segment.address() as usize;
+    assert_eq!(
+        &segment.data()?[offset..offset + 16],
+        &target.read_bytes(breakpoint_addr, 16)?[..]
+    );
+
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn read_core_file() -> Result<(), Box<dyn std::error::Error>> {
+    use headcrab::target::CoreTarget;
+
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(BIN_PATH);
+
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    let breakpoint_addr = debuginfo.get_symbol_address("breakpoint").unwrap();
+    let str_addr = debuginfo
+        .get_var_address("STATICVAR")?
+        .expect("Expected static var has not been found in the target binary");
+    target.set_breakpoint(breakpoint_addr)?;
+    match target.unpause()? {
+        nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+        status => panic!("Status: {:?}", status),
+    }
+    target.handle_breakpoint_hit()?;
+
+    let path = std::env::temp_dir().join(format!("headcrab-core-read.{}", target.pid()));
+    target.write_core_dump(&path)?;
+    let core = CoreTarget::open(&path);
+    std::fs::remove_file(&path)?;
+    let core = core?;
+
+    assert_eq!(core.pid(), target.pid().as_raw());
+    assert_eq!(core.thread_ids(), [target.pid().as_raw()]);
+    let regs = target.read_regs()?;
+    assert_eq!(core.read_regs().rip, regs.rip);
+    assert_eq!(core.read_registers().sp(), regs.rsp);
+    assert_eq!(core.read_regs().rip as usize, breakpoint_addr);
+
+    // `STATICVAR` is a `&str`, so a pointer and a length.
+    let [ptr, len]: [usize; 2] = core.read_struct(str_addr)?;
+    let mut bytes = vec![0; len];
+    core.read_memory(ptr, &mut bytes)?;
+    assert_eq!(std::str::from_utf8(&bytes)?, "Hello, world!\n");
+
+    assert!(core.read_memory(0, &mut [0; 8]).is_err());
+
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
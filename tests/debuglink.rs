@@ -0,0 +1,53 @@
+//! This is a test for loading the symbols of a stripped binary from its separate debug file.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::symbol::SymbolSource;
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/stripped");
+static DEBUG_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/stripped.debug");
+
+#[cfg(target_os = "linux")]
+#[test]
+fn debuglink() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let source = SymbolSource::open(BIN_PATH.as_ref())?;
+    assert_eq!(
+        source.debug_file(),
+        Some(std::fs::canonicalize(DEBUG_PATH)?.as_path())
+    );
+    let function = source
+        .table()
+        .symbol_by_name("stripped_function")
+        .expect("symbol of the debug file is missing");
+    let (symbol, offset) = source.table().symbol_for_addr(function.address()).unwrap();
+    assert_eq!(symbol.name(), Some("stripped_function"));
+    assert_eq!(offset, 0);
+    assert!(source.table().address_of("main").is_some());
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn debuglink_crc_mismatch() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let dir = std::env::temp_dir().join(format!("headcrab-debuglink.{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let bin_path = dir.join("stripped");
+    std::fs::copy(BIN_PATH, &bin_path)?;
+    let mut debug_data = std::fs::read(DEBUG_PATH)?;
+    *debug_data.last_mut().unwrap() ^= 0xff;
+    std::fs::write(dir.join("stripped.debug"), debug_data)?;
+
+    let source = SymbolSource::open(&bin_path);
+    std::fs::remove_dir_all(&dir)?;
+    let source = source?;
+    assert_eq!(source.debug_file(), None);
+    assert_eq!(source.table().address_of("stripped_function"), None);
+
+    Ok(())
+}
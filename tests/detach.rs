@@ -0,0 +1,41 @@
+//! This is a simple test for detaching from a child process.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::{symbol::RelocatedDwarf, target::UnixTarget};
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/hello");
+
+// FIXME: Running this test just for linux because of privileges issue on macOS. Enable for everything after fixing.
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn detach() -> Result<(), Box<dyn std::error::Error>> {
+    use headcrab::target::{HardwareBreakpoint, HardwareBreakpointSize, HardwareBreakpointType};
+
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(BIN_PATH);
+
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    let breakpoint_addr = debuginfo.get_symbol_address("breakpoint").unwrap();
+    let main_addr = debuginfo.get_symbol_address("main").unwrap();
+
+    target.set_breakpoint(breakpoint_addr)?;
+    target.set_hardware_breakpoint(HardwareBreakpoint {
+        addr: main_addr,
+        typ: HardwareBreakpointType::Execute,
+        size: HardwareBreakpointSize::_1,
+    })?;
+
+    // Without breakpoints the detached debuggee runs to the end on its own instead of being
+    // killed by a `SIGTRAP` nobody handles.
+    let pid = target.pid();
+    target.detach()?;
+    match nix::sys::wait::waitpid(pid, None)? {
+        nix::sys::wait::WaitStatus::Exited(_, 0) => {}
+        status => panic!("Detached process didn't run to the end: {:?}", status),
+    }
+
+    Ok(())
+}
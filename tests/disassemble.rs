@@ -20,7 +20,7 @@ static MAC_DSYM_PATH: &str = concat!(
 fn disassemble() -> Result<(), Box<dyn std::error::Error>> {
     test_utils::ensure_testees();
 
-    let target = test_utils::launch(BIN_PATH);
+    let mut target = test_utils::launch(BIN_PATH);
     let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
 
     // First breakpoint
@@ -49,6 +49,19 @@ movq $0, %rax\n\
 retq \n"
     );
 
+    // A software breakpoint on the `nop` isn't visible in the disassembly.
+    target.set_breakpoint(ip as usize)?;
+    let insns = target.disassemble(ip as usize, 3)?;
+    assert_eq!(
+        insns.iter().map(|insn| &insn.text[..]).collect::<Vec<_>>(),
+        ["nop", "int3", "movq $0, %rax"]
+    );
+    assert_eq!(insns[0].address, ip as usize);
+    assert_eq!(insns[0].bytes, [0x90]);
+    assert_eq!(insns[1].address, ip as usize + 1);
+    assert_eq!(insns[2].len, 7);
+    target.clear_breakpoint(ip as usize)?;
+
     // Second breakpoint
     target.unpause()?;
 
@@ -0,0 +1,38 @@
+//! This is a simple test for catching an `execve` of the debuggee.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::{
+    symbol::RelocatedDwarf,
+    target::{StopEvent, UnixTarget},
+};
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/exec");
+
+// FIXME: Running this test just for linux because of privileges issue on macOS. Enable for everything after fixing.
+#[cfg(target_os = "linux")]
+#[test]
+fn exec_event() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(BIN_PATH);
+    target.set_exec_tracing(true)?;
+
+    nix::sys::ptrace::cont(target.pid(), None)?;
+    assert_eq!(target.wait()?, StopEvent::Exec);
+
+    // The debuggee runs `hello` now, so its symbols have to be loaded again.
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    let breakpoint_addr = debuginfo.get_symbol_address("breakpoint").unwrap();
+    target.set_breakpoint(breakpoint_addr)?;
+
+    nix::sys::ptrace::cont(target.pid(), None)?;
+    assert_eq!(target.wait()?, StopEvent::Signal(nix::sys::signal::SIGTRAP));
+    assert_eq!(target.handle_breakpoint_hit()?, Some(breakpoint_addr));
+
+    nix::sys::ptrace::cont(target.pid(), None)?;
+    assert_eq!(target.wait()?, StopEvent::Exited(0));
+
+    Ok(())
+}
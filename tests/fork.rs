@@ -0,0 +1,144 @@
+//! This is a simple test for following a child forked by the debuggee.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::target::{StopEvent, UnixTarget};
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/fork");
+
+// FIXME: Running this test just for linux because of privileges issue on macOS. Enable for everything after fixing.
+#[cfg(target_os = "linux")]
+#[test]
+fn follow_fork() -> Result<(), Box<dyn std::error::Error>> {
+    use nix::sys::wait::WaitStatus;
+
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(BIN_PATH);
+    target.set_fork_follow(true)?;
+
+    let status = target.unpause()?;
+    match status {
+        WaitStatus::PtraceEvent(_, nix::sys::signal::SIGTRAP, libc::PTRACE_EVENT_FORK) => {}
+        status => panic!("Process hasn't stopped on fork: {:?}", status),
+    }
+
+    let child = target
+        .forked_child(&status)?
+        .expect("Expected a target for the forked child");
+    assert_ne!(child.pid(), target.pid());
+    assert!(target.forked_child(&WaitStatus::StillAlive)?.is_none());
+
+    assert_eq!(child.unpause()?, WaitStatus::Exited(child.pid(), 0));
+    // The debuggee is notified about its child's exit before it can finish.
+    match target.unpause()? {
+        WaitStatus::Stopped(_, nix::sys::signal::SIGCHLD) => {}
+        status => panic!("Process hasn't stopped on SIGCHLD: {:?}", status),
+    }
+    assert_eq!(
+        target.cont(Some(nix::sys::signal::SIGCHLD))?,
+        WaitStatus::Exited(target.pid(), 0)
+    );
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn fork_event() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(BIN_PATH);
+    target.set_fork_follow(true)?;
+
+    nix::sys::ptrace::cont(target.pid(), None)?;
+    let child_pid = match target.wait()? {
+        StopEvent::Fork(pid) => pid,
+        event => panic!("Process hasn't stopped on fork: {:?}", event),
+    };
+
+    let mut child = target.follow_child(child_pid)?;
+    nix::sys::ptrace::cont(child.pid(), None)?;
+    assert_eq!(child.wait()?, StopEvent::Exited(0));
+
+    nix::sys::ptrace::cont(target.pid(), None)?;
+    assert_eq!(target.wait()?, StopEvent::Signal(nix::sys::signal::SIGCHLD));
+    nix::sys::ptrace::cont(target.pid(), nix::sys::signal::SIGCHLD)?;
+    assert_eq!(target.wait()?, StopEvent::Exited(0));
+
+    Ok(())
+}
+
+static THREADS_BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/multithreaded");
+
+#[cfg(target_os = "linux")]
+#[test]
+fn fork_copies_breakpoints() -> Result<(), Box<dyn std::error::Error>> {
+    use headcrab::symbol::RelocatedDwarf;
+    use nix::sys::wait::WaitStatus;
+
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(BIN_PATH);
+    target.set_fork_follow(true)?;
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    let main_addr = debuginfo.get_symbol_address("main").unwrap();
+    target.set_breakpoint(main_addr)?;
+
+    // Neither the debuggee nor its child runs `main` from the start again once it's running.
+    match target.unpause()? {
+        WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+        status => panic!("Process hasn't stopped on main: {:?}", status),
+    }
+    assert_eq!(target.rewind_breakpoint()?, Some(main_addr));
+    target.step_over_breakpoint(main_addr)?;
+
+    let status = target.unpause()?;
+    let child = target.forked_child(&status)?.unwrap();
+    assert!(child.breakpoint_at(main_addr).is_some());
+
+    assert_eq!(child.unpause()?, WaitStatus::Exited(child.pid(), 0));
+    match target.unpause()? {
+        WaitStatus::Stopped(_, nix::sys::signal::SIGCHLD) => {}
+        status => panic!("Process hasn't stopped on SIGCHLD: {:?}", status),
+    }
+    assert_eq!(
+        target.cont(Some(nix::sys::signal::SIGCHLD))?,
+        WaitStatus::Exited(target.pid(), 0)
+    );
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn thread_shares_breakpoints() -> Result<(), Box<dyn std::error::Error>> {
+    use headcrab::symbol::RelocatedDwarf;
+    use nix::sys::wait::WaitStatus;
+
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(THREADS_BIN_PATH);
+    target.set_fork_follow(true)?;
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    let breakpoint_addr = debuginfo.get_symbol_address("breakpoint").unwrap();
+    target.set_breakpoint(breakpoint_addr)?;
+
+    let status = target.unpause()?;
+    match status {
+        WaitStatus::PtraceEvent(_, nix::sys::signal::SIGTRAP, libc::PTRACE_EVENT_CLONE) => {}
+        status => panic!("Process hasn't stopped on clone: {:?}", status),
+    }
+    // The thread's memory is the debuggee's, its breakpoints are only known to the debuggee.
+    let thread = target.forked_child(&status)?.unwrap();
+    assert_eq!(thread.breakpoint_at(breakpoint_addr), None);
+    assert!(target.breakpoint_at(breakpoint_addr).is_some());
+
+    // The thread has to be reaped before the debuggee's exit is reported.
+    nix::sys::signal::kill(target.pid(), nix::sys::signal::SIGKILL)?;
+    nix::sys::wait::waitpid(thread.pid(), Some(nix::sys::wait::WaitPidFlag::__WALL))?;
+    nix::sys::wait::waitpid(target.pid(), None)?;
+
+    Ok(())
+}
@@ -0,0 +1,42 @@
+//! This is a simple test to read and write floating point registers of a child process.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::{symbol::RelocatedDwarf, target::UnixTarget};
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/float");
+
+// FIXME: Running this test just for linux because of privileges issue on macOS. Enable for everything after fixing.
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn read_write_fpregs() -> Result<(), Box<dyn std::error::Error>> {
+    use headcrab::target::{set_xmm_register, xmm_register};
+
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(BIN_PATH);
+
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    target.set_breakpoint(debuginfo.get_symbol_address("takes_double").unwrap())?;
+
+    match target.unpause()? {
+        nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+        status => panic!("Process hasn't stopped on breakpoint: {:?}", status),
+    }
+
+    // The first floating point argument is passed in XMM0.
+    let mut fpregs = target.read_fpregs()?;
+    assert_eq!(&xmm_register(&fpregs, 0)[..8], &1.5f64.to_ne_bytes());
+
+    let mut xmm0 = [0; 16];
+    xmm0[..8].copy_from_slice(&2.5f64.to_ne_bytes());
+    set_xmm_register(&mut fpregs, 0, xmm0);
+    target.write_fpregs(fpregs)?;
+    assert_eq!(xmm_register(&target.read_fpregs()?, 0), xmm0);
+
+    target.handle_breakpoint_hit()?;
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
@@ -3,9 +3,13 @@
 mod test_utils;
 
 #[cfg(target_os = "linux")]
-use headcrab::{symbol::RelocatedDwarf, target::UnixTarget};
+use headcrab::{
+    symbol::{ProcessSymbols, RelocatedDwarf},
+    target::UnixTarget,
+};
 
 static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/hw_breakpoint");
+static HELLO_BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/hello");
 
 // FIXME: Running this test just for linux because of privileges issue on macOS. Enable for everything after fixing.
 #[cfg(target_os = "linux")]
@@ -66,3 +70,211 @@ fn hardware_breakpoint() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn triggered_breakpoint() -> Result<(), Box<dyn std::error::Error>> {
+    use headcrab::target::{HardwareBreakpoint, HardwareBreakpointSize, HardwareBreakpointType};
+
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(BIN_PATH);
+
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    let var2_addr = debuginfo.get_symbol_address("STATICVAR2").unwrap();
+
+    let index = target.set_hardware_breakpoint(HardwareBreakpoint {
+        addr: var2_addr,
+        typ: HardwareBreakpointType::Write,
+        size: HardwareBreakpointSize::from_usize(std::mem::size_of::<u8>())?,
+    })?;
+
+    match target.unpause()? {
+        nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+        status => panic!(
+            "Process hasn't stopped on hardware breakpoint: {:?}",
+            status
+        ),
+    }
+    let status = target.debug_status()?;
+    assert!(status.condition_met(index));
+    assert!(!status.single_step());
+
+    let (triggered, breakpoint) = target.triggered_breakpoint()?.unwrap();
+    assert_eq!(triggered, index);
+    assert_eq!(breakpoint.addr, var2_addr);
+    assert!(!target.debug_status()?.condition_met(index));
+    assert!(target.triggered_breakpoint()?.is_none());
+
+    target.clear_all_hardware_breakpoints()?;
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn read_dr6() -> Result<(), Box<dyn std::error::Error>> {
+    use headcrab::target::{HardwareBreakpoint, HardwareBreakpointSize, HardwareBreakpointType};
+
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(BIN_PATH);
+
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    let var2_addr = debuginfo.get_symbol_address("STATICVAR2").unwrap();
+
+    // Take a slot other than the first one, so B0 being set by accident would be noticed.
+    let placeholder = target.set_hardware_breakpoint(HardwareBreakpoint {
+        addr: 0,
+        typ: HardwareBreakpointType::Execute,
+        size: HardwareBreakpointSize::_1,
+    })?;
+    let index = target.set_hardware_breakpoint(HardwareBreakpoint {
+        addr: var2_addr,
+        typ: HardwareBreakpointType::Write,
+        size: HardwareBreakpointSize::_1,
+    })?;
+    assert_eq!(index, 1);
+    assert_eq!(target.read_dr6()? & 0xf, 0);
+
+    match target.unpause()? {
+        nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+        status => panic!(
+            "Process hasn't stopped on hardware breakpoint: {:?}",
+            status
+        ),
+    }
+    let dr6 = target.read_dr6()?;
+    assert_eq!(dr6 & 0xf, 1 << index, "DR6: {:#x}", dr6);
+    // Not a single step.
+    assert_eq!(dr6 & (1 << 14), 0);
+
+    target.clear_dr6()?;
+    assert_eq!(target.read_dr6()?, 0);
+    assert!(target.is_hardware_breakpoint_triggered()?.is_none());
+
+    target.clear_hardware_breakpoint(placeholder)?;
+    target.clear_all_hardware_breakpoints()?;
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
+
+#[cfg(all(target_os = "linux", target_arch = "aarch64"))]
+#[test]
+fn aarch64_watchpoint() -> Result<(), Box<dyn std::error::Error>> {
+    use headcrab::target::{HardwareBreakpoint, HardwareBreakpointSize, HardwareBreakpointType};
+
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(BIN_PATH);
+
+    let (_, watchpoint_slots) = target.hardware_breakpoint_slots()?;
+    assert!(watchpoint_slots > 0);
+
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    let var2_addr = debuginfo.get_symbol_address("STATICVAR2").unwrap();
+
+    let index = target.set_hardware_breakpoint(HardwareBreakpoint {
+        addr: var2_addr,
+        typ: HardwareBreakpointType::Write,
+        size: HardwareBreakpointSize::from_usize(std::mem::size_of::<u8>())?,
+    })?;
+
+    match target.unpause()? {
+        nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+        status => panic!(
+            "Process hasn't stopped on hardware watchpoint: {:?}",
+            status
+        ),
+    }
+    assert_eq!(target.is_hardware_breakpoint_triggered()?, Some(index));
+
+    // Watchpoints trigger before the access on aarch64, so it has to be removed to continue.
+    target.clear_all_hardware_breakpoints()?;
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn hardware_breakpoint_at_symbol() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(HELLO_BIN_PATH);
+
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    let breakpoint_addr = debuginfo.get_symbol_address("breakpoint").unwrap();
+    let symbols = ProcessSymbols::new(&target.loaded_objects()?)?;
+
+    assert!(matches!(
+        target.set_hardware_breakpoint_at_symbol(&symbols, "no_such_symbol"),
+        Err(headcrab::Error::SymbolNotFound(_))
+    ));
+    let index = target.set_hardware_breakpoint_at_symbol(&symbols, "breakpoint")?;
+
+    match target.unpause()? {
+        nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+        status => panic!(
+            "Process hasn't stopped on hardware breakpoint: {:?}",
+            status
+        ),
+    }
+    assert_eq!(target.is_hardware_breakpoint_triggered()?, Some(index));
+    // Execute breakpoints stop before the instruction is executed.
+    assert_eq!(target.read_registers()?.pc() as usize, breakpoint_addr);
+
+    target.clear_hardware_breakpoint(index)?;
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn watch_symbol() -> Result<(), Box<dyn std::error::Error>> {
+    use headcrab::target::{HardwareBreakpointSize, HardwareBreakpointType};
+
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(BIN_PATH);
+    let symbols = ProcessSymbols::new(&target.loaded_objects()?)?;
+
+    // `STATICVAR2` is a single `u8`.
+    assert!(matches!(
+        target.watch_symbol(
+            &symbols,
+            "STATICVAR2",
+            HardwareBreakpointSize::_2,
+            HardwareBreakpointType::Write
+        ),
+        Err(headcrab::Error::SymbolTooSmall { size: 1, .. })
+    ));
+    let index = target.watch_symbol(
+        &symbols,
+        "STATICVAR2",
+        HardwareBreakpointSize::_1,
+        HardwareBreakpointType::Write,
+    )?;
+
+    match target.unpause()? {
+        nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+        status => panic!(
+            "Process hasn't stopped on hardware breakpoint: {:?}",
+            status
+        ),
+    }
+    assert_eq!(target.is_hardware_breakpoint_triggered()?, Some(index));
+
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    let var2_addr = debuginfo.get_symbol_address("STATICVAR2").unwrap();
+    // Watchpoints trigger after the write.
+    assert_eq!(target.read_bytes(var2_addr, 1)?, [200]);
+
+    target.clear_hardware_breakpoint(index)?;
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
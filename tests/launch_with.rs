@@ -0,0 +1,59 @@
+//! Tests launching a debuggee with arguments and an environment.
+
+mod test_utils;
+
+// FIXME: Running this test just for linux because of privileges issue on macOS. Enable for everything after fixing.
+#[cfg(target_os = "linux")]
+#[test]
+fn launch_with() -> Result<(), Box<dyn std::error::Error>> {
+    use headcrab::target::LinuxTarget;
+
+    test_utils::ensure_testees();
+
+    let (target, status) =
+        LinuxTarget::launch_with("/bin/echo", &["hello", "world"], &[("FOO", "bar")])?;
+    match status {
+        nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+        _ => panic!("Status: {:?}", status),
+    }
+
+    // The debuggee is stopped right after `execve`.
+    assert_eq!(target.cmdline()?, ["/bin/echo", "hello", "world"]);
+    let environ = target.environ()?;
+    assert_eq!(environ.len(), 1);
+    assert_eq!(environ["FOO"], "bar");
+
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn launch_capturing_stdout() -> Result<(), Box<dyn std::error::Error>> {
+    use headcrab::target::{LaunchOptions, LinuxTarget, Stdio};
+    use std::io::{Read, Write};
+
+    test_utils::ensure_testees();
+
+    let options = LaunchOptions {
+        stdin: Stdio::Piped,
+        stdout: Stdio::Piped,
+        ..Default::default()
+    };
+    let (target, _status, pipes) =
+        LinuxTarget::launch_with_options("/bin/cat", &[], None, options)?;
+    assert!(pipes.stderr.is_none());
+
+    let mut stdin = pipes.stdin.unwrap();
+    stdin.write_all(b"hello\n")?;
+    // Closing the pipe lets `cat` exit.
+    drop(stdin);
+    test_utils::continue_to_end(&target);
+
+    let mut output = String::new();
+    pipes.stdout.unwrap().read_to_string(&mut output)?;
+    assert_eq!(output, "hello\n");
+
+    Ok(())
+}
@@ -0,0 +1,35 @@
+//! This is a simple test for mapping addresses of a binary to source lines.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::symbol::{LineTable, SymbolTable};
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/hello");
+
+// The testees are only looked up by their ELF symbol names for now.
+#[cfg(target_os = "linux")]
+#[test]
+fn line_table() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let bytes = std::fs::read(BIN_PATH)?;
+    let object = object::File::parse(&bytes)?;
+    let lines = LineTable::new(&object)?;
+    let symbols = SymbolTable::new(&object);
+
+    let breakpoint_addr = symbols.address_of("breakpoint").unwrap();
+    let (path, line) = lines.location(breakpoint_addr).unwrap();
+    assert!(path.ends_with("tests/testees/hello.rs"), "{:?}", path);
+    assert_eq!(line, 5);
+
+    // An address inside the function maps to the row before it.
+    let (path, line) = lines.location(breakpoint_addr + 1).unwrap();
+    assert!(path.ends_with("tests/testees/hello.rs"), "{:?}", path);
+    assert!((5..=8).contains(&line), "{}", line);
+
+    // The last sequence of instructions ends before this.
+    assert_eq!(lines.location(u64::MAX), None);
+
+    Ok(())
+}
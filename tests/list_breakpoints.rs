@@ -0,0 +1,70 @@
+//! This is a test for listing the software breakpoints of a child process with their symbols.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::symbol::ProcessSymbols;
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/hello");
+
+// FIXME: Running this test just for linux because of privileges issue on macOS. Enable for everything after fixing.
+#[cfg(target_os = "linux")]
+#[test]
+fn list_breakpoints() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(BIN_PATH);
+    let symbols = ProcessSymbols::new(&target.loaded_objects()?)?;
+    let main_addr = symbols.address_of("main").unwrap() as usize;
+    let breakpoint_addr = symbols.address_of("breakpoint").unwrap() as usize;
+    let orig_byte = target.read_bytes(main_addr, 1)?[0];
+
+    target.set_breakpoint(main_addr)?;
+    let id = target
+        .breakpoints()
+        .add(breakpoint_addr + 1, Some("breakpoint".to_string()))?;
+    target.breakpoints().disable(id)?;
+
+    let list = target.list_breakpoints(Some(&symbols));
+    assert_eq!(list.len(), 2);
+    let main = list
+        .iter()
+        .find(|breakpoint| breakpoint.addr == main_addr)
+        .unwrap();
+    assert_eq!(main.symbol, Some(("main".to_string(), 0)));
+    assert_eq!(main.orig_byte, Some(orig_byte));
+    assert!(main.enabled);
+    assert_eq!(main.id, None);
+    assert!(!main.temporary);
+
+    let disabled = list
+        .iter()
+        .find(|breakpoint| breakpoint.addr == breakpoint_addr + 1)
+        .unwrap();
+    assert_eq!(disabled.symbol, Some(("breakpoint".to_string(), 1)));
+    assert_eq!(disabled.orig_byte, None);
+    assert!(!disabled.enabled);
+    assert_eq!(disabled.id, Some(id));
+    assert!(!disabled.temporary);
+
+    // Without symbols only the breakpoints themselves are known.
+    let list = target.list_breakpoints(None);
+    assert_eq!(list.len(), 2);
+    assert!(list.iter().all(|breakpoint| breakpoint.symbol.is_none()));
+
+    target.clear_breakpoint(main_addr)?;
+    assert_eq!(target.list_breakpoints(None).len(), 1);
+
+    let temp_id = target.breakpoints().set_temp_breakpoint(main_addr)?;
+    let list = target.list_breakpoints(None);
+    let temp = list
+        .iter()
+        .find(|breakpoint| breakpoint.id == Some(temp_id))
+        .unwrap();
+    assert!(temp.temporary);
+    target.breakpoints().remove(temp_id)?;
+
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
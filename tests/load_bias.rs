@@ -0,0 +1,42 @@
+//! This is a simple test for translating link-time addresses of a PIE executable.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::symbol::{RelocatedDwarf, SymbolTable};
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/hello");
+
+// FIXME: Running this test just for linux because of privileges issue on macOS. Enable for everything after fixing.
+#[cfg(target_os = "linux")]
+#[test]
+fn load_bias() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let target = test_utils::launch(BIN_PATH);
+
+    let data = std::fs::read(BIN_PATH)?;
+    let object = object::File::parse(&data)?;
+    let symbols = SymbolTable::new(&object);
+    let link_addr = symbols.address_of("breakpoint").unwrap() as usize;
+
+    // The testees are position independent, so they never run at their link-time addresses.
+    let bias = target.load_bias(std::path::Path::new(BIN_PATH))?.unwrap();
+    assert_ne!(bias, 0);
+
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    assert_eq!(
+        Some(link_addr + bias),
+        debuginfo.get_symbol_address("breakpoint")
+    );
+
+    let auxv = target.auxv()?;
+    assert_eq!(
+        auxv[&libc::AT_ENTRY],
+        object::Object::entry(&object) + bias as u64
+    );
+
+    assert_eq!(target.load_bias(std::path::Path::new("/"))?, None);
+
+    Ok(())
+}
@@ -0,0 +1,115 @@
+//! This is a simple test for listing the objects loaded into a child process.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::{symbol::RelocatedDwarf, target::UnixTarget};
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/hello");
+
+// FIXME: Running this test just for linux because of privileges issue on macOS. Enable for everything after fixing.
+#[cfg(target_os = "linux")]
+#[test]
+fn loaded_objects() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(BIN_PATH);
+
+    // Shared libraries are only loaded by the dynamic linker after the debuggee started.
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    let breakpoint_addr = debuginfo.get_symbol_address("breakpoint").unwrap();
+    target.set_breakpoint(breakpoint_addr)?;
+    match target.unpause()? {
+        nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+        status => panic!(
+            "Process hasn't stopped on software breakpoint: {:?}",
+            status
+        ),
+    }
+    assert_eq!(target.handle_breakpoint_hit()?, Some(breakpoint_addr));
+
+    let objects = target.loaded_objects()?;
+    let libc = objects
+        .iter()
+        .find(|object| {
+            let name = object.path.file_name().unwrap_or_default();
+            name.to_string_lossy().starts_with("libc")
+        })
+        .unwrap_or_else(|| panic!("libc isn't loaded: {:?}", objects));
+    assert!(libc.is_executable);
+
+    // Every file is listed once.
+    for (i, object) in objects.iter().enumerate() {
+        assert!(objects[i + 1..]
+            .iter()
+            .all(|other| other.path != object.path));
+    }
+
+    // The executable is mapped starting with its first segment at link-time address 0.
+    let bin_path = std::fs::canonicalize(BIN_PATH)?;
+    let bin = objects
+        .iter()
+        .find(|object| object.path == bin_path)
+        .unwrap();
+    assert!(bin.is_executable);
+    assert_eq!(
+        Some(bin.base as usize),
+        target.load_bias(std::path::Path::new(BIN_PATH))?
+    );
+
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
+
+// FIXME: Running this test just for linux because of privileges issue on macOS. Enable for everything after fixing.
+#[cfg(target_os = "linux")]
+#[test]
+fn vdso() -> Result<(), Box<dyn std::error::Error>> {
+    use headcrab::symbol::ProcessSymbols;
+
+    test_utils::ensure_testees();
+
+    let target = test_utils::launch(BIN_PATH);
+
+    let vdso = target.vdso()?.expect("No vDSO");
+    assert_eq!(vdso.path, std::path::Path::new("[vdso]"));
+    let maps = std::fs::read_to_string(format!("/proc/{}/maps", target.pid()))?;
+    let (start, end) = maps
+        .lines()
+        .find(|line| line.ends_with("[vdso]"))
+        .and_then(|line| line.split_whitespace().next())
+        .and_then(|range| {
+            let mut range = range.split('-');
+            let start = u64::from_str_radix(range.next()?, 16).ok()?;
+            let end = u64::from_str_radix(range.next()?, 16).ok()?;
+            Some((start, end))
+        })
+        .unwrap_or_else(|| panic!("No [vdso] mapping: {}", maps));
+    assert_eq!(vdso.base, start);
+
+    let image = target.read_vdso_image()?.unwrap();
+    assert_eq!(&image[..4], b"\x7fELF");
+    assert!(image.len() as u64 <= end - start);
+
+    let mut symbols = ProcessSymbols::new(&target.loaded_objects()?)?;
+    symbols.add_image(&vdso, image)?;
+    let clock_gettime = symbols.address_of("__vdso_clock_gettime").unwrap();
+    assert!(vdso.base <= clock_gettime && clock_gettime < end);
+    let (module, symbol, offset) = symbols.resolve(clock_gettime + 1).unwrap();
+    assert_eq!(module, vdso.path);
+    // `clock_gettime` is an alias.
+    assert!(symbol.name().unwrap().ends_with("clock_gettime"));
+    assert_eq!(offset, 1);
+
+    // Objects added from memory survive updates.
+    symbols.update(&target.loaded_objects()?)?;
+    assert_eq!(
+        symbols.address_of("__vdso_clock_gettime"),
+        Some(clock_gettime)
+    );
+
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
@@ -0,0 +1,146 @@
+//! This is a simple test for resolving addresses in all objects loaded into a child process.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::{
+    symbol::{ProcessSymbols, RelocatedDwarf},
+    target::UnixTarget,
+};
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/hello");
+
+// FIXME: Running this test just for linux because of privileges issue on macOS. Enable for everything after fixing.
+#[cfg(target_os = "linux")]
+#[test]
+fn process_symbols() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(BIN_PATH);
+
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    let breakpoint_addr = debuginfo.get_symbol_address("breakpoint").unwrap();
+
+    // Only the executable and the dynamic linker are loaded yet.
+    let mut symbols = ProcessSymbols::new(&target.loaded_objects()?)?;
+    assert_eq!(symbols.address_of("printf"), None);
+
+    target.set_breakpoint(breakpoint_addr)?;
+    match target.unpause()? {
+        nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+        status => panic!(
+            "Process hasn't stopped on software breakpoint: {:?}",
+            status
+        ),
+    }
+    assert_eq!(target.handle_breakpoint_hit()?, Some(breakpoint_addr));
+    symbols.update(&target.loaded_objects()?)?;
+
+    let (module, symbol, offset) = symbols.resolve(breakpoint_addr as u64 + 1).unwrap();
+    assert_eq!(module, std::fs::canonicalize(BIN_PATH)?);
+    assert_eq!(symbol.name(), Some("breakpoint"));
+    assert_eq!(offset, 1);
+
+    let printf_addr = symbols.address_of("printf").unwrap();
+    let (module, symbol, offset) = symbols.resolve(printf_addr).unwrap();
+    assert!(
+        module
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .starts_with("libc"),
+        "{}",
+        module.display()
+    );
+    // glibc has aliases of `printf`, like `_IO_printf`.
+    assert!(symbol.name().unwrap().ends_with("printf"));
+    assert_eq!(offset, 0);
+    assert!(
+        target
+            .map_containing(printf_addr as usize)?
+            .unwrap()
+            .is_executable
+    );
+
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn current_location() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(BIN_PATH);
+
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    let breakpoint_addr = debuginfo.get_symbol_address("breakpoint").unwrap();
+
+    target.set_breakpoint(breakpoint_addr)?;
+    match target.unpause()? {
+        nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+        status => panic!(
+            "Process hasn't stopped on software breakpoint: {:?}",
+            status
+        ),
+    }
+    assert_eq!(target.handle_breakpoint_hit()?, Some(breakpoint_addr));
+    let symbols = ProcessSymbols::new(&target.loaded_objects()?)?;
+
+    let location = target.current_location(&symbols, Some(&debuginfo))?;
+    assert_eq!(location.pc, breakpoint_addr);
+    assert_eq!(location.symbol, Some(("breakpoint".to_string(), 0)));
+    let (file, line) = location.source.clone().unwrap();
+    assert!(file.ends_with("hello.rs"), "{}", file);
+    // The body of `breakpoint`
+    assert!((5..=8).contains(&line), "{}", line);
+    assert_eq!(
+        location.to_string(),
+        format!(
+            "0x{:x} in breakpoint+0 at {}:{}",
+            breakpoint_addr, file, line
+        )
+    );
+
+    // Without debug info only the symbol is known.
+    let location = target.current_location(&symbols, None)?;
+    assert_eq!(location.source, None);
+    assert_eq!(
+        location.to_string(),
+        format!("0x{:x} in breakpoint+0", breakpoint_addr)
+    );
+
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn skips_unparsable_objects() -> Result<(), Box<dyn std::error::Error>> {
+    use headcrab::target::LoadedObject;
+
+    test_utils::ensure_testees();
+
+    let object = |path: &str, base| LoadedObject {
+        path: path.into(),
+        base,
+        is_executable: true,
+    };
+    // Files that aren't objects get mapped too, e.g. locale archives or fonts.
+    let not_elf = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/hello.rs");
+    let objects = [object(not_elf, 0x1000), object(BIN_PATH, 0x10000)];
+
+    let mut symbols = ProcessSymbols::new(&objects)?;
+    assert!(symbols.address_of("breakpoint").is_some());
+    let skipped: Vec<_> = symbols.skipped().iter().map(|(path, _)| path).collect();
+    assert_eq!(skipped, [&std::path::PathBuf::from(not_elf)]);
+    assert!(matches!(symbols.skipped()[0].1, headcrab::Error::Object(_)));
+
+    symbols.update(&objects[1..])?;
+    assert!(symbols.address_of("breakpoint").is_some());
+    assert!(symbols.skipped().is_empty());
+
+    Ok(())
+}
@@ -54,3 +54,45 @@ fn read_regs() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn register_by_name() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let target = test_utils::launch(BIN_PATH);
+
+    target.set_register_by_name("rdi", 0x1234_5678)?;
+    assert_eq!(target.register_by_name("rdi")?, Some(0x1234_5678));
+    assert_eq!(target.read_regs()?.rdi, 0x1234_5678);
+
+    let rip = target.read_regs()?.rip;
+    assert_eq!(target.register_by_name("rip")?, Some(rip));
+    assert_eq!(target.register_by_name("xyz")?, None);
+    assert!(matches!(
+        target.set_register_by_name("xyz", 0),
+        Err(headcrab::Error::UnknownRegister(_))
+    ));
+
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn write_flags() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let target = test_utils::launch(BIN_PATH);
+
+    let mut flags = target.read_flags()?;
+    flags.zero = true;
+    target.write_flags(flags)?;
+    assert_eq!(target.read_flags()?, flags);
+    assert_ne!(target.read_regs()?.eflags & headcrab::target::Flags::ZF, 0);
+
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
@@ -0,0 +1,63 @@
+//! This is a test for reading the registers of a 32-bit child process.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::target::UnixTarget;
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/i386");
+static HELLO_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/hello");
+
+// FIXME: Running this test just for linux because of privileges issue on macOS. Enable for everything after fixing.
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn read_regs32() -> Result<(), Box<dyn std::error::Error>> {
+    use headcrab::target::SegmentRegisters;
+    use nix::sys::{signal::SIGTRAP, wait::WaitStatus};
+
+    test_utils::ensure_testees();
+    if !std::path::Path::new(BIN_PATH).exists() {
+        eprintln!("Skipping the test, the 32-bit testee couldn't be built");
+        return Ok(());
+    }
+
+    let target = test_utils::launch(BIN_PATH);
+    assert!(target.is_32bit_executable()?);
+    assert!(!target.is_64bit_mode()?);
+    assert_eq!(
+        target.read_segment_registers()?.cs,
+        SegmentRegisters::USER32_CS
+    );
+
+    match target.unpause()? {
+        WaitStatus::Stopped(_, SIGTRAP) => {}
+        status => panic!("Unexpected status {:?}", status),
+    }
+    let regs = target.read_regs32()?;
+    assert_eq!(regs.ebx, 7);
+    assert_eq!(regs.ecx, 0x1122_3344);
+    assert_eq!(regs.xcs, SegmentRegisters::USER32_CS as u32);
+    // The 64-bit view of the same registers.
+    let regs64 = target.read_regs()?;
+    assert_eq!(regs64.rip, regs.eip as u64);
+    assert_eq!(regs64.rsp, regs.esp as u64);
+
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn read_regs32_of_64bit_debuggee() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let target = test_utils::launch(HELLO_PATH);
+    assert!(!target.is_32bit_executable()?);
+    assert!(target.is_64bit_mode()?);
+    assert!(target.read_regs32().is_err());
+
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
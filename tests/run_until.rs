@@ -0,0 +1,43 @@
+//! This is a test for running a child process until it reaches an address.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::symbol::RelocatedDwarf;
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/run_until");
+
+// FIXME: Running this test just for linux because of privileges issue on macOS. Enable for everything after fixing.
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn run_until() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(BIN_PATH);
+
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    let loop_addr = debuginfo.get_symbol_address("loop_start").unwrap();
+    let after_loop_addr = debuginfo.get_symbol_address("after_loop").unwrap();
+
+    // `loop_start` is reached on every iteration, the first one is the one stopped on.
+    match target.run_until(loop_addr)? {
+        nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+        status => panic!("Unexpected status after run until: {:?}", status),
+    }
+    let regs = target.read_regs()?;
+    assert_eq!(regs.rip as usize, loop_addr);
+    assert_eq!(regs.rcx, 0);
+
+    match target.run_until(after_loop_addr)? {
+        nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+        status => panic!("Unexpected status after run until: {:?}", status),
+    }
+    let regs = target.read_regs()?;
+    assert_eq!(regs.rip as usize, after_loop_addr);
+    assert_eq!(regs.rcx, 10);
+
+    // The temporary breakpoints are gone, so the debuggee should run to the end.
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
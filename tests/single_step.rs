@@ -0,0 +1,50 @@
+//! This is a test for single-stepping a child process with the trap flag.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::target::UnixTarget;
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/known_asm");
+
+// FIXME: Running this test just for linux because of privileges issue on macOS. Enable for everything after fixing.
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn set_single_step() -> Result<(), Box<dyn std::error::Error>> {
+    use nix::sys::{signal::SIGTRAP, wait::WaitStatus};
+
+    test_utils::ensure_testees();
+
+    let target = test_utils::launch(BIN_PATH);
+    let pid = target.pid();
+
+    // Stopped right after the first `int $3`, at the `nop`.
+    match target.unpause()? {
+        WaitStatus::Stopped(_, SIGTRAP) => {}
+        status => panic!("Process hasn't stopped on int3: {:?}", status),
+    }
+    let nop_addr = target.read_regs()?.rip;
+
+    target.set_single_step(pid.as_raw(), true)?;
+    assert!(target.read_flags()?.trap);
+    match target.unpause()? {
+        WaitStatus::Stopped(_, SIGTRAP) => {}
+        status => panic!("Process hasn't stopped after a single step: {:?}", status),
+    }
+    // `nop` is 1 byte long.
+    assert_eq!(target.read_regs()?.rip, nop_addr + 1);
+    assert!(target.read_flags()?.trap);
+
+    target.set_single_step(pid.as_raw(), false)?;
+    assert!(!target.read_flags()?.trap);
+    // Second `int $3`
+    match target.unpause()? {
+        WaitStatus::Stopped(_, SIGTRAP) => {}
+        status => panic!("Process hasn't stopped on int3: {:?}", status),
+    }
+    assert_eq!(target.read_regs()?.rip, nop_addr + 2);
+
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
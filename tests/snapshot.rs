@@ -0,0 +1,42 @@
+//! This is a simple test for capturing what a single instruction changed.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::{symbol::RelocatedDwarf, target::UnixTarget};
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/snapshot");
+
+// FIXME: Running this test just for linux because of privileges issue on macOS. Enable for everything after fixing.
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn snapshot_diff() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let target = test_utils::launch(BIN_PATH);
+
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    let value_addr = debuginfo.get_symbol_address("value").unwrap();
+
+    match target.unpause()? {
+        nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+        status => panic!("Status: {:?}", status),
+    }
+
+    // Step over the `mov %rsi, (%rdi)`.
+    let before = target.snapshot(usize::MAX)?;
+    target.step()?;
+    let after = target.snapshot(usize::MAX)?;
+
+    let diff = before.diff(&after);
+    assert_eq!(diff.registers, ["rip"]);
+    assert_eq!(diff.memory.len(), 1);
+    assert_eq!(diff.memory[0], value_addr..value_addr + 8);
+
+    // Nothing is captured beyond the limit.
+    let limited = target.snapshot(16)?;
+    let captured: usize = limited.regions().iter().map(|(_, bytes)| bytes.len()).sum();
+    assert_eq!(captured, 16);
+
+    Ok(())
+}
@@ -0,0 +1,154 @@
+//! This is a simple test for setting a software breakpoint in a child process.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::{symbol::RelocatedDwarf, target::UnixTarget};
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/hello");
+static LOOP_BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/breakpoint_loop");
+
+// FIXME: Running this test just for linux because of privileges issue on macOS. Enable for everything after fixing.
+#[cfg(target_os = "linux")]
+#[test]
+fn software_breakpoint() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(BIN_PATH);
+
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    let breakpoint_addr = debuginfo.get_symbol_address("breakpoint").unwrap();
+    assert!(
+        target
+            .map_containing(breakpoint_addr)?
+            .unwrap()
+            .is_executable
+    );
+    assert!(target
+        .executable_maps()?
+        .iter()
+        .any(|map| map.contains(breakpoint_addr)));
+
+    target.set_breakpoint(breakpoint_addr)?;
+
+    match target.unpause()? {
+        nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+        status => panic!(
+            "Process hasn't stopped on software breakpoint: {:?}",
+            status
+        ),
+    }
+    assert_eq!(target.handle_breakpoint_hit()?, Some(breakpoint_addr));
+    assert_eq!(target.read_regs()?.rip as usize, breakpoint_addr);
+    assert_eq!(target.read_registers()?.pc() as usize, breakpoint_addr);
+
+    // The breakpoint is gone now, so the debuggee should run to the end.
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn step_over_breakpoint() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(LOOP_BIN_PATH);
+
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    let loop_body_addr = debuginfo.get_symbol_address("loop_body").unwrap();
+
+    target.set_breakpoint(loop_body_addr)?;
+
+    for _ in 0..3 {
+        match target.unpause()? {
+            nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+            status => panic!(
+                "Process hasn't stopped on software breakpoint: {:?}",
+                status
+            ),
+        }
+        assert_eq!(target.rewind_breakpoint()?, Some(loop_body_addr));
+
+        match target.step_over_breakpoint(loop_body_addr)? {
+            nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+            status => panic!("Unexpected status after step: {:?}", status),
+        }
+        assert_ne!(target.read_regs()?.rip as usize, loop_body_addr);
+    }
+
+    target.clear_breakpoint(loop_body_addr)?;
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn wait_status() -> Result<(), Box<dyn std::error::Error>> {
+    use headcrab::target::TargetStatus;
+
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(BIN_PATH);
+
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    let breakpoint_addr = debuginfo.get_symbol_address("breakpoint").unwrap();
+    target.set_breakpoint(breakpoint_addr)?;
+
+    nix::sys::ptrace::cont(target.pid(), None)?;
+    assert_eq!(target.wait_status()?, TargetStatus::Breakpoint);
+    assert_eq!(target.handle_breakpoint_hit()?, Some(breakpoint_addr));
+
+    nix::sys::ptrace::step(target.pid(), None)?;
+    assert_eq!(target.wait_status()?, TargetStatus::SingleStep);
+
+    nix::sys::ptrace::cont(target.pid(), None)?;
+    assert_eq!(target.wait_status()?, TargetStatus::Exited(0));
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn hide_breakpoints() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(BIN_PATH);
+
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    let breakpoint_addr = debuginfo.get_symbol_address("breakpoint").unwrap();
+    let start = breakpoint_addr - 3;
+    let original = target.read_bytes(start, 16)?;
+
+    // Breakpoints in the middle, at the first and at the last byte of the read ranges.
+    for &addr in &[breakpoint_addr, start, start + 15] {
+        target.set_breakpoint(addr)?;
+    }
+    let patched = target.read_bytes(start, 16)?;
+    assert_ne!(patched, original);
+
+    let mut word = 0u64;
+    let mut bytes = [0u8; 8];
+    unsafe {
+        target
+            .read()
+            .hide_breakpoints()
+            .read(&mut word, start)
+            .read_slice(&mut bytes, start + 8)
+            .apply()?;
+    }
+    assert_eq!(&word.to_ne_bytes()[..], &original[..8]);
+    assert_eq!(&bytes[..], &original[8..]);
+
+    // The breakpoints are still there.
+    assert_eq!(target.read_bytes(start, 16)?, patched);
+    for &addr in &[breakpoint_addr, start, start + 15] {
+        target.clear_breakpoint(addr)?;
+    }
+    assert_eq!(target.read_bytes(start, 16)?, original);
+
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
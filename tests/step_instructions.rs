@@ -0,0 +1,51 @@
+//! This is a test for single-stepping a child process a bounded number of instructions.
+
+mod test_utils;
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/run_until");
+
+// FIXME: Running this test just for linux because of privileges issue on macOS. Enable for everything after fixing.
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn step_instructions() -> Result<(), Box<dyn std::error::Error>> {
+    use headcrab::symbol::RelocatedDwarf;
+    use nix::sys::{signal::SIGTRAP, wait::WaitStatus};
+
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(BIN_PATH);
+
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    let main_addr = debuginfo.get_symbol_address("main").unwrap();
+    let loop_addr = debuginfo.get_symbol_address("loop_start").unwrap();
+    target.run_until(main_addr)?;
+
+    assert_eq!(target.step_instructions(0)?, (0, WaitStatus::StillAlive));
+
+    // `mov $0, %rcx`, `inc %rcx` and `cmp $10, %rcx`
+    match target.step_instructions(3)? {
+        (3, WaitStatus::Stopped(_, SIGTRAP)) => {}
+        result => panic!("Unexpected result of stepping: {:?}", result),
+    }
+    assert_eq!(target.read_regs()?.rcx, 1);
+
+    // The `jne` back to `loop_start` reaches the breakpoint.
+    target.set_breakpoint(loop_addr)?;
+    match target.step_instructions(10)? {
+        (1, WaitStatus::Stopped(_, SIGTRAP)) => {}
+        result => panic!("Unexpected result of stepping: {:?}", result),
+    }
+    assert_eq!(target.read_regs()?.rip as usize, loop_addr);
+
+    // The breakpoint the debuggee is stopped at is stepped over.
+    match target.step_instructions(2)? {
+        (2, WaitStatus::Stopped(_, SIGTRAP)) => {}
+        result => panic!("Unexpected result of stepping: {:?}", result),
+    }
+    assert_eq!(target.read_regs()?.rcx, 2);
+
+    target.clear_breakpoint(loop_addr)?;
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
@@ -0,0 +1,72 @@
+//! This is a test for stepping over source lines of a child process.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::{symbol::RelocatedDwarf, target::UnixTarget};
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/step_line");
+
+// FIXME: Running this test just for linux because of privileges issue on macOS. Enable for everything after fixing.
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn step_line() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(BIN_PATH);
+
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    let lines_addr = debuginfo.get_symbol_address("lines").unwrap();
+    let nested_addr = debuginfo.get_symbol_address("nested").unwrap();
+
+    target.set_breakpoint(lines_addr)?;
+    match target.unpause()? {
+        nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+        status => panic!(
+            "Process hasn't stopped on software breakpoint: {:?}",
+            status
+        ),
+    }
+    assert_eq!(target.rewind_breakpoint()?, Some(lines_addr));
+
+    let step = |target: &mut headcrab::target::LinuxTarget| {
+        match target.step_line(&debuginfo).unwrap() {
+            nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+            status => panic!("Unexpected status after step: {:?}", status),
+        }
+        let rip = target.read_regs().unwrap().rip as usize;
+        let (file, line, _column) = debuginfo.source_location(rip).unwrap().unwrap();
+        assert!(file.ends_with("step_line.rs"), "{}", file);
+        line
+    };
+
+    // `black_box(1);`, then `nested(2);`
+    assert_eq!(step(&mut target), 18);
+    assert_eq!(step(&mut target), 19);
+
+    // A breakpoint inside the called function ends the step.
+    target.set_breakpoint(nested_addr)?;
+    step(&mut target);
+    assert_eq!(target.read_regs()?.rip as usize, nested_addr);
+    target.clear_breakpoint(nested_addr)?;
+
+    // Through `nested`, whose last call is a tail call, and back into `lines`.
+    assert_eq!(step(&mut target), 11);
+    assert_eq!(step(&mut target), 12);
+    assert_eq!(step(&mut target), 20);
+    assert_eq!(
+        debuginfo
+            .get_address_symbol_name(target.read_regs()?.rip as usize)
+            .as_deref(),
+        Some("lines")
+    );
+
+    // Returning from `lines`, again through a tail call, stops in `main`.
+    assert_eq!(step(&mut target), 21);
+    assert_eq!(step(&mut target), 26);
+
+    target.clear_breakpoint(lines_addr)?;
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
@@ -0,0 +1,177 @@
+//! This is a test for running a child process until the current function returns.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::{
+    symbol::RelocatedDwarf,
+    target::{LinuxTarget, UnixTarget},
+};
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/step_line");
+
+/// Stops at the start of `nested`, runs `prepare` and steps out of `nested`, checking that the
+/// debuggee is stopped right after the call in `lines`.
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+fn step_out_of_nested(
+    prepare: impl FnOnce(&mut LinuxTarget, &RelocatedDwarf),
+) -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(BIN_PATH);
+
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    let nested_addr = debuginfo.get_symbol_address("nested").unwrap();
+
+    target.set_breakpoint(nested_addr)?;
+    match target.unpause()? {
+        nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+        status => panic!(
+            "Process hasn't stopped on software breakpoint: {:?}",
+            status
+        ),
+    }
+    assert_eq!(target.rewind_breakpoint()?, Some(nested_addr));
+    let mut return_addr = 0usize;
+    unsafe {
+        target
+            .read()
+            .read(&mut return_addr, target.read_regs()?.rsp as usize)
+            .apply()?;
+    }
+
+    prepare(&mut target, &debuginfo);
+
+    match target.step_out()? {
+        nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+        status => panic!("Unexpected status after step out: {:?}", status),
+    }
+    let rip = target.read_regs()?.rip as usize;
+    assert_eq!(rip, return_addr);
+    assert_eq!(
+        debuginfo.get_address_symbol_name(rip).as_deref(),
+        Some("lines")
+    );
+    // `call nested` is 5 bytes long.
+    let call = target.disassemble(rip - 5, 1)?;
+    assert_eq!(call[0].text, format!("callq 0x{:x}", nested_addr));
+
+    target.clear_breakpoint(nested_addr)?;
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
+
+// FIXME: Running this test just for linux because of privileges issue on macOS. Enable for everything after fixing.
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn step_out_at_function_start() -> Result<(), Box<dyn std::error::Error>> {
+    step_out_of_nested(|_, _| {})
+}
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn step_out_of_function_body() -> Result<(), Box<dyn std::error::Error>> {
+    step_out_of_nested(|target, debuginfo| {
+        target.step_line(debuginfo).unwrap();
+        let rip = target.read_regs().unwrap().rip as usize;
+        assert_eq!(debuginfo.source_location(rip).unwrap().unwrap().1, 11);
+    })
+}
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn step_out_at_endbr64() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/testees/cf_protection"
+    ));
+
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    let twice_addr = debuginfo.get_symbol_address("twice").unwrap();
+
+    target.set_breakpoint(twice_addr)?;
+    match target.unpause()? {
+        nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+        status => panic!(
+            "Process hasn't stopped on software breakpoint: {:?}",
+            status
+        ),
+    }
+    assert_eq!(target.rewind_breakpoint()?, Some(twice_addr));
+    assert_eq!(target.disassemble(twice_addr, 1)?[0].text, "endbr64");
+    let mut return_addr = 0usize;
+    unsafe {
+        target
+            .read()
+            .read(&mut return_addr, target.read_regs()?.rsp as usize)
+            .apply()?;
+    }
+
+    match target.step_out()? {
+        nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+        status => panic!("Unexpected status after step out: {:?}", status),
+    }
+    let rip = target.read_regs()?.rip as usize;
+    assert_eq!(rip, return_addr);
+    assert_eq!(
+        debuginfo.get_address_symbol_name(rip).as_deref(),
+        Some("main")
+    );
+
+    target.clear_breakpoint(twice_addr)?;
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn step_out_at_ret_forms() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/testees/ret_forms"
+    ));
+
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    // Each function is just the `ret`, so the frame pointer still belongs to `main`.
+    for (function, bytes) in &[
+        ("rep_ret", &[0xf3, 0xc3][..]),
+        ("bnd_ret", &[0xf2, 0xc3][..]),
+        ("ret_imm", &[0xc2, 0x08, 0x00][..]),
+    ] {
+        let addr = debuginfo.get_symbol_address(function).unwrap();
+        target.set_breakpoint(addr)?;
+        match target.unpause()? {
+            nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+            status => panic!(
+                "Process hasn't stopped on software breakpoint: {:?}",
+                status
+            ),
+        }
+        assert_eq!(target.rewind_breakpoint()?, Some(addr));
+        assert_eq!(target.disassemble(addr, 1)?[0].bytes, *bytes);
+        let mut return_addr = 0usize;
+        unsafe {
+            target
+                .read()
+                .read(&mut return_addr, target.read_regs()?.rsp as usize)
+                .apply()?;
+        }
+
+        match target.step_out()? {
+            nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+            status => panic!("Unexpected status after step out: {:?}", status),
+        }
+        assert_eq!(target.read_regs()?.rip as usize, return_addr);
+        target.clear_breakpoint(addr)?;
+    }
+
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
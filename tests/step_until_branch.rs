@@ -0,0 +1,55 @@
+//! This is a test for single-stepping a child process until it executes a branch.
+
+mod test_utils;
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/branch");
+
+// FIXME: Running this test just for linux because of privileges issue on macOS. Enable for everything after fixing.
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn step_until_branch() -> Result<(), Box<dyn std::error::Error>> {
+    use headcrab::{
+        symbol::{BranchKind, RelocatedDwarf},
+        target::Branch,
+    };
+    use nix::sys::{signal::SIGTRAP, wait::WaitStatus};
+
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(BIN_PATH);
+
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    let symbol = |name| debuginfo.get_symbol_address(name).unwrap();
+    target.run_until(symbol("main"))?;
+
+    // `mov $0, %rcx` isn't a branch, `call function` is.
+    match target.step_until_branch()? {
+        (Some(branch), WaitStatus::Stopped(_, SIGTRAP)) => assert_eq!(
+            branch,
+            Branch {
+                kind: BranchKind::Call,
+                // A `call` with a 32 bit displacement is 5 bytes long.
+                address: symbol("after_call") - 5,
+                target: symbol("function"),
+            }
+        ),
+        result => panic!("Unexpected result of stepping: {:?}", result),
+    }
+
+    match target.step_until_branch()? {
+        (Some(branch), WaitStatus::Stopped(_, SIGTRAP)) => assert_eq!(
+            branch,
+            Branch {
+                kind: BranchKind::Return,
+                address: symbol("function_ret"),
+                target: symbol("after_call"),
+            }
+        ),
+        result => panic!("Unexpected result of stepping: {:?}", result),
+    }
+    assert_eq!(target.read_regs()?.rcx, 1);
+
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
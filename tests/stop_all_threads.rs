@@ -0,0 +1,156 @@
+//! This is a test for stopping and resuming all threads of a child process.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::{
+    symbol::RelocatedDwarf,
+    target::{ThreadState, UnixTarget},
+};
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/multithreaded");
+
+// FIXME: Running this test just for linux because of privileges issue on macOS. Enable for everything after fixing.
+#[cfg(target_os = "linux")]
+#[test]
+fn stop_all_threads() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(BIN_PATH);
+
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    let breakpoint_addr = debuginfo.get_symbol_address("breakpoint").unwrap();
+    target.set_breakpoint(breakpoint_addr)?;
+
+    match target.unpause()? {
+        nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+        status => panic!("Process hasn't stopped on breakpoint: {:?}", status),
+    }
+
+    // Only the main thread is stopped on the breakpoint, the second one is sleeping.
+    let states = |target: &headcrab::target::LinuxTarget| {
+        target
+            .threads()
+            .unwrap()
+            .iter()
+            .map(|thread| (thread.thread_id(), thread.state().unwrap()))
+            .collect::<Vec<_>>()
+    };
+    let main_tid = target.pid().as_raw();
+    let before = states(&target);
+    assert_eq!(before.len(), 2, "Expected 2 threads, found {:?}", before);
+    assert!(before
+        .iter()
+        .any(|&(tid, state)| tid != main_tid && state != Some(ThreadState::TracingStop)));
+
+    target.stop_all_threads()?;
+    for (tid, state) in states(&target) {
+        assert_eq!(state, Some(ThreadState::TracingStop), "Thread {}", tid);
+    }
+    // The registers of every thread can be read now.
+    for (tid, _) in states(&target) {
+        target.read_thread_regs(tid)?;
+    }
+
+    target.resume_all_threads()?;
+    let after = states(&target);
+    assert!(after
+        .iter()
+        .any(|&(tid, state)| tid != main_tid && state != Some(ThreadState::TracingStop)));
+    assert!(after
+        .iter()
+        .any(|&(tid, state)| tid == main_tid && state == Some(ThreadState::TracingStop)));
+
+    assert_eq!(target.rewind_breakpoint()?, Some(breakpoint_addr));
+    target.clear_breakpoint(breakpoint_addr)?;
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
+
+static VFORK_BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/vfork_thread");
+
+// FIXME: Running this test just for linux because of privileges issue on macOS. Enable for everything after fixing.
+#[cfg(target_os = "linux")]
+#[test]
+fn stop_all_threads_keeps_other_stops() -> Result<(), Box<dyn std::error::Error>> {
+    use headcrab::target::{AttachOptions, LinuxTarget};
+    use nix::sys::{
+        ptrace,
+        signal::{self, Signal},
+        wait::{waitpid, WaitPidFlag, WaitStatus},
+    };
+    use nix::unistd::Pid;
+
+    test_utils::ensure_testees();
+
+    let child = std::process::Command::new(VFORK_BIN_PATH).spawn()?;
+    let pid = Pid::from_raw(child.id() as i32);
+    let task_dir = format!("/proc/{}/task", pid);
+    for _ in 0..500 {
+        if std::fs::read_dir(&task_dir)?.count() == 2 {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+
+    let (mut target, _status) = LinuxTarget::attach(
+        pid,
+        AttachOptions {
+            kill_on_exit: true,
+            all_threads: true,
+        },
+    )?;
+    let tid = target.traced_threads()[0];
+    target.cont_all_threads()?;
+
+    // The spawned thread can't take signals while it waits for its `vfork` child, so a
+    // `SIGTRAP`, like the one of a breakpoint, is reported before the `SIGSTOP` sent to stop it.
+    let state = || {
+        target
+            .threads()
+            .unwrap()
+            .iter()
+            .find(|thread| thread.thread_id() == tid)
+            .and_then(|thread| thread.state().unwrap())
+    };
+    for _ in 0..500 {
+        if state() == Some(ThreadState::DiskSleep) {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+    assert_eq!(state(), Some(ThreadState::DiskSleep));
+    let ret = unsafe { libc::syscall(libc::SYS_tgkill, pid.as_raw(), tid, libc::SIGTRAP) };
+    assert_eq!(ret, 0);
+
+    target.stop_all_threads()?;
+    assert_eq!(state(), Some(ThreadState::TracingStop));
+
+    // The thread stays stopped, and its stop is reported now.
+    target.resume_all_threads()?;
+    assert_eq!(state(), Some(ThreadState::TracingStop));
+    assert_eq!(
+        target.wait_any_thread()?,
+        WaitStatus::Stopped(Pid::from_raw(tid), Signal::SIGTRAP)
+    );
+
+    // After continuing it no `SIGSTOP` is left to stop it again.
+    ptrace::cont(Pid::from_raw(tid), None)?;
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    assert_eq!(
+        waitpid(
+            Pid::from_raw(-1),
+            Some(WaitPidFlag::__WALL | WaitPidFlag::WNOHANG)
+        )?,
+        WaitStatus::StillAlive
+    );
+
+    signal::kill(pid, Signal::SIGKILL)?;
+    match target.wait_any_thread()? {
+        WaitStatus::Signaled(exited, Signal::SIGKILL, _) if exited == pid => {}
+        status => panic!("Unexpected status: {:?}", status),
+    }
+
+    Ok(())
+}
@@ -44,3 +44,83 @@ fn syscall() -> Result<(), Box<dyn std::error::Error>> {
         std::fs::read_to_string(format!("/proc/{}/maps", target.pid()))?
     );
 }
+
+#[cfg(target_os = "linux")]
+#[test]
+fn syscall_with_readonly_rip() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let target = test_utils::launch(BIN_PATH);
+
+    // Patching a `syscall` at `rip` would fault now, as its page can't be executed anymore.
+    let page_size = 4096;
+    let rip_page = target.read_regs()?.rip & !(page_size - 1);
+    let res = target.syscall(
+        libc::SYS_mprotect as _,
+        rip_page,
+        page_size,
+        libc::PROT_READ as _,
+        0,
+        0,
+        0,
+    )?;
+    assert_eq!(res, 0);
+
+    let pid = target.syscall(libc::SYS_getpid as _, 0, 0, 0, 0, 0, 0)?;
+    assert_eq!(pid, target.pid().as_raw() as u64);
+
+    let res = target.syscall(
+        libc::SYS_mprotect as _,
+        rip_page,
+        page_size,
+        (libc::PROT_READ | libc::PROT_EXEC) as _,
+        0,
+        0,
+        0,
+    )?;
+    assert_eq!(res, 0);
+
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn munmap() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let target = test_utils::launch(BIN_PATH);
+
+    let page_size = 4096;
+    let addr = target.mmap(
+        std::ptr::null_mut(),
+        page_size,
+        libc::PROT_READ | libc::PROT_WRITE,
+        libc::MAP_ANONYMOUS | libc::MAP_PRIVATE,
+        0,
+        0,
+    )?;
+    let is_mapped = || -> Result<bool, Box<dyn std::error::Error>> {
+        Ok(target
+            .memory_maps()?
+            .iter()
+            .any(|map| map.address.0 == addr))
+    };
+    assert!(is_mapped()?);
+
+    assert!(target.munmap(addr as *mut _, 0).is_err());
+    assert!(target.munmap(addr as *mut _, page_size - 1).is_err());
+    assert!(matches!(
+        target.munmap((addr + 1) as *mut _, page_size),
+        Err(headcrab::Error::NotPageAligned(_))
+    ));
+    assert!(is_mapped()?);
+
+    assert_eq!(target.munmap(addr as *mut _, page_size)?, 0);
+    assert!(!is_mapped()?);
+
+    test_utils::continue_to_end(&target);
+
+    Ok(())
+}
@@ -0,0 +1,22 @@
+#[no_mangle]
+#[inline(never)]
+fn breakpoint() {
+    // The debugger sets a breakpoint here to walk the stack
+    unsafe { std::ptr::read_volatile(&0u8); }
+}
+
+#[no_mangle]
+#[inline(never)]
+fn recurse(depth: usize) {
+    if depth == 0 {
+        breakpoint();
+    } else {
+        recurse(depth - 1);
+    }
+    // Keep the recursive call from becoming a tail call
+    unsafe { std::ptr::read_volatile(&depth); }
+}
+
+pub fn main() {
+    recurse(3);
+}
@@ -0,0 +1,11 @@
+#[no_mangle]
+#[inline(never)]
+fn loop_body(i: usize) {
+    unsafe { std::ptr::read_volatile(&i); }
+}
+
+pub fn main() {
+    for i in 0..10 {
+        loop_body(i);
+    }
+}
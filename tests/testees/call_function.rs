@@ -0,0 +1,19 @@
+#[no_mangle]
+#[inline(never)]
+fn breakpoint() {
+    // The debugger calls functions from here
+    unsafe { std::ptr::read_volatile(&0u8); }
+}
+
+#[no_mangle]
+#[inline(never)]
+extern "C" fn weighted_sum(a: u64, b: u64, c: u64, d: u64, e: u64, f: u64) -> u64 {
+    a + 2 * b + 3 * c + 4 * d + 5 * e + 6 * f
+}
+
+pub fn main() {
+    breakpoint();
+    // Keep `weighted_sum` in the binary
+    let sum: extern "C" fn(u64, u64, u64, u64, u64, u64) -> u64 = weighted_sum;
+    unsafe { std::ptr::read_volatile(&sum); }
+}
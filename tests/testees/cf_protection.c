@@ -0,0 +1,5 @@
+/* Built with `-fcf-protection`, so every function starts with an `endbr64`. */
+
+__attribute__((noinline)) int twice(int x) { return x * 2; }
+
+int main(void) { return twice(21) - 42; }
@@ -0,0 +1,11 @@
+static COMPRESSED_VAR: usize = 42;
+
+#[no_mangle]
+#[inline(never)]
+fn compressed_function() -> usize {
+    unsafe { std::ptr::read_volatile(&COMPRESSED_VAR) }
+}
+
+fn main() {
+    std::process::exit(compressed_function() as i32 - 42);
+}
@@ -0,0 +1,8 @@
+use std::os::unix::process::CommandExt;
+
+pub fn main() {
+    // Replace this process with the `hello` testee next to it
+    let hello = std::env::current_exe().unwrap().with_file_name("hello");
+    let err = std::process::Command::new(hello).exec();
+    panic!("exec failed: {}", err);
+}
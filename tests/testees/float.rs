@@ -0,0 +1,10 @@
+#[no_mangle]
+#[inline(never)]
+fn takes_double(x: f64) {
+    // The debugger inspects XMM0 when this function is entered
+    unsafe { std::ptr::read_volatile(&x); }
+}
+
+pub fn main() {
+    takes_double(1.5);
+}
@@ -0,0 +1,14 @@
+extern "C" {
+    fn fork() -> i32;
+    fn waitpid(pid: i32, status: *mut i32, options: i32) -> i32;
+    fn _exit(status: i32) -> !;
+}
+
+pub fn main() {
+    let pid = unsafe { fork() };
+    if pid == 0 {
+        unsafe { _exit(0) }
+    }
+    let mut status = 0;
+    unsafe { waitpid(pid, &mut status, 0) };
+}
@@ -0,0 +1,17 @@
+#[no_mangle]
+#[inline(never)]
+fn breakpoint() {
+    // The debugger sets a breakpoint here once the second thread is running
+    unsafe { std::ptr::read_volatile(&0u8); }
+}
+
+pub fn main() {
+    let (tx, rx) = std::sync::mpsc::channel();
+    let handle = std::thread::spawn(move || {
+        tx.send(()).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(500));
+    });
+    rx.recv().unwrap();
+    breakpoint();
+    handle.join().unwrap();
+}
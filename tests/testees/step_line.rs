@@ -0,0 +1,27 @@
+#[inline(never)]
+fn black_box<T>(v: T) {
+    unsafe {
+        std::ptr::read_volatile(&v);
+    }
+}
+
+#[no_mangle]
+#[inline(never)]
+fn nested(v: usize) {
+    black_box(v * 2);
+    black_box(v * 3);
+}
+
+#[no_mangle]
+#[inline(never)]
+fn lines() {
+    black_box(1);
+    nested(2);
+    black_box(3);
+    black_box(4);
+}
+
+pub fn main() {
+    lines();
+    black_box(5);
+}
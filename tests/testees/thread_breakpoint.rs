@@ -0,0 +1,20 @@
+#[no_mangle]
+#[inline(never)]
+fn thread_work(i: usize) {
+    // The debugger sets a breakpoint here, it's only called by the spawned threads
+    unsafe { std::ptr::read_volatile(&i); }
+}
+
+pub fn main() {
+    let handles: Vec<_> = (0..3)
+        .map(|i| {
+            std::thread::spawn(move || loop {
+                thread_work(i);
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
@@ -0,0 +1,30 @@
+#include <pthread.h>
+#include <signal.h>
+#include <time.h>
+#include <unistd.h>
+
+// The spawned thread spends most of its time waiting for a `vfork` child, which signals can't
+// interrupt.
+static void *vfork_loop(void *arg) {
+    for (;;) {
+        if (vfork() == 0) {
+            struct timespec delay = { 0, 300 * 1000 * 1000 };
+            nanosleep(&delay, NULL);
+            _exit(0);
+        }
+    }
+    return arg;
+}
+
+int main(void) {
+    // The exiting children would stop the debuggee with a `SIGCHLD` otherwise.
+    sigset_t set;
+    sigemptyset(&set);
+    sigaddset(&set, SIGCHLD);
+    pthread_sigmask(SIG_BLOCK, &set, NULL);
+
+    pthread_t thread;
+    pthread_create(&thread, NULL, vfork_loop, NULL);
+    pthread_join(thread, NULL);
+    return 0;
+}
@@ -0,0 +1,54 @@
+//! This is a simple test to read registers from different threads of a child process.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::{symbol::RelocatedDwarf, target::UnixTarget};
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/multithreaded");
+
+// FIXME: Running this test just for linux because of privileges issue on macOS. Enable for everything after fixing.
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn read_thread_regs() -> Result<(), Box<dyn std::error::Error>> {
+    use nix::sys::wait::{waitpid, WaitPidFlag, WaitStatus};
+
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(BIN_PATH);
+
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    target.set_breakpoint(debuginfo.get_symbol_address("breakpoint").unwrap())?;
+
+    match target.unpause()? {
+        WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+        status => panic!("Process hasn't stopped on breakpoint: {:?}", status),
+    }
+
+    let tids: Vec<_> = target
+        .threads()?
+        .iter()
+        .map(|thread| thread.thread_id())
+        .collect();
+    assert_eq!(tids.len(), 2, "Expected 2 threads, found {:?}", tids);
+
+    // Only the main thread is traced, so attach to the second one.
+    let second_tid = *tids
+        .iter()
+        .find(|&&tid| tid != target.pid().as_raw())
+        .unwrap();
+    let second_pid = nix::unistd::Pid::from_raw(second_tid);
+    nix::sys::ptrace::attach(second_pid)?;
+    waitpid(second_pid, Some(WaitPidFlag::__WALL))?;
+
+    let main_rip = target.read_thread_regs(target.pid().as_raw())?.rip;
+    let second_rip = target.read_thread_regs(second_tid)?.rip;
+    assert_eq!(main_rip, target.read_regs()?.rip);
+    assert_ne!(main_rip, second_rip);
+
+    // The main thread's exit isn't reported while another thread is still traced.
+    nix::sys::ptrace::detach(second_pid, None)?;
+    target.kill()?;
+
+    Ok(())
+}
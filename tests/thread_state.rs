@@ -0,0 +1,51 @@
+//! This is a test for reading the scheduling state of the threads of a child process.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::{
+    symbol::RelocatedDwarf,
+    target::{ThreadState, UnixTarget},
+};
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/multithreaded");
+
+// FIXME: Running this test just for linux because of privileges issue on macOS. Enable for everything after fixing.
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn thread_state() -> Result<(), Box<dyn std::error::Error>> {
+    use nix::sys::wait::WaitStatus;
+
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(BIN_PATH);
+
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    target.set_breakpoint(debuginfo.get_symbol_address("breakpoint").unwrap())?;
+
+    match target.unpause()? {
+        WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+        status => panic!("Process hasn't stopped on breakpoint: {:?}", status),
+    }
+
+    let threads = target.threads()?;
+    assert_eq!(threads.len(), 2);
+    for thread in &threads {
+        assert!(thread.cpu()?.is_some());
+        let state = thread.state()?;
+        if thread.thread_id() == target.pid().as_raw() {
+            assert_eq!(state, Some(ThreadState::TracingStop));
+        } else {
+            // Only the main thread is traced, the second one keeps sleeping.
+            assert!(
+                state == Some(ThreadState::Running) || state == Some(ThreadState::Sleeping),
+                "Unexpected state of the second thread: {:?}",
+                state
+            );
+        }
+    }
+
+    target.kill()?;
+
+    Ok(())
+}
@@ -0,0 +1,40 @@
+//! This is a test for asynchronously waiting for a child process to stop.
+#![cfg(feature = "tokio")]
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::{symbol::RelocatedDwarf, target::UnixTarget};
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/hello");
+
+// FIXME: Running this test just for linux because of privileges issue on macOS. Enable for everything after fixing.
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn wait_async() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let mut target = test_utils::launch(BIN_PATH);
+
+    let debuginfo = RelocatedDwarf::from_maps(&target.memory_maps()?)?;
+    let breakpoint_addr = debuginfo.get_symbol_address("breakpoint").unwrap();
+    target.set_breakpoint(breakpoint_addr)?;
+
+    nix::sys::ptrace::cont(target.pid(), None)?;
+    match target.wait_async().await? {
+        nix::sys::wait::WaitStatus::Stopped(_, nix::sys::signal::SIGTRAP) => {}
+        status => panic!(
+            "Process hasn't stopped on software breakpoint: {:?}",
+            status
+        ),
+    }
+    assert_eq!(target.handle_breakpoint_hit()?, Some(breakpoint_addr));
+
+    nix::sys::ptrace::cont(target.pid(), None)?;
+    assert_eq!(
+        target.wait_async().await?,
+        nix::sys::wait::WaitStatus::Exited(target.pid(), 0)
+    );
+
+    Ok(())
+}